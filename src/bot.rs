@@ -1,21 +1,467 @@
+use crate::executor::block::block_state_property;
+use crate::spec::MAX_LIGHT;
 use anyhow::Result;
+use azalea::ecs::entity::Entity;
+use azalea::entity::{EntityKindComponent, Position};
 use azalea::prelude::*;
-use parking_lot::RwLock;
+use azalea::world::MinecraftEntityId;
+use parking_lot::{Mutex, RwLock};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
 // Constants for connection and timing
-const INIT_WAIT_ATTEMPTS: u32 = 50;
+pub const DEFAULT_CONNECT_TIMEOUT_SECS: u64 = 15;
 const INIT_WAIT_DELAY_MS: u64 = 100;
-const GAME_STATE_WAIT_ATTEMPTS: u32 = 100;
 const WORLD_SYNC_DELAY_MS: u64 = 500;
+const COMMAND_FEEDBACK_WAIT_MS: u64 = 100;
+const BLOCK_DATA_QUERY_TIMEOUT_MS: u64 = 2000;
+const EXECUTE_TEST_TIMEOUT_MS: u64 = 2000;
+/// Default cap on commands sent per second (`--max-commands-per-sec`)
+pub const DEFAULT_MAX_COMMANDS_PER_SEC: u32 = 100;
+/// Wait before reconnecting after a spam kick, so the server's spam counter cools down
+pub const RECONNECT_DELAY_SECS: u64 = 5;
+
+/// How vanilla command errors start
+/// "Could not set the block" / "No blocks were filled" are left out: the server sends them
+/// when the target already matches, which is not an error for a test
+const COMMAND_ERROR_PREFIXES: &[&str] = &[
+    "Unknown block type",
+    "Unknown or incomplete command",
+    "Incorrect argument for command",
+    "Unknown block state property",
+    "Invalid integer",
+    "Invalid long",
+    "Invalid float",
+    "Invalid double",
+    "Invalid boolean",
+    "Expected integer",
+    "Expected long",
+    "Expected float",
+    "Expected double",
+    "Expected boolean",
+    "Expected whitespace to end one argument",
+    "Expected value for property",
+    "Expected closing ] for block state properties",
+    "That position is not loaded",
+    "That position is out of this world",
+    "Too many blocks in the specified area",
+];
+
+/// Parts of vanilla command errors that don't start the message
+const COMMAND_ERROR_FRAGMENTS: &[&str] = &[
+    " does not have property '",
+    " does not accept '",
+    "<--[HERE]",
+];
+
+/// Why connecting to the server failed
+#[derive(Debug, thiserror::Error)]
+pub enum ConnectError {
+    #[error(
+        "Connection to {server} refused: {reason}. Is the server running and is the port correct?"
+    )]
+    Refused { server: String, reason: String },
+    #[error("Could not connect to {server}: {reason}")]
+    Failed { server: String, reason: String },
+    #[error(
+        "Timed out after {secs}s connecting to {server} - no response from the server. Check the address and port"
+    )]
+    Timeout { server: String, secs: u64 },
+    #[error(
+        "Connected to {server} but the bot did not enter the game within {secs}s. \
+         The server may be whitelisted or in online mode (the bot joins offline as 'flintmc_testbot')"
+    )]
+    NotInGame { server: String, secs: u64 },
+    #[error("Lost connection to the server: the bot is no longer in game. Did the server crash?")]
+    Disconnected,
+    #[error("The server disconnected the bot: {reason}")]
+    Kicked { reason: String },
+    #[error(
+        "The server kicked the bot for spamming commands ({reason}). \
+         Lower --max-commands-per-sec, or pass --retries to reconnect and resume"
+    )]
+    SpamKicked { reason: String },
+}
+
+impl ConnectError {
+    /// The error for a bot that left the game, from the disconnect reason if the server sent one
+    fn from_disconnect(reason: Option<String>) -> Self {
+        match reason {
+            Some(reason) if is_spam_kick(&reason) => ConnectError::SpamKicked { reason },
+            Some(reason) => ConnectError::Kicked { reason },
+            None => ConnectError::Disconnected,
+        }
+    }
+}
+
+/// Words in kick messages from vanilla ("Kicked for spamming") and common anti-spam plugins
+const SPAM_KICK_MARKERS: &[&str] = &["spam", "flood", "too many", "too fast"];
+
+/// Whether a disconnect reason means the server kicked the bot for sending too much
+fn is_spam_kick(reason: &str) -> bool {
+    let reason = reason.to_lowercase();
+    SPAM_KICK_MARKERS
+        .iter()
+        .any(|marker| reason.contains(marker))
+}
+
+/// Whether an error (anywhere in its chain) is a spam kick, which a reconnect can recover from
+pub fn is_spam_kick_error(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<ConnectError>(),
+        Some(ConnectError::SpamKicked { .. })
+    )
+}
+
+/// Classify an azalea connection failure
+fn connection_error(server: &str, error: &str) -> ConnectError {
+    let server = server.to_string();
+    let reason = error.to_string();
+    if error.to_lowercase().contains("refused") {
+        ConnectError::Refused { server, reason }
+    } else {
+        ConnectError::Failed { server, reason }
+    }
+}
+
+/// Whether a command can advance game time (`tick step`, `tick sprint`, `tick unfreeze`, ...)
+/// While frozen nothing else moves it, so a game time read before such a command is still valid.
+fn moves_gametime(command: &str) -> bool {
+    let command = command.trim_start_matches('/');
+    command == "tick" || command.starts_with("tick ") || command.contains(" run tick ")
+}
+
+/// Check whether a system chat message is a command error
+fn is_command_error(message: &str) -> bool {
+    let message = message.trim_start();
+    COMMAND_ERROR_PREFIXES
+        .iter()
+        .any(|prefix| message.starts_with(prefix))
+        || COMMAND_ERROR_FRAGMENTS
+            .iter()
+            .any(|fragment| message.contains(fragment))
+}
+
+/// Vanilla biome ids `get_biome` tries in turn
+/// Datapack biomes aren't listed, so a position in one of those reads as unknown.
+const VANILLA_BIOMES: &[&str] = &[
+    "minecraft:plains",
+    "minecraft:sunflower_plains",
+    "minecraft:snowy_plains",
+    "minecraft:ice_spikes",
+    "minecraft:desert",
+    "minecraft:swamp",
+    "minecraft:mangrove_swamp",
+    "minecraft:forest",
+    "minecraft:flower_forest",
+    "minecraft:birch_forest",
+    "minecraft:dark_forest",
+    "minecraft:old_growth_birch_forest",
+    "minecraft:old_growth_pine_taiga",
+    "minecraft:old_growth_spruce_taiga",
+    "minecraft:taiga",
+    "minecraft:snowy_taiga",
+    "minecraft:savanna",
+    "minecraft:savanna_plateau",
+    "minecraft:windswept_hills",
+    "minecraft:windswept_gravelly_hills",
+    "minecraft:windswept_forest",
+    "minecraft:windswept_savanna",
+    "minecraft:jungle",
+    "minecraft:sparse_jungle",
+    "minecraft:bamboo_jungle",
+    "minecraft:badlands",
+    "minecraft:eroded_badlands",
+    "minecraft:wooded_badlands",
+    "minecraft:meadow",
+    "minecraft:cherry_grove",
+    "minecraft:grove",
+    "minecraft:snowy_slopes",
+    "minecraft:frozen_peaks",
+    "minecraft:jagged_peaks",
+    "minecraft:stony_peaks",
+    "minecraft:river",
+    "minecraft:frozen_river",
+    "minecraft:beach",
+    "minecraft:snowy_beach",
+    "minecraft:stony_shore",
+    "minecraft:warm_ocean",
+    "minecraft:lukewarm_ocean",
+    "minecraft:deep_lukewarm_ocean",
+    "minecraft:ocean",
+    "minecraft:deep_ocean",
+    "minecraft:cold_ocean",
+    "minecraft:deep_cold_ocean",
+    "minecraft:frozen_ocean",
+    "minecraft:deep_frozen_ocean",
+    "minecraft:mushroom_fields",
+    "minecraft:dripstone_caves",
+    "minecraft:lush_caves",
+    "minecraft:deep_dark",
+    "minecraft:nether_wastes",
+    "minecraft:warped_forest",
+    "minecraft:crimson_forest",
+    "minecraft:soul_sand_valley",
+    "minecraft:basalt_deltas",
+    "minecraft:the_end",
+    "minecraft:end_highlands",
+    "minecraft:end_midlands",
+    "minecraft:small_end_islands",
+    "minecraft:end_barrens",
+    "minecraft:the_void",
+    // Newest last: older servers reject the id, which only costs a quick error reply
+    "minecraft:pale_garden",
+];
+
+/// Feedback lines that confirm a command ran, keyed by the command's name
+/// "Could not set the block" and "No blocks were filled" are no-op successes (already in place).
+const COMMAND_SUCCESS_FEEDBACK: &[(&str, &[&str])] = &[
+    (
+        "setblock",
+        &["Changed the block", "Could not set the block"],
+    ),
+    ("fill", &["Successfully filled", "No blocks were filled"]),
+    ("clone", &["Successfully cloned"]),
+    ("gamerule", &["Gamerule "]),
+    ("summon", &["Summoned new"]),
+    ("kill", &["Killed "]),
+];
+
+/// What a system message says about a command sent with `send_command_checked`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CommandFeedback {
+    /// The server rejected the command
+    Rejected,
+    /// The command's own success message
+    Succeeded,
+    /// Anything else (a broadcast, a stale line); keep waiting
+    Unrelated,
+}
+
+/// Classify a system message received after sending `command`
+fn command_feedback(command: &str, message: &str) -> CommandFeedback {
+    if is_command_error(message) {
+        return CommandFeedback::Rejected;
+    }
+    let command = command.trim_start_matches('/');
+    // `execute ... run setblock ...` reports as the command it runs
+    let command = command.rsplit_once(" run ").map_or(command, |(_, run)| run);
+    let name = command.split_whitespace().next().unwrap_or("");
+    let message = message.trim_start();
+    let succeeded = COMMAND_SUCCESS_FEEDBACK
+        .iter()
+        .filter(|(command_name, _)| *command_name == name)
+        .any(|(_, prefixes)| prefixes.iter().any(|prefix| message.starts_with(prefix)));
+    if succeeded {
+        CommandFeedback::Succeeded
+    } else {
+        CommandFeedback::Unrelated
+    }
+}
+
+/// `execute` condition for the light level at a block being within min..=max
+fn light_condition(pos: [i32; 3], min: u8, max: u8) -> String {
+    format!(
+        "positioned {} {} {} if predicate {{condition:\"minecraft:location_check\",predicate:{{light:{{light:{{min:{},max:{}}}}}}}}}",
+        pos[0], pos[1], pos[2], min, max
+    )
+}
+
+/// An entity the bot can see
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityInfo {
+    /// Network entity id
+    pub id: i32,
+    /// Entity type as azalea prints it, e.g. "Zombie" or "Item"
+    pub kind: String,
+    pub pos: [f64; 3],
+}
+
+/// Whether a point lies in the blocks spanned by two corners (inclusive, any order)
+fn in_block_region(pos: [f64; 3], region: [[i32; 3]; 2]) -> bool {
+    (0..3).all(|axis| {
+        let min = region[0][axis].min(region[1][axis]) as f64;
+        let max = region[0][axis].max(region[1][axis]) as f64 + 1.0;
+        pos[axis] >= min && pos[axis] < max
+    })
+}
+
+/// Extract the game time from a `time query gametime` reply
+/// Input: "The time is 1,234" (the number may be formatted)
+/// Output: Some(1234)
+fn parse_gametime(message: &str) -> Option<u32> {
+    let time_str = message.split("The time is ").nth(1)?;
+    let time_clean: String = time_str.chars().filter(|c| c.is_ascii_digit()).collect();
+    time_clean.parse().ok()
+}
+
+/// Extract the ms per tick from a sprint completion message
+/// Input: "Sprint completed with 1234 ticks per second, or 0.81 ms per tick"
+/// Output: Some(0.81)
+fn parse_sprint_mspt(message: &str) -> Option<f64> {
+    let ms_part = message.split("or ").nth(1)?;
+    let ms_str = ms_part.split(" ms per tick").next()?;
+    ms_str.trim().parse::<f64>().ok()
+}
+
+/// Kind of chat message, each routed to its own channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatKind {
+    /// Replies to `time query gametime`
+    GameTime,
+    /// `tick sprint` completion messages
+    Sprint,
+    /// Lines a player typed ("<Name> text"): interactive and step commands
+    Player,
+    /// Everything else: command feedback, `/say`, server and plugin messages
+    /// Step commands are read from here too, for the console and `/tellraw`.
+    System,
+}
+
+/// A chat message, parsed as far as its kind allows
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatMessage {
+    /// "The time is N"
+    GameTime(u32),
+    /// "Sprint completed ...", with the server-reported ms per tick if it could be parsed
+    Sprint(Option<f64>),
+    /// A player's line, kept whole (commands are matched against "<Name> text")
+    Player {
+        sender: String,
+        message: String,
+    },
+    System(String),
+}
+
+impl ChatMessage {
+    /// Classify a raw chat line
+    /// Player lines are recognized first, so a player can't fake a gametime or sprint reply.
+    pub fn classify(message: String) -> Self {
+        if let Some(rest) = message.strip_prefix('<')
+            && let Some((sender, _)) = rest.split_once('>')
+        {
+            return ChatMessage::Player {
+                sender: sender.to_string(),
+                message,
+            };
+        }
+        if let Some(time) = parse_gametime(&message) {
+            return ChatMessage::GameTime(time);
+        }
+        if message.contains("Sprint completed") {
+            return ChatMessage::Sprint(parse_sprint_mspt(&message));
+        }
+        ChatMessage::System(message)
+    }
+
+    pub fn kind(&self) -> ChatKind {
+        match self {
+            ChatMessage::GameTime(_) => ChatKind::GameTime,
+            ChatMessage::Sprint(_) => ChatKind::Sprint,
+            ChatMessage::Player { .. } => ChatKind::Player,
+            ChatMessage::System(_) => ChatKind::System,
+        }
+    }
+}
+
+/// One value per chat kind: the handler's senders, or the bot's receivers
+#[derive(Clone)]
+struct ChatChannels<T> {
+    game_time: T,
+    sprint: T,
+    player: T,
+    system: T,
+}
+
+impl<T> ChatChannels<T> {
+    fn get(&self, kind: ChatKind) -> &T {
+        match kind {
+            ChatKind::GameTime => &self.game_time,
+            ChatKind::Sprint => &self.sprint,
+            ChatKind::Player => &self.player,
+            ChatKind::System => &self.system,
+        }
+    }
+
+    fn get_mut(&mut self, kind: ChatKind) -> &mut T {
+        match kind {
+            ChatKind::GameTime => &mut self.game_time,
+            ChatKind::Sprint => &mut self.sprint,
+            ChatKind::Player => &mut self.player,
+            ChatKind::System => &mut self.system,
+        }
+    }
+}
+
+type ChatSenders = ChatChannels<mpsc::UnboundedSender<ChatMessage>>;
+type ChatReceivers = ChatChannels<mpsc::UnboundedReceiver<ChatMessage>>;
+
+/// Chat router: one channel per kind, so a gametime query can't swallow a step command
+fn chat_channels() -> (ChatSenders, ChatReceivers) {
+    let (game_time_tx, game_time_rx) = mpsc::unbounded_channel();
+    let (sprint_tx, sprint_rx) = mpsc::unbounded_channel();
+    let (player_tx, player_rx) = mpsc::unbounded_channel();
+    let (system_tx, system_rx) = mpsc::unbounded_channel();
+    (
+        ChatChannels {
+            game_time: game_time_tx,
+            sprint: sprint_tx,
+            player: player_tx,
+            system: system_tx,
+        },
+        ChatChannels {
+            game_time: game_time_rx,
+            sprint: sprint_rx,
+            player: player_rx,
+            system: system_rx,
+        },
+    )
+}
+
+/// Token bucket for outgoing commands
+/// Allows short bursts of a tenth of a second's worth, then spaces commands out evenly.
+struct RateLimiter {
+    per_sec: f64,
+    burst: f64,
+    /// (available tokens, last refill); tokens go negative while callers wait their turn
+    bucket: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    fn new(per_sec: u32) -> Self {
+        let per_sec = per_sec as f64;
+        let burst = (per_sec / 10.0).max(1.0);
+        Self {
+            per_sec,
+            burst,
+            bucket: Mutex::new((burst, Instant::now())),
+        }
+    }
+
+    /// Take a token for one command, returning how long to wait before sending it
+    fn reserve(&self, now: Instant) -> Duration {
+        let mut bucket = self.bucket.lock();
+        let (tokens, last) = *bucket;
+        let elapsed = now.saturating_duration_since(last).as_secs_f64();
+        let tokens = (tokens + elapsed * self.per_sec).min(self.burst) - 1.0;
+        *bucket = (tokens, now);
+        if tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-tokens / self.per_sec)
+        }
+    }
+}
 
 #[derive(Clone, Component)]
 struct State {
     client_handle: Arc<RwLock<Option<Client>>>,
     in_game: Arc<AtomicBool>,
-    chat_tx: Option<mpsc::UnboundedSender<(Option<String>, String)>>,
+    chat: Option<ChatSenders>,
+    /// Reason the server gave for the last disconnect
+    disconnect_reason: Arc<RwLock<Option<String>>>,
 }
 
 impl Default for State {
@@ -23,7 +469,8 @@ impl Default for State {
         Self {
             client_handle: Arc::new(RwLock::new(None)),
             in_game: Arc::new(AtomicBool::new(false)),
-            chat_tx: None,
+            chat: None,
+            disconnect_reason: Arc::new(RwLock::new(None)),
         }
     }
 }
@@ -32,12 +479,76 @@ impl Default for State {
 pub struct TestBot {
     client: Option<Arc<RwLock<Option<Client>>>>,
     in_game: Option<Arc<AtomicBool>>,
-    chat_rx: Option<mpsc::UnboundedReceiver<(Option<String>, String)>>,
+    chat: Option<ChatReceivers>,
+    disconnect_reason: Option<Arc<RwLock<Option<String>>>>,
+    /// Throttle for `send_command`; None sends immediately
+    rate_limiter: Option<RateLimiter>,
+    /// Commands sent since the last `take_sent_commands`, when recording them (`--trace-log`)
+    sent_commands: Option<Mutex<Vec<(Instant, String)>>>,
+    /// Last game time read from the server, forgotten whenever a command may have moved it
+    known_gametime: Mutex<Option<u32>>,
+    /// How long polling checks keep re-reading (`--assert-timeout`); None polls a fixed count
+    poll_timeout: Option<Duration>,
 }
 
 impl TestBot {
     pub fn new() -> Self {
-        Self::default()
+        Self {
+            rate_limiter: Some(RateLimiter::new(DEFAULT_MAX_COMMANDS_PER_SEC)),
+            ..Default::default()
+        }
+    }
+
+    /// Cap the commands sent per second; 0 removes the cap
+    pub fn set_max_commands_per_sec(&mut self, per_sec: u32) {
+        self.rate_limiter = (per_sec > 0).then(|| RateLimiter::new(per_sec));
+    }
+
+    /// Wall-clock budget for polling checks (`--assert-timeout`); None keeps the fixed retry count
+    pub fn set_poll_timeout(&mut self, timeout: Option<Duration>) {
+        self.poll_timeout = timeout;
+    }
+
+    /// How long a polling check may keep re-reading before it gives up, if limited by time
+    pub fn poll_timeout(&self) -> Option<Duration> {
+        self.poll_timeout
+    }
+
+    /// Keep every sent command with its send time, for `take_sent_commands`
+    pub fn set_record_commands(&mut self, record: bool) {
+        self.sent_commands = record.then(|| Mutex::new(Vec::new()));
+    }
+
+    /// Commands sent since the last call (empty unless recording)
+    pub fn take_sent_commands(&self) -> Vec<(Instant, String)> {
+        self.sent_commands
+            .as_ref()
+            .map(|sent| std::mem::take(&mut *sent.lock()))
+            .unwrap_or_default()
+    }
+
+    /// Whether the bot is still in game (false before connecting and after a disconnect)
+    pub fn is_connected(&self) -> bool {
+        self.in_game
+            .as_ref()
+            .is_some_and(|in_game| in_game.load(Ordering::SeqCst))
+    }
+
+    /// Reason the server gave when it last disconnected the bot, e.g. "Kicked for spamming"
+    pub fn last_disconnect_reason(&self) -> Option<String> {
+        self.disconnect_reason
+            .as_ref()
+            .and_then(|reason| reason.read().clone())
+    }
+
+    /// Fail fast with a connection error if the bot has dropped out of the game
+    /// A kick is reported with the server's reason, and spam kicks get their own error.
+    pub fn ensure_connected(&self) -> Result<()> {
+        if self.is_connected() {
+            Ok(())
+        } else {
+            Err(ConnectError::from_disconnect(self.last_disconnect_reason()).into())
+        }
     }
 
     /// Get a reference to the client, or error if not connected
@@ -48,20 +559,27 @@ impl TestBot {
             .map(|handle| handle.read())
     }
 
-    pub async fn connect(&mut self, server: &str) -> Result<()> {
+    /// Connect to a server and wait until the bot is in game
+    /// `timeout` covers both the initial connection and entering the game state
+    pub async fn connect(&mut self, server: &str, timeout: std::time::Duration) -> Result<()> {
         let account = Account::offline("flintmc_testbot");
 
-        tracing::info!("Connecting to server: {}", server);
+        tracing::info!(server, "Connecting to server");
 
-        // Create chat channel
-        let (chat_tx, chat_rx) = mpsc::unbounded_channel();
+        // Create chat channels
+        let (chat_tx, chat_rx) = chat_channels();
 
         let state = State {
-            chat_tx: Some(chat_tx),
+            chat: Some(chat_tx),
             ..Default::default()
         };
         let client_handle = state.client_handle.clone();
         let in_game = state.in_game.clone();
+        let disconnect_reason = state.disconnect_reason.clone();
+        // Set by the bot thread if azalea exits with an error
+        let exit_error: Arc<RwLock<Option<String>>> = Arc::new(RwLock::new(None));
+        let thread_exit_error = exit_error.clone();
+        let thread_in_game = in_game.clone();
 
         // Spawn the bot in a background thread with LocalSet (required by new azalea version)
         let server_owned = server.to_string();
@@ -84,23 +602,16 @@ impl TestBot {
                             state.in_game.store(true, Ordering::SeqCst);
                             tracing::info!("Bot in game state");
                         }
+                        Event::Disconnect(reason) => {
+                            state.in_game.store(false, Ordering::SeqCst);
+                            tracing::warn!(reason = ?reason, "Bot disconnected");
+                            *state.disconnect_reason.write() =
+                                reason.map(|reason| reason.to_string());
+                        }
                         Event::Chat(m) => {
-                            // Extract the message content
-                            let message = m.message().to_string();
-                            // Try to get sender name (best effort)
-                            // Fallback: parse "<Name>"
-                            let sender = if message.starts_with('<') {
-                                if let Some(end) = message.find('>') {
-                                    Some(message[1..end].to_string())
-                                } else {
-                                    None
-                                }
-                            } else {
-                                None
-                            };
-
-                            if let Some(ref tx) = state.chat_tx {
-                                let _ = tx.send((sender, message));
+                            let message = ChatMessage::classify(m.message().to_string());
+                            if let Some(ref chat) = state.chat {
+                                let _ = chat.get(message.kind()).send(message);
                             }
                         }
                         _ => {}
@@ -113,42 +624,54 @@ impl TestBot {
                     .set_state(state)
                     .start(account, server_owned.as_str())
                     .await;
+                thread_in_game.store(false, Ordering::SeqCst);
 
                 if let AppExit::Error(e) = result {
-                    tracing::error!("Bot connection error: {}", e);
+                    tracing::error!(error = %e, "Bot connection error");
+                    *thread_exit_error.write() = Some(e.to_string());
                 }
             });
         });
 
+        let deadline = tokio::time::Instant::now() + timeout;
+
         // Wait for client to initialize
-        for _ in 0..INIT_WAIT_ATTEMPTS {
-            tokio::time::sleep(tokio::time::Duration::from_millis(INIT_WAIT_DELAY_MS)).await;
-            if client_handle.read().is_some() {
-                break;
+        while client_handle.read().is_none() {
+            if let Some(e) = exit_error.read().clone() {
+                return Err(connection_error(server, &e).into());
             }
-        }
-
-        if client_handle.read().is_none() {
-            anyhow::bail!("Failed to initialize bot connection");
+            if tokio::time::Instant::now() >= deadline {
+                return Err(ConnectError::Timeout {
+                    server: server.to_string(),
+                    secs: timeout.as_secs(),
+                }
+                .into());
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(INIT_WAIT_DELAY_MS)).await;
         }
 
         // Wait for bot to be in game state
         tracing::info!("Waiting for bot to enter game state...");
-        for _ in 0..GAME_STATE_WAIT_ATTEMPTS {
-            tokio::time::sleep(tokio::time::Duration::from_millis(INIT_WAIT_DELAY_MS)).await;
-            if in_game.load(Ordering::SeqCst) {
-                break;
+        while !in_game.load(Ordering::SeqCst) {
+            if let Some(e) = exit_error.read().clone() {
+                return Err(connection_error(server, &e).into());
             }
-        }
-
-        if !in_game.load(Ordering::SeqCst) {
-            anyhow::bail!("Bot failed to enter game state within timeout");
+            if tokio::time::Instant::now() >= deadline {
+                return Err(ConnectError::NotInGame {
+                    server: server.to_string(),
+                    secs: timeout.as_secs(),
+                }
+                .into());
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(INIT_WAIT_DELAY_MS)).await;
         }
 
         self.client = Some(client_handle);
         self.in_game = Some(in_game);
-        self.chat_rx = Some(chat_rx);
-        tracing::info!("Connected successfully and in game state");
+        self.chat = Some(chat_rx);
+        *self.known_gametime.lock() = None;
+        self.disconnect_reason = Some(disconnect_reason);
+        tracing::info!(server, "Connected successfully and in game state");
 
         // Give a small amount of extra time for world data to sync
         tokio::time::sleep(tokio::time::Duration::from_millis(WORLD_SYNC_DELAY_MS)).await;
@@ -156,13 +679,14 @@ impl TestBot {
         Ok(())
     }
 
-    /// Wait for a chat message with timeout
+    /// Wait for a chat message of the given kind with timeout
     pub async fn recv_chat_timeout(
         &mut self,
+        kind: ChatKind,
         timeout: std::time::Duration,
-    ) -> Option<(Option<String>, String)> {
-        if let Some(ref mut rx) = self.chat_rx {
-            tokio::time::timeout(timeout, rx.recv())
+    ) -> Option<ChatMessage> {
+        if let Some(ref mut chat) = self.chat {
+            tokio::time::timeout(timeout, chat.get_mut(kind).recv())
                 .await
                 .ok()
                 .flatten()
@@ -171,7 +695,22 @@ impl TestBot {
         }
     }
 
+    /// Drop queued messages of the given kind
+    pub fn clear_chat(&mut self, kind: ChatKind) {
+        if let Some(ref mut chat) = self.chat {
+            while chat.get_mut(kind).try_recv().is_ok() {}
+        }
+    }
+
+    /// Send a command, waiting first if it would exceed the command rate limit
     pub async fn send_command(&self, command: &str) -> Result<()> {
+        if let Some(ref limiter) = self.rate_limiter {
+            let wait = limiter.reserve(Instant::now());
+            if !wait.is_zero() {
+                tokio::time::sleep(wait).await;
+            }
+        }
+
         let client_guard = self.get_client()?;
         let client = client_guard
             .as_ref()
@@ -184,10 +723,166 @@ impl TestBot {
             format!("/{}", command)
         };
         tracing::debug!("Sending command: {}", command_with_slash);
+        if moves_gametime(command) {
+            *self.known_gametime.lock() = None;
+        }
         client.chat(&command_with_slash);
+        if let Some(ref sent) = self.sent_commands {
+            sent.lock().push((Instant::now(), command.to_string()));
+        }
         Ok(())
     }
 
+    /// Game time as last read, if no command since could have changed it
+    pub fn known_gametime(&self) -> Option<u32> {
+        *self.known_gametime.lock()
+    }
+
+    /// Remember a game time just read from the server
+    pub fn set_known_gametime(&self, time: u32) {
+        *self.known_gametime.lock() = Some(time);
+    }
+
+    /// Send a command and wait briefly for the server to report it as failed
+    /// Catches typos like unknown block ids that would otherwise silently do nothing.
+    /// Returns early on the command's own success line ("Changed the block", "Successfully filled");
+    /// any other message keeps it waiting, so only a silent server costs the full wait.
+    pub async fn send_command_checked(&mut self, command: &str) -> Result<()> {
+        // Drop stale messages so only feedback for this command is considered
+        self.clear_chat(ChatKind::System);
+
+        self.send_command(command).await?;
+
+        let deadline = tokio::time::Instant::now()
+            + std::time::Duration::from_millis(COMMAND_FEEDBACK_WAIT_MS);
+        loop {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            let Some(message) = self.recv_chat_timeout(ChatKind::System, remaining).await else {
+                break;
+            };
+            if let ChatMessage::System(message) = message {
+                match command_feedback(command, &message) {
+                    CommandFeedback::Rejected => {
+                        anyhow::bail!("Server rejected '{}': {}", command, message)
+                    }
+                    CommandFeedback::Succeeded => break,
+                    // Not about this command (or a command with no known success line): the
+                    // error could still follow, so keep reading until the deadline
+                    CommandFeedback::Unrelated => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Read block entity NBT with `/data get block`, returning the SNBT text
+    /// `path` selects a tag (e.g. "Items"); None if the block entity has no such tag.
+    pub async fn query_block_data(&mut self, pos: [i32; 3], path: &str) -> Result<Option<String>> {
+        self.clear_chat(ChatKind::System);
+        let command = format!("data get block {} {} {} {}", pos[0], pos[1], pos[2], path);
+        self.send_command(&command).await?;
+
+        let deadline = tokio::time::Instant::now()
+            + std::time::Duration::from_millis(BLOCK_DATA_QUERY_TIMEOUT_MS);
+        loop {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if remaining.is_zero() {
+                anyhow::bail!("No reply to '{}'", command);
+            }
+            let Some(ChatMessage::System(message)) =
+                self.recv_chat_timeout(ChatKind::System, remaining).await
+            else {
+                continue;
+            };
+            if let Some((_, data)) = message.split_once("has the following block data: ") {
+                return Ok(Some(data.trim().to_string()));
+            }
+            if message.contains("Found no elements matching") {
+                return Ok(None);
+            }
+            if message.contains("is not a block entity") || is_command_error(&message) {
+                anyhow::bail!("Server rejected '{}': {}", command, message);
+            }
+        }
+    }
+
+    /// Run `execute <condition>` as a test and read the server's pass/fail reply
+    /// Needs command feedback, like `query_block_data`.
+    pub async fn execute_test(&mut self, condition: &str) -> Result<bool> {
+        self.clear_chat(ChatKind::System);
+        let command = format!("execute {}", condition);
+        self.send_command(&command).await?;
+
+        let deadline =
+            tokio::time::Instant::now() + std::time::Duration::from_millis(EXECUTE_TEST_TIMEOUT_MS);
+        loop {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if remaining.is_zero() {
+                anyhow::bail!("No reply to '{}'", command);
+            }
+            let Some(ChatMessage::System(message)) =
+                self.recv_chat_timeout(ChatKind::System, remaining).await
+            else {
+                continue;
+            };
+            if message.starts_with("Test passed") {
+                return Ok(true);
+            }
+            if message.starts_with("Test failed") {
+                return Ok(false);
+            }
+            if is_command_error(&message) {
+                anyhow::bail!("Server rejected '{}': {}", command, message);
+            }
+        }
+    }
+
+    /// Whether the light level at a position is within min..=max
+    /// Asked of the server with a `location_check` predicate: azalea keeps no light data.
+    pub async fn light_in_range(&mut self, pos: [i32; 3], min: u8, max: u8) -> Result<bool> {
+        self.execute_test(&light_condition(pos, min, max)).await
+    }
+
+    /// The light level at a position (0-15), narrowed down with range tests
+    pub async fn get_light(&mut self, pos: [i32; 3]) -> Result<u8> {
+        let (mut low, mut high) = (0, MAX_LIGHT);
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if self.light_in_range(pos, mid, MAX_LIGHT).await? {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        Ok(low)
+    }
+
+    /// Whether the biome at a position is `biome`: an id, or a `#tag`
+    /// The server only answers yes or no; there is no command that names the biome at a position.
+    pub async fn is_biome(&mut self, pos: [i32; 3], biome: &str) -> Result<bool> {
+        self.execute_test(&format!(
+            "if biome {} {} {} {}",
+            pos[0], pos[1], pos[2], biome
+        ))
+        .await
+    }
+
+    /// The vanilla biome at a position, found by asking `is_biome` about each in turn
+    /// Costs up to one command per vanilla biome, so it is only used to explain a failure.
+    /// None for a datapack biome, or if the server stops answering.
+    pub async fn get_biome(&mut self, pos: [i32; 3]) -> Option<&'static str> {
+        for &biome in VANILLA_BIOMES {
+            // An id this server doesn't know is rejected; that just means it isn't this one
+            if let Ok(true) = self.is_biome(pos, biome).await {
+                return Some(biome);
+            }
+        }
+        None
+    }
+
     pub async fn get_block(&self, pos: [i32; 3]) -> Result<Option<String>> {
         let client_guard = self.get_client()?;
         let client = client_guard
@@ -208,6 +903,43 @@ impl TestBot {
         }
     }
 
+    /// Get a single block state property (e.g. "powered") at a position
+    /// Returns None if the chunk isn't loaded or the block has no such property
+    #[allow(dead_code)] // bot API for property-level polling, not used by the executor yet
+    pub async fn get_block_state_property(
+        &self,
+        pos: [i32; 3],
+        property: &str,
+    ) -> Result<Option<String>> {
+        let state = self.get_block(pos).await?;
+        Ok(state.and_then(|state| block_state_property(&state, property)))
+    }
+
+    /// Entities (other than the bot) inside a world-space block region
+    /// Entities the server hasn't synced to the bot yet are missing, see `poll_entities_with_retry`.
+    pub async fn get_entities(&self, region: [[i32; 3]; 2]) -> Result<Vec<EntityInfo>> {
+        let client_guard = self.get_client()?;
+        let client = client_guard
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Bot not initialized"))?;
+
+        let mut ecs = client.ecs.lock();
+        let mut query =
+            ecs.query::<(Entity, &MinecraftEntityId, &EntityKindComponent, &Position)>();
+        let mut entities: Vec<EntityInfo> = query
+            .iter(&ecs)
+            .filter(|(entity, ..)| *entity != client.entity)
+            .map(|(_, id, kind, pos)| EntityInfo {
+                id: id.0,
+                kind: format!("{:?}", **kind),
+                pos: [pos.x, pos.y, pos.z],
+            })
+            .filter(|info| in_block_region(info.pos, region))
+            .collect();
+        entities.sort_by_key(|info| info.id);
+        Ok(entities)
+    }
+
     /// Get the bot's current position
     pub fn get_position(&self) -> Result<[i32; 3]> {
         let client_guard = self.get_client()?;
@@ -219,3 +951,187 @@ impl TestBot {
         Ok([pos.x as i32, pos.y as i32, pos.z as i32])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sprint_mspt() {
+        assert_eq!(
+            parse_sprint_mspt("Sprint completed with 1234 ticks per second, or 0.81 ms per tick"),
+            Some(0.81)
+        );
+        assert_eq!(parse_sprint_mspt("Sprint completed"), None);
+    }
+
+    #[test]
+    fn test_parse_gametime() {
+        assert_eq!(parse_gametime("The time is 1234"), Some(1234));
+        assert_eq!(parse_gametime("The time is 1,234"), Some(1234));
+        assert_eq!(parse_gametime("Set the time to 1234"), None);
+    }
+
+    #[test]
+    fn test_connection_error() {
+        let refused = connection_error("localhost:25566", "Connection refused (os error 111)");
+        assert!(matches!(refused, ConnectError::Refused { .. }));
+        assert!(refused.to_string().contains("Is the server running"));
+        let other = connection_error("localhost:25565", "Disconnected: You are not whitelisted");
+        assert!(matches!(other, ConnectError::Failed { .. }));
+        assert!(other.to_string().contains("not whitelisted"));
+    }
+
+    #[test]
+    fn test_disconnect_errors() {
+        let spam = ConnectError::from_disconnect(Some("Kicked for spamming".to_string()));
+        assert!(matches!(spam, ConnectError::SpamKicked { .. }));
+        assert!(spam.to_string().contains("--max-commands-per-sec"));
+        assert!(is_spam_kick_error(
+            &anyhow::Error::from(spam).context("Tick step failed")
+        ));
+        assert!(matches!(
+            ConnectError::from_disconnect(Some("Server closed".to_string())),
+            ConnectError::Kicked { .. }
+        ));
+        assert!(matches!(
+            ConnectError::from_disconnect(None),
+            ConnectError::Disconnected
+        ));
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let limiter = RateLimiter::new(100);
+        let start = Instant::now();
+        // A burst of 10 goes out at once, then one command every 10ms
+        for _ in 0..10 {
+            assert_eq!(limiter.reserve(start), Duration::ZERO);
+        }
+        assert_eq!(limiter.reserve(start), Duration::from_millis(10));
+        assert_eq!(limiter.reserve(start), Duration::from_millis(20));
+        // Idle time refills the bucket, up to the burst
+        let later = start + Duration::from_secs(1);
+        for _ in 0..10 {
+            assert_eq!(limiter.reserve(later), Duration::ZERO);
+        }
+        assert!(!limiter.reserve(later).is_zero());
+    }
+
+    #[test]
+    fn test_light_condition() {
+        assert_eq!(
+            light_condition([1, 64, -2], 0, 7),
+            "positioned 1 64 -2 if predicate {condition:\"minecraft:location_check\",predicate:{light:{light:{min:0,max:7}}}}"
+        );
+    }
+
+    #[test]
+    fn test_in_block_region() {
+        let region = [[2, 64, 2], [0, 66, 0]];
+        assert!(in_block_region([0.0, 64.0, 0.0], region));
+        assert!(in_block_region([2.9, 66.5, 2.9], region));
+        assert!(!in_block_region([3.0, 65.0, 1.0], region));
+        assert!(!in_block_region([1.0, 63.9, 1.0], region));
+    }
+
+    #[test]
+    fn test_classify_chat() {
+        let classify = |message: &str| ChatMessage::classify(message.to_string());
+        assert_eq!(classify("The time is 1234"), ChatMessage::GameTime(1234));
+        assert_eq!(
+            classify("Sprint completed with 1234 ticks per second, or 0.81 ms per tick"),
+            ChatMessage::Sprint(Some(0.81))
+        );
+        assert_eq!(
+            classify("<Steve> s"),
+            ChatMessage::Player {
+                sender: "Steve".to_string(),
+                message: "<Steve> s".to_string()
+            }
+        );
+        // A player can't pose as a server reply
+        assert_eq!(classify("<Steve> The time is 5").kind(), ChatKind::Player);
+        assert_eq!(
+            classify("[flintmc_testbot] Waiting for step/continue").kind(),
+            ChatKind::System
+        );
+    }
+
+    #[test]
+    fn test_is_command_error() {
+        assert!(is_command_error("Unknown block type 'minecraft:stoen'"));
+        assert!(is_command_error(
+            "Unknown or incomplete command, see below for error"
+        ));
+        assert!(is_command_error("...ck 0 64 0 minecraft:stoen<--[HERE]"));
+        assert!(!is_command_error("Could not set the block"));
+        assert!(!is_command_error("Changed the block at 0, 64, 0"));
+        assert!(!is_command_error("<Steve> !run fence_test"));
+        assert!(is_command_error("Invalid integer 'x'"));
+        assert!(is_command_error(
+            "Expected whitespace to end one argument, but found trailing data"
+        ));
+        assert!(is_command_error(
+            "Block minecraft:oak_fence does not have property 'facing'"
+        ));
+        assert!(is_command_error("That position is not loaded"));
+        // Everyday chat that merely contains the words
+        assert!(!is_command_error("Invalid move, try again"));
+        assert!(!is_command_error("[Server] Expected restart in 5 minutes"));
+        assert!(!is_command_error("<Steve> Invalid integer 'x'"));
+    }
+
+    #[test]
+    fn test_command_feedback() {
+        let setblock = "setblock 0 64 0 minecraft:stone";
+        assert_eq!(
+            command_feedback(setblock, "Changed the block at 0, 64, 0"),
+            CommandFeedback::Succeeded
+        );
+        assert_eq!(
+            command_feedback(setblock, "Could not set the block"),
+            CommandFeedback::Succeeded
+        );
+        assert_eq!(
+            command_feedback(setblock, "Unknown block type 'minecraft:stoen'"),
+            CommandFeedback::Rejected
+        );
+        // Another command's feedback or a broadcast doesn't end the wait
+        assert_eq!(
+            command_feedback(setblock, "Successfully filled 8 block(s)"),
+            CommandFeedback::Unrelated
+        );
+        assert_eq!(
+            command_feedback(setblock, "[Server] Expected restart in 5 minutes"),
+            CommandFeedback::Unrelated
+        );
+        assert_eq!(
+            command_feedback(
+                "fill 0 64 0 1 65 1 minecraft:stone",
+                "Successfully filled 8 block(s)"
+            ),
+            CommandFeedback::Succeeded
+        );
+        assert_eq!(
+            command_feedback(
+                "execute positioned 0 64 0 run setblock ~ ~ ~ minecraft:stone",
+                "Changed the block at 0, 64, 0"
+            ),
+            CommandFeedback::Succeeded
+        );
+        assert_eq!(
+            command_feedback("say hi", "Changed the block at 0, 64, 0"),
+            CommandFeedback::Unrelated
+        );
+    }
+
+    #[test]
+    fn test_moves_gametime() {
+        assert!(moves_gametime("tick step"));
+        assert!(moves_gametime("/tick sprint 19"));
+        assert!(moves_gametime("execute as @p run tick unfreeze"));
+        assert!(!moves_gametime("time query gametime"));
+        assert!(!moves_gametime("scoreboard players set ticks flint 0"));
+    }
+}
@@ -0,0 +1,165 @@
+//! Result cache - remembers each test file's mtime and last result for `--only-changed`
+
+use crate::spec::LoadedTest;
+use anyhow::{Context, Result};
+use flint_core::results::TestResult;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Manifest file name, kept in the test root
+pub const CACHE_FILE: &str = ".flint-cache";
+
+/// Where the manifest for a run over `root` lives: inside a test directory,
+/// or next to a single test file
+pub fn cache_path(root: &Path) -> PathBuf {
+    if root.is_dir() {
+        root.join(CACHE_FILE)
+    } else {
+        root.parent().unwrap_or(Path::new("")).join(CACHE_FILE)
+    }
+}
+
+/// Last known state of one test file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CacheEntry {
+    mtime_ms: u64,
+    test_name: String,
+    success: bool,
+    total_ticks: u32,
+    execution_time_ms: u64,
+}
+
+impl CacheEntry {
+    /// Rebuild the cached result for the final summary
+    fn to_result(&self) -> TestResult {
+        let mut result = if self.success {
+            TestResult::new(self.test_name.clone())
+        } else {
+            TestResult::new(self.test_name.clone())
+                .with_failure_reason("failed in a previous run (cached)".to_string())
+        };
+        result.total_ticks = self.total_ticks;
+        result.execution_time_ms = self.execution_time_ms;
+        result
+    }
+}
+
+/// Test path -> last run mtime and result
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    entries: BTreeMap<PathBuf, CacheEntry>,
+}
+
+/// Modification time of a file in milliseconds since the epoch
+fn mtime_ms(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
+impl Manifest {
+    /// Load the manifest, starting fresh if it is missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        let Ok(contents) = std::fs::read_to_string(path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable cache {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write cache {}", path.display()))
+    }
+
+    /// Cached entry for a file, if the file hasn't changed since it was recorded
+    fn fresh_entry(&self, file: &Path) -> Option<&CacheEntry> {
+        let entry = self.entries.get(file)?;
+        (mtime_ms(file)? == entry.mtime_ms).then_some(entry)
+    }
+
+    /// Split tests into changed ones (to run) and cached results for the rest
+    pub fn split_changed(&self, tests: Vec<LoadedTest>) -> (Vec<LoadedTest>, Vec<TestResult>) {
+        let mut changed = Vec::new();
+        let mut cached = Vec::new();
+        for test in tests {
+            match self.fresh_entry(&test.path) {
+                Some(entry) => cached.push(entry.to_result()),
+                None => changed.push(test),
+            }
+        }
+        (changed, cached)
+    }
+
+    /// Remember a test file's current mtime and the result it just produced
+    pub fn record(&mut self, file: &Path, result: &TestResult) {
+        let Some(mtime_ms) = mtime_ms(file) else {
+            return;
+        };
+        self.entries.insert(
+            file.to_path_buf(),
+            CacheEntry {
+                mtime_ms,
+                test_name: result.test_name.clone(),
+                success: result.success,
+                total_ticks: result.total_ticks,
+                execution_time_ms: result.execution_time_ms,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_changed() {
+        let dir = std::env::temp_dir().join(format!("flint-cache-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let unchanged = dir.join("unchanged.json");
+        let edited = dir.join("edited.json");
+        let new = dir.join("new.json");
+        for file in [&unchanged, &edited, &new] {
+            std::fs::write(file, "{}").unwrap();
+        }
+
+        let mut manifest = Manifest::default();
+        manifest.record(&unchanged, &TestResult::new("unchanged".to_string()));
+        manifest.record(&edited, &TestResult::new("edited".to_string()));
+        manifest.entries.get_mut(&edited).unwrap().mtime_ms -= 1;
+
+        let tests = [&unchanged, &edited, &new]
+            .into_iter()
+            .map(|file| LoadedTest::load(file.clone()))
+            .collect();
+        let (changed, cached) = manifest.split_changed(tests);
+        let changed: Vec<PathBuf> = changed.into_iter().map(|test| test.path).collect();
+        assert_eq!(changed, vec![edited, new]);
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0].test_name, "unchanged");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_path_follows_test_root() {
+        let dir = std::env::temp_dir().join(format!("flint-cache-root-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("lever.json");
+        std::fs::write(&file, "{}").unwrap();
+
+        assert_eq!(cache_path(&dir), dir.join(CACHE_FILE));
+        assert_eq!(cache_path(&file), dir.join(CACHE_FILE));
+        assert_eq!(
+            cache_path(Path::new("lever.json")),
+            PathBuf::from(CACHE_FILE)
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,112 @@
+//! Git-based selection (`--changed-since <ref>`) - run only the tests whose files differ from a ref,
+//! plus the tests that depend on them
+
+use crate::spec::LoadedTest;
+use anyhow::{Context, Result, bail};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Run git with `args` in the working directory, returning stdout
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Files changed since `git_ref` (committed, staged, unstaged or untracked), as canonical paths
+/// Returns None outside a git work tree, so the caller can fall back to the whole suite.
+pub fn changed_since(git_ref: &str) -> Result<Option<HashSet<PathBuf>>> {
+    let Ok(root) = git(&["rev-parse", "--show-toplevel"]) else {
+        return Ok(None);
+    };
+    let root = PathBuf::from(root.trim());
+    let diff = git(&["diff", "--name-only", git_ref, "--"])
+        .with_context(|| format!("Can't diff against '{}'", git_ref))?;
+    let untracked = git(&["ls-files", "--others", "--exclude-standard", "--full-name"])?;
+    Ok(Some(
+        diff.lines()
+            .chain(untracked.lines())
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| root.join(line).canonicalize().ok())
+            .collect(),
+    ))
+}
+
+/// Names in `selected` plus every test that (transitively) depends on one of them
+/// Input: (test_name, dependencies) per test
+fn with_dependents(selected: HashSet<String>, tests: &[(String, Vec<String>)]) -> HashSet<String> {
+    let mut selected = selected;
+    loop {
+        let dependents: Vec<&String> = tests
+            .iter()
+            .filter(|(name, deps)| {
+                !selected.contains(name) && deps.iter().any(|dep| selected.contains(dep))
+            })
+            .map(|(name, _)| name)
+            .collect();
+        if dependents.is_empty() {
+            return selected;
+        }
+        selected.extend(dependents.into_iter().cloned());
+    }
+}
+
+/// Keep the tests whose files are in `changed`, and those that depend on a kept test
+/// Files that don't load are kept if they changed, so the run reports their error.
+pub fn select_changed(tests: Vec<LoadedTest>, changed: &HashSet<PathBuf>) -> Vec<LoadedTest> {
+    let is_changed = |file: &Path| {
+        file.canonicalize()
+            .is_ok_and(|path| changed.contains(&path))
+    };
+    let names_and_deps: Vec<(String, Vec<String>)> = tests
+        .iter()
+        .filter_map(|test| test.loaded.as_ref().ok())
+        .map(|(test, _)| (test.name.clone(), test.dependencies.clone()))
+        .collect();
+    let directly: HashSet<String> = tests
+        .iter()
+        .filter(|test| is_changed(&test.path))
+        .filter_map(|test| test.loaded.as_ref().ok())
+        .map(|(test, _)| test.name.clone())
+        .collect();
+    let selected = with_dependents(directly, &names_and_deps);
+
+    tests
+        .into_iter()
+        .filter(|test| match &test.loaded {
+            Ok((spec, _)) => selected.contains(&spec.name),
+            Err(_) => is_changed(&test.path),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_dependents() {
+        let tests = vec![
+            ("furnace".to_string(), vec![]),
+            ("smelting".to_string(), vec!["furnace".to_string()]),
+            ("autosmelter".to_string(), vec!["smelting".to_string()]),
+            ("lamp".to_string(), vec![]),
+        ];
+        let selected = with_dependents(["furnace".to_string()].into_iter().collect(), &tests);
+        let mut selected: Vec<String> = selected.into_iter().collect();
+        selected.sort();
+        assert_eq!(selected, vec!["autosmelter", "furnace", "smelting"]);
+
+        assert!(with_dependents(HashSet::new(), &tests).is_empty());
+    }
+}
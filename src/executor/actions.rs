@@ -1,313 +1,1404 @@
 //! Test action execution - block placement, assertions, etc.
 
-use crate::bot::TestBot;
-use anyhow::Result;
-use colored::Colorize;
-use flint_core::results::{ActionOutcome, AssertFailure, InfoType};
-use flint_core::test_spec::{ActionType, TimelineEntry};
+use crate::bot::{EntityInfo, TestBot};
+use crate::spec::{
+    BiomeCheck, CheckFlags, ContainerCheck, Dimension, EntityCheck, ExtraCheck, FillCheck,
+    LightCheck, MAX_LIGHT, StructureCheck,
+};
+use anyhow::{Context, Result, bail};
+use flint_core::results::{AssertFailure, InfoType};
+use flint_core::test_spec::{ActionType, Block, BlockCheck, TimelineEntry};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
-use super::block::{block_matches, extract_block_id};
+use super::block::{
+    block_matches, block_properties, extract_all_properties, extract_block_id,
+    normalize_block_name, normalize_property_value,
+};
+use super::snbt;
 
 // Constants for action timing
 pub const BLOCK_POLL_ATTEMPTS: u32 = 10;
 pub const BLOCK_POLL_DELAY_MS: u64 = 50;
-pub const PLACE_EACH_DELAY_MS: u64 = 10;
+
+/// Retry pacing shared by polling checks: BLOCK_POLL_ATTEMPTS reads BLOCK_POLL_DELAY_MS apart,
+/// or with `--assert-timeout` as many reads as fit before a wall-clock deadline
+struct Poll {
+    deadline: Option<Instant>,
+    attempt: u32,
+}
+
+impl Poll {
+    fn start(bot: &TestBot) -> Self {
+        Self {
+            deadline: bot.poll_timeout().map(|timeout| Instant::now() + timeout),
+            attempt: 0,
+        }
+    }
+
+    /// After a read that didn't satisfy the check: wait for the next read, or return false
+    /// once the budget is spent. With a deadline, the last read happens right at it.
+    async fn retry(&mut self) -> bool {
+        self.attempt += 1;
+        let delay = Duration::from_millis(BLOCK_POLL_DELAY_MS);
+        let wait = match self.deadline {
+            Some(deadline) => deadline
+                .checked_duration_since(Instant::now())
+                .filter(|left| !left.is_zero())
+                .map(|left| left.min(delay)),
+            None => (self.attempt < BLOCK_POLL_ATTEMPTS).then_some(delay),
+        };
+        match wait {
+            Some(wait) => {
+                tokio::time::sleep(wait).await;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// What running one timeline entry produced; like flint-core's ActionOutcome, but a failed
+/// assertion carries every check that failed
+#[derive(Debug)]
+pub enum Outcome {
+    Action,
+    AssertPassed,
+    /// Failed checks in the order they were listed, never empty
+    AssertFailed(Vec<AssertFailure>),
+}
+
+impl Outcome {
+    /// Passed if nothing failed
+    pub fn from_failures(failures: Vec<AssertFailure>) -> Self {
+        if failures.is_empty() {
+            Outcome::AssertPassed
+        } else {
+            Outcome::AssertFailed(failures)
+        }
+    }
+}
 
 /// Apply offset to a position
 pub fn apply_offset(pos: [i32; 3], offset: [i32; 3]) -> [i32; 3] {
     [pos[0] + offset[0], pos[1] + offset[1], pos[2] + offset[2]]
 }
 
+/// `setblock` command for a world position
+pub fn setblock_command(world_pos: [i32; 3], block_spec: &str) -> String {
+    format!(
+        "setblock {} {} {} {}",
+        world_pos[0], world_pos[1], world_pos[2], block_spec
+    )
+}
+
+/// `fill` command for a world region
+pub fn fill_command(world_min: [i32; 3], world_max: [i32; 3], block_spec: &str) -> String {
+    format!(
+        "fill {} {} {} {} {} {} {}",
+        world_min[0],
+        world_min[1],
+        world_min[2],
+        world_max[0],
+        world_max[1],
+        world_max[2],
+        block_spec
+    )
+}
+
+/// Most blocks one `fill` may change (the server's default `commandModificationBlockLimit`)
+pub const MAX_FILL_VOLUME: i64 = 32_768;
+
+/// Split a region into boxes of at most MAX_FILL_VOLUME blocks, as normalized (min, max) corners
+/// Boxes are whole x-z layers where those fit, otherwise rows along x, otherwise pieces of a row.
+pub fn split_fill_region(corner_a: [i32; 3], corner_b: [i32; 3]) -> Vec<([i32; 3], [i32; 3])> {
+    let min = [0, 1, 2].map(|axis| corner_a[axis].min(corner_b[axis]));
+    let max = [0, 1, 2].map(|axis| corner_a[axis].max(corner_b[axis]));
+    let size = [0, 1, 2].map(|axis| (max[axis] - min[axis]) as i64 + 1);
+    let step_x = size[0].min(MAX_FILL_VOLUME);
+    let step_z = size[2].min(MAX_FILL_VOLUME / step_x).max(1);
+    let step_y = size[1].min(MAX_FILL_VOLUME / (step_x * step_z)).max(1);
+
+    let starts = |axis: usize, step: i64| {
+        (min[axis] as i64..=max[axis] as i64)
+            .step_by(step as usize)
+            .map(move |start| {
+                (
+                    start as i32,
+                    (start + step - 1).min(max[axis] as i64) as i32,
+                )
+            })
+    };
+    let mut boxes = Vec::new();
+    for (y0, y1) in starts(1, step_y) {
+        for (z0, z1) in starts(2, step_z) {
+            for (x0, x1) in starts(0, step_x) {
+                boxes.push(([x0, y0, z0], [x1, y1, z1]));
+            }
+        }
+    }
+    boxes
+}
+
+/// `fill` commands for a world region, split so none exceeds MAX_FILL_VOLUME
+pub fn fill_commands(world_min: [i32; 3], world_max: [i32; 3], block_spec: &str) -> Vec<String> {
+    split_fill_region(world_min, world_max)
+        .into_iter()
+        .map(|(min, max)| fill_command(min, max, block_spec))
+        .collect()
+}
+
 /// Poll for a block at the given position with retries
 /// This handles timing issues in CI environments where block updates may take longer
 pub async fn poll_block_with_retry(
     bot: &TestBot,
     world_pos: [i32; 3],
     expected_block: &str,
+    settles_fast: bool,
 ) -> Result<Option<String>> {
-    for attempt in 0..BLOCK_POLL_ATTEMPTS {
+    poll_block_until(
+        bot,
+        world_pos,
+        |actual| block_matches(actual, expected_block),
+        settles_fast,
+    )
+    .await
+}
+
+/// Poll a block until `done` accepts its state, returning the last state read either way
+/// With `settles_fast`, the same rejected state on two reads in a row ends polling early.
+pub async fn poll_block_until(
+    bot: &TestBot,
+    world_pos: [i32; 3],
+    done: impl Fn(&str) -> bool,
+    settles_fast: bool,
+) -> Result<Option<String>> {
+    let mut previous: Option<String> = None;
+    let mut poll = Poll::start(bot);
+    loop {
         let block = bot.get_block(world_pos).await?;
 
-        // Check if the block matches what we expect
         if let Some(ref actual) = block
-            && block_matches(actual, expected_block)
+            && done(actual)
         {
             return Ok(block);
         }
+        if settles_fast && block.is_some() && block == previous {
+            tracing::debug!(pos = ?world_pos, attempt = poll.attempt, "Block settled on a wrong state");
+            return Ok(block);
+        }
+        // Return whatever we have once the retries are used up
+        if !poll.retry().await {
+            return Ok(block);
+        }
+        previous = block;
+    }
+}
+
+/// Poll the entities in a world-space region until `done` accepts them
+/// Entity spawns reach the bot a little after the tick they happen, like block updates.
+pub async fn poll_entities_with_retry(
+    bot: &TestBot,
+    world_region: [[i32; 3]; 2],
+    done: impl Fn(&[EntityInfo]) -> bool,
+) -> Result<Vec<EntityInfo>> {
+    let mut poll = Poll::start(bot);
+    loop {
+        let entities = bot.get_entities(world_region).await?;
+        // Return whatever we have once the retries are used up
+        if done(&entities) || !poll.retry().await {
+            return Ok(entities);
+        }
+    }
+}
+
+/// Check if an entity type as azalea prints it ("ItemFrame") is the expected id ("minecraft:item_frame")
+pub fn entity_matches(kind: &str, expected: &str) -> bool {
+    kind.to_lowercase().replace('_', "") == normalize_block_name(expected)
+}
+
+/// Count the matching entities in a check's region
+async fn check_entities(
+    bot: &TestBot,
+    tick: u32,
+    check: &EntityCheck,
+    offset: [i32; 3],
+) -> Result<Option<AssertFailure>> {
+    let world_region = [
+        apply_offset(check.region[0], offset),
+        apply_offset(check.region[1], offset),
+    ];
+    let count_matching = |entities: &[EntityInfo]| {
+        entities
+            .iter()
+            .filter(|info| {
+                check
+                    .entity
+                    .as_deref()
+                    .is_none_or(|expected| entity_matches(&info.kind, expected))
+            })
+            .count() as u32
+    };
+    let entities = poll_entities_with_retry(bot, world_region, |entities| {
+        count_matching(entities) == check.count
+    })
+    .await?;
+    let actual = count_matching(&entities);
+    let what = check.entity.as_deref().unwrap_or("entities");
+    if actual != check.count {
+        tracing::debug!(
+            tick,
+            region = ?check.region,
+            entity = %what,
+            expected = check.count,
+            actual,
+            "Entity count check failed"
+        );
+        return Ok(Some(AssertFailure {
+            tick,
+            expected: InfoType::String(format!("{} {}", check.count, what)),
+            actual: InfoType::String(format!("{} {}", actual, what)),
+            position: check.region[0],
+            error_message: "Entity count was different".to_string(),
+            execution_time_ms: None,
+        }));
+    }
+    tracing::debug!(tick, region = ?check.region, entity = %what, count = actual, "Entity count check passed");
+    Ok(None)
+}
+
+/// Compare one container slot against the expected item and count
+/// An empty slot reads as "minecraft:air" with count 0.
+async fn check_container(
+    bot: &mut TestBot,
+    tick: u32,
+    check: &ContainerCheck,
+    offset: [i32; 3],
+) -> Result<Option<AssertFailure>> {
+    let world_pos = apply_offset(check.pos, offset);
+    let items = match bot.query_block_data(world_pos, "Items").await? {
+        Some(data) => snbt::item_stacks(&snbt::parse(&data)?),
+        None => Vec::new(),
+    };
+    let (actual_id, actual_count) = items
+        .iter()
+        .find(|stack| stack.slot == check.slot)
+        .map_or(("minecraft:air", 0), |stack| {
+            (stack.id.as_str(), stack.count)
+        });
+
+    let expected_empty = normalize_block_name(&check.item) == "air";
+    let id_matches = if expected_empty {
+        actual_count == 0
+    } else {
+        normalize_block_name(actual_id) == normalize_block_name(&check.item)
+    };
+    if id_matches && (expected_empty || actual_count == check.count) {
+        tracing::debug!(tick, pos = ?check.pos, slot = check.slot, item = %check.item, "Container check passed");
+        return Ok(None);
+    }
+
+    tracing::debug!(
+        tick,
+        pos = ?check.pos,
+        slot = check.slot,
+        expected = %check.item,
+        actual = %actual_id,
+        "Container check failed"
+    );
+    Ok(Some(AssertFailure {
+        tick,
+        expected: InfoType::String(format!(
+            "slot {}: {} x{}",
+            check.slot, check.item, check.count
+        )),
+        actual: InfoType::String(format!(
+            "slot {}: {} x{}",
+            check.slot, actual_id, actual_count
+        )),
+        position: check.pos,
+        error_message: "Container slot was different".to_string(),
+        execution_time_ms: None,
+    }))
+}
+
+/// First position (relative to the structure origin) where `live` differs from `scratch`,
+/// with the expected and actual block; re-read a few times while block updates arrive
+async fn first_structure_difference(
+    bot: &TestBot,
+    live: [i32; 3],
+    scratch: [i32; 3],
+    size: [i32; 3],
+) -> Result<Option<([i32; 3], String, String)>> {
+    let read = async |pos: [i32; 3]| -> Result<String> {
+        Ok(bot
+            .get_block(pos)
+            .await?
+            .map(|state| extract_block_id(&state))
+            .unwrap_or_else(|| "none".to_string()))
+    };
+    let mut poll = Poll::start(bot);
+    loop {
+        let mut difference = None;
+        'scan: for y in 0..size[1] {
+            for z in 0..size[2] {
+                for x in 0..size[0] {
+                    let rel = [x, y, z];
+                    let expected = read(apply_offset(rel, scratch)).await?;
+                    let actual = read(apply_offset(rel, live)).await?;
+                    if expected != actual {
+                        difference = Some((rel, expected, actual));
+                        break 'scan;
+                    }
+                }
+            }
+        }
+        if difference.is_none() || !poll.retry().await {
+            return Ok(difference);
+        }
+    }
+}
+
+/// Compare a region against a structure template, placed for reference in a scratch area at the
+/// top of the world straight above it: the same chunks, so the bot has them loaded, and clear of
+/// every test area on the grid. The scratch area is emptied again afterwards.
+async fn check_structure(
+    bot: &mut TestBot,
+    tick: u32,
+    check: &StructureCheck,
+    offset: [i32; 3],
+    dimension: Dimension,
+) -> Result<Option<AssertFailure>> {
+    if check.size.iter().any(|len| *len < 1) {
+        bail!("assert_structure size must be at least 1 on every axis");
+    }
+    let live = apply_offset(check.pos, offset);
+    let (_, max_y) = dimension.y_range();
+    let scratch = [live[0], max_y - check.size[1] + 1, live[2]];
+    if live[1] + check.size[1] > scratch[1] {
+        bail!(
+            "assert_structure region at y {} reaches the scratch area at y {}",
+            live[1],
+            scratch[1]
+        );
+    }
+    let scratch_max = [
+        scratch[0] + check.size[0] - 1,
+        max_y,
+        scratch[2] + check.size[2] - 1,
+    ];
+
+    for command in fill_commands(scratch, scratch_max, "air") {
+        bot.send_command(&command).await?;
+    }
+    bot.send_command_checked(&format!(
+        "place template {} {} {} {}",
+        check.structure, scratch[0], scratch[1], scratch[2]
+    ))
+    .await?;
+    let difference = first_structure_difference(bot, live, scratch, check.size).await;
+    for command in fill_commands(scratch, scratch_max, "air") {
+        bot.send_command(&command).await?;
+    }
+
+    let Some((rel, expected, actual)) = difference? else {
+        tracing::debug!(tick, pos = ?check.pos, structure = %check.structure, "Structure check passed");
+        return Ok(None);
+    };
+    let position = apply_offset(check.pos, rel);
+    tracing::debug!(
+        tick,
+        pos = ?position,
+        structure = %check.structure,
+        expected = %expected,
+        actual = %actual,
+        "Structure check failed"
+    );
+    Ok(Some(AssertFailure {
+        tick,
+        expected: InfoType::String(expected),
+        actual: InfoType::String(actual),
+        position,
+        error_message: format!("Region differs from structure {}", check.structure),
+        execution_time_ms: None,
+    }))
+}
+
+/// Check the light level at a position against a range, polling while light updates arrive
+async fn check_light(
+    bot: &mut TestBot,
+    tick: u32,
+    check: &LightCheck,
+    offset: [i32; 3],
+) -> Result<Option<AssertFailure>> {
+    if check.min > check.max || check.max > MAX_LIGHT {
+        bail!(
+            "assert_light needs 0 <= min <= max <= {}, got {}..{}",
+            MAX_LIGHT,
+            check.min,
+            check.max
+        );
+    }
+    let world_pos = apply_offset(check.pos, offset);
+    let mut poll = Poll::start(bot);
+    loop {
+        if bot.light_in_range(world_pos, check.min, check.max).await? {
+            tracing::debug!(tick, pos = ?check.pos, min = check.min, max = check.max, "Light check passed");
+            return Ok(None);
+        }
+        if !poll.retry().await {
+            break;
+        }
+    }
+
+    let actual = bot.get_light(world_pos).await?;
+    tracing::debug!(
+        tick,
+        pos = ?check.pos,
+        min = check.min,
+        max = check.max,
+        actual,
+        "Light check failed"
+    );
+    Ok(Some(AssertFailure {
+        tick,
+        expected: InfoType::String(format!("light {}..{}", check.min, check.max)),
+        actual: InfoType::String(format!("light {}", actual)),
+        position: check.pos,
+        error_message: "Light level was out of range".to_string(),
+        execution_time_ms: None,
+    }))
+}
+
+/// Check the biome at a position
+/// The server can't name the biome, so a failure probes the vanilla biomes for the actual one.
+async fn check_biome(
+    bot: &mut TestBot,
+    tick: u32,
+    check: &BiomeCheck,
+    offset: [i32; 3],
+) -> Result<Option<AssertFailure>> {
+    let world_pos = apply_offset(check.pos, offset);
+    if bot.is_biome(world_pos, &check.biome).await? {
+        tracing::debug!(tick, pos = ?check.pos, biome = %check.biome, "Biome check passed");
+        return Ok(None);
+    }
+    let actual = match bot.get_biome(world_pos).await {
+        Some(biome) => biome.to_string(),
+        None => format!("not {}", check.biome),
+    };
+    tracing::debug!(tick, pos = ?check.pos, biome = %check.biome, actual = %actual, "Biome check failed");
+    Ok(Some(AssertFailure {
+        tick,
+        expected: InfoType::String(check.biome.clone()),
+        actual: InfoType::String(actual),
+        position: check.pos,
+        error_message: "Biome was different".to_string(),
+        execution_time_ms: None,
+    }))
+}
+
+/// Check that every block in a region matches one block, scanning y, then z, then x and
+/// stopping at the first mismatch; the scan is repeated while block updates arrive
+async fn check_fill(
+    bot: &TestBot,
+    tick: u32,
+    check: &FillCheck,
+    offset: [i32; 3],
+) -> Result<Option<AssertFailure>> {
+    let min = [0, 1, 2].map(|axis| check.region[0][axis].min(check.region[1][axis]));
+    let max = [0, 1, 2].map(|axis| check.region[0][axis].max(check.region[1][axis]));
+    let volume: i64 = (0..3)
+        .map(|axis| (max[axis] - min[axis]) as i64 + 1)
+        .product();
+    if volume > MAX_FILL_VOLUME {
+        bail!(
+            "assert_fill region holds {} blocks, more than the {} one check may scan; split it",
+            volume,
+            MAX_FILL_VOLUME
+        );
+    }
+    if check.block.starts_with('#') {
+        bail!("assert_fill can't check block tag {}", check.block);
+    }
+    let expected = super::block::make_block(&check.block);
+
+    let mut poll = Poll::start(bot);
+    let (pos, actual) = loop {
+        let mut mismatch = None;
+        'scan: for y in min[1]..=max[1] {
+            for z in min[2]..=max[2] {
+                for x in min[0]..=max[0] {
+                    let pos = [x, y, z];
+                    let actual = bot.get_block(apply_offset(pos, offset)).await?;
+                    if actual
+                        .as_deref()
+                        .is_none_or(|actual| !state_matches(actual, &expected))
+                    {
+                        mismatch = Some((pos, actual));
+                        break 'scan;
+                    }
+                }
+            }
+        }
+        match mismatch {
+            None => {
+                tracing::debug!(tick, region = ?check.region, block = %check.block, "Fill check passed");
+                return Ok(None);
+            }
+            Some(mismatch) if !poll.retry().await => break mismatch,
+            Some(_) => {}
+        }
+    };
+
+    let actual_name = actual
+        .as_ref()
+        .map(|s| extract_block_id(s))
+        .unwrap_or_else(|| "none".to_string());
+    tracing::debug!(
+        tick,
+        pos = ?pos,
+        expected = %check.block,
+        actual = %actual_name,
+        "Fill check failed"
+    );
+    Ok(Some(AssertFailure {
+        tick,
+        expected: InfoType::String(check.block.clone()),
+        actual: InfoType::String(actual_name),
+        position: pos,
+        error_message: "Block in filled region was different".to_string(),
+        execution_time_ms: None,
+    }))
+}
+
+/// Run the extra checks a placeholder assertion stands in for
+/// Returns AssertFailed with every check that doesn't hold.
+pub async fn run_extra_checks(
+    bot: &mut TestBot,
+    tick: u32,
+    checks: &[ExtraCheck],
+    offset: [i32; 3],
+    dimension: Dimension,
+) -> Result<Outcome> {
+    let mut failures = Vec::new();
+    for check in checks {
+        let failure = match check {
+            ExtraCheck::AssertEntities(check) => check_entities(bot, tick, check, offset).await?,
+            ExtraCheck::AssertContainer(check) => check_container(bot, tick, check, offset).await?,
+            ExtraCheck::AssertStructure(check) => {
+                check_structure(bot, tick, check, offset, dimension).await?
+            }
+            ExtraCheck::AssertLight(check) => check_light(bot, tick, check, offset).await?,
+            ExtraCheck::AssertBiome(check) => check_biome(bot, tick, check, offset).await?,
+            ExtraCheck::AssertFill(check) => check_fill(bot, tick, check, offset).await?,
+        };
+        failures.extend(failure);
+    }
+    Ok(Outcome::from_failures(failures))
+}
+
+/// Check a block against a tag (`#minecraft:logs`) with `execute if block`
+/// Tags come from the server's datapacks, so membership is the server's call, not ours.
+async fn check_block_tag(
+    bot: &mut TestBot,
+    tick: u32,
+    check: &BlockCheck,
+    world_pos: [i32; 3],
+    negated: bool,
+) -> Result<Option<AssertFailure>> {
+    let tag = check.is.to_command();
+    let condition = format!(
+        "{} block {} {} {} {}",
+        if negated { "unless" } else { "if" },
+        world_pos[0],
+        world_pos[1],
+        world_pos[2],
+        tag
+    );
+    let mut poll = Poll::start(bot);
+    loop {
+        if bot.execute_test(&condition).await? {
+            tracing::debug!(tick, pos = ?check.pos, tag = %tag, negated, "Block tag check passed");
+            return Ok(None);
+        }
+        if !poll.retry().await {
+            break;
+        }
+    }
+
+    let actual_name = bot
+        .get_block(world_pos)
+        .await?
+        .map(|s| extract_block_id(&s))
+        .unwrap_or_else(|| "none".to_string());
+    tracing::debug!(
+        tick,
+        pos = ?check.pos,
+        tag = %tag,
+        negated,
+        actual = %actual_name,
+        "Block tag check failed"
+    );
+    let (expected, error_message) = if negated {
+        (format!("not {}", tag), "Block was in a negated tag")
+    } else {
+        (tag, "Block was not in the tag")
+    };
+    Ok(Some(AssertFailure {
+        tick,
+        expected: InfoType::String(expected),
+        actual: InfoType::String(actual_name),
+        position: check.pos,
+        error_message: error_message.to_string(),
+        execution_time_ms: None,
+    }))
+}
+
+/// Compare one block check against the world, honoring its negate and strict flags
+async fn check_block(
+    bot: &mut TestBot,
+    tick: u32,
+    check: &BlockCheck,
+    offset: [i32; 3],
+    flags: &CheckFlags,
+) -> Result<Option<AssertFailure>> {
+    let world_pos = apply_offset(check.pos, offset);
+    let strict = flags.strict.contains(&check.pos);
+
+    for (prop_name, prop_value) in &check.is.properties {
+        property_expectation(prop_value)
+            .with_context(|| format!("Property '{}' of {}", prop_name, check.is.id))?;
+    }
+
+    if check.is.id.starts_with('#') {
+        if check
+            .is
+            .properties
+            .values()
+            .any(serde_json::Value::is_object)
+        {
+            bail!(
+                "property comparisons can't be used with block tag {}",
+                check.is.id
+            );
+        }
+        if strict {
+            bail!(
+                "strict_properties can't be used with block tag {}",
+                check.is.id
+            );
+        }
+        let negated = flags.negated.contains(&check.pos);
+        return check_block_tag(bot, tick, check, world_pos, negated).await;
+    }
 
-        // If not the last attempt, wait before retrying
-        if attempt < BLOCK_POLL_ATTEMPTS - 1 {
-            tokio::time::sleep(tokio::time::Duration::from_millis(BLOCK_POLL_DELAY_MS)).await;
+    if flags.negated.contains(&check.pos) {
+        let matches = |actual: &str| {
+            state_matches(actual, &check.is)
+                && (!strict || unlisted_properties(actual, &check.is).is_empty())
+        };
+        let actual_block = poll_block_until(
+            bot,
+            world_pos,
+            |actual| !matches(actual),
+            flags.settles_fast,
+        )
+        .await?;
+        // An unreadable block can't prove the negation
+        if actual_block.as_deref().is_none_or(matches) {
+            let actual_name = actual_block
+                .as_ref()
+                .map(|s| extract_block_id(s))
+                .unwrap_or_else(|| "none".to_string());
+            tracing::debug!(
+                tick,
+                pos = ?check.pos,
+                unexpected = %check.is.to_command(),
+                "Negated block check failed"
+            );
+            return Ok(Some(AssertFailure {
+                tick,
+                expected: InfoType::String(format!("not {}", check.is.to_command())),
+                actual: InfoType::String(actual_name),
+                position: check.pos,
+                error_message: "Block matched a negated check".to_string(),
+                execution_time_ms: None,
+            }));
         }
+        tracing::debug!(tick, pos = ?check.pos, block = %check.is.id, "Negated block check passed");
+        return Ok(None);
     }
 
-    // Return whatever we have after all retries
-    bot.get_block(world_pos).await
+    // Poll with retries to handle timing issues in CI environments
+    let actual_block =
+        poll_block_with_retry(bot, world_pos, &check.is.id, flags.settles_fast).await?;
+
+    // Check block type
+    let matches = actual_block
+        .as_ref()
+        .is_some_and(|actual| block_matches(actual, &check.is.id));
+
+    if !matches {
+        let actual_name = actual_block
+            .as_ref()
+            .map(|s| extract_block_id(s))
+            .unwrap_or_else(|| "none".to_string());
+
+        tracing::debug!(
+            tick,
+            pos = ?check.pos,
+            expected = %check.is.id,
+            actual = %actual_name,
+            "Block check failed"
+        );
+
+        return Ok(Some(AssertFailure {
+            tick,
+            expected: InfoType::String(check.is.id.clone()),
+            actual: InfoType::String(actual_name),
+            position: check.pos,
+            error_message: "Block was different".to_string(),
+            execution_time_ms: None,
+        }));
+    }
+
+    // Check state properties if any are specified
+    if !check.is.properties.is_empty() {
+        let actual_str = actual_block.as_ref().unwrap();
+        let actual_props = block_properties(actual_str);
+
+        for (prop_name, prop_value) in &check.is.properties {
+            let expected_value = expectation_label(prop_value);
+
+            if !property_matches(&actual_props, prop_name, prop_value) {
+                let actual_prop = actual_props
+                    .get(&prop_name.to_lowercase())
+                    .cloned()
+                    .unwrap_or_else(|| "?".to_string());
+
+                tracing::debug!(
+                    tick,
+                    pos = ?check.pos,
+                    property = %prop_name,
+                    expected = %expected_value,
+                    actual = %actual_prop,
+                    "Block state check failed\n{}",
+                    property_diff(&check.is.properties, actual_str, strict)
+                );
+
+                return Ok(Some(AssertFailure {
+                    tick,
+                    expected: InfoType::String(property_label(prop_name, prop_value)),
+                    actual: InfoType::String(format!("{}={}", prop_name, actual_prop)),
+                    position: check.pos,
+                    error_message: "Block was different".to_string(),
+                    execution_time_ms: None,
+                }));
+            }
+
+            tracing::debug!(
+                tick,
+                pos = ?check.pos,
+                property = %prop_name,
+                value = %expected_value,
+                "Block state check passed"
+            );
+        }
+    } else {
+        tracing::debug!(tick, pos = ?check.pos, block = %check.is.id, "Block check passed");
+    }
+
+    // With strict_properties, the listed properties must be the whole state
+    if strict {
+        let actual_str = actual_block.as_deref().unwrap_or_default();
+        let unlisted = unlisted_properties(actual_str, &check.is);
+        if !unlisted.is_empty() {
+            tracing::debug!(
+                tick,
+                pos = ?check.pos,
+                unlisted = ?unlisted,
+                "Strict property check failed\n{}",
+                property_diff(&check.is.properties, actual_str, true)
+            );
+            return Ok(Some(AssertFailure {
+                tick,
+                expected: InfoType::String(check.is.to_command()),
+                actual: InfoType::String(extract_block_id(actual_str)),
+                position: check.pos,
+                error_message: format!(
+                    "Block had properties the check doesn't list: {}",
+                    unlisted.join(", ")
+                ),
+                execution_time_ms: None,
+            }));
+        }
+        tracing::debug!(tick, pos = ?check.pos, "Strict property check passed");
+    }
+    Ok(None)
+}
+
+/// Run-wide settings for sending actions
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ActionSettings {
+    /// Extra pause after each place, fill and remove (`--action-delay`, 0 by default)
+    /// Commands are paced by the bot's rate limiter; this only slows runs down further.
+    pub delay_ms: u64,
+    /// Read back written blocks and resend a command the server seems to have dropped
+    pub verify_writes: bool,
+}
+
+impl ActionSettings {
+    /// The `--action-delay` pause after an action, if one was asked for
+    async fn pause(self) {
+        if self.delay_ms > 0 {
+            tokio::time::sleep(tokio::time::Duration::from_millis(self.delay_ms)).await;
+        }
+    }
+}
+
+/// Confirm that `command` left `expected_id` at `positions`, sending it once more if not
+/// Block updates can take a moment to reach the bot, so each position is polled like a check.
+async fn verify_write(
+    bot: &mut TestBot,
+    command: &str,
+    positions: &[[i32; 3]],
+    expected_id: &str,
+) -> Result<()> {
+    for resent in [false, true] {
+        let mut missing = None;
+        for &pos in positions {
+            let actual = poll_block_with_retry(bot, pos, expected_id, false).await?;
+            if !actual.is_some_and(|actual| block_matches(&actual, expected_id)) {
+                missing = Some(pos);
+                break;
+            }
+        }
+        let Some(pos) = missing else {
+            return Ok(());
+        };
+        if resent {
+            tracing::warn!(command, pos = ?pos, "Write still not visible after resending");
+        } else {
+            tracing::warn!(command, pos = ?pos, "Write not visible, resending the command");
+            bot.send_command_checked(command).await?;
+        }
+    }
+    Ok(())
 }
 
 /// Execute a single test action
-/// Returns the outcome: Action (non-assertion), AssertPassed, or AssertFailed with details
+/// Returns the outcome: Action (non-assertion), AssertPassed, or AssertFailed with every failed check.
+/// Checks at a `flags.negated` position pass when the block does NOT match;
+/// at a `flags.strict` position the block may not have properties the check doesn't list.
+/// `nbt` is block entity data for a single place, e.g. a chest's `{Items:[...]}`.
+#[allow(clippy::too_many_arguments)]
 pub async fn execute_action(
     bot: &mut TestBot,
     tick: u32,
     entry: &TimelineEntry,
     _value_idx: usize,
     offset: [i32; 3],
-    action_delay_ms: u64,
-    verbose: bool,
-) -> Result<ActionOutcome> {
+    settings: ActionSettings,
+    flags: &CheckFlags,
+    nbt: Option<&str>,
+) -> Result<Outcome> {
     match &entry.action_type {
         ActionType::Place { pos, block } => {
             let world_pos = apply_offset(*pos, offset);
-            let block_spec = block.to_command();
-            let cmd = format!(
-                "setblock {} {} {} {}",
-                world_pos[0], world_pos[1], world_pos[2], block_spec
-            );
-            bot.send_command(&cmd).await?;
-            if verbose {
-                println!(
-                    "    {} Tick {}: place at [{}, {}, {}] = {}",
-                    "→".blue(),
-                    tick,
-                    pos[0],
-                    pos[1],
-                    pos[2],
-                    block_spec.dimmed()
-                );
+            let block_spec = format!("{}{}", block.to_command(), nbt.unwrap_or_default());
+            let cmd = setblock_command(world_pos, &block_spec);
+            bot.send_command_checked(&cmd).await?;
+            if settings.verify_writes {
+                verify_write(bot, &cmd, &[world_pos], &block.id).await?;
             }
-            tokio::time::sleep(tokio::time::Duration::from_millis(action_delay_ms)).await;
-            Ok(ActionOutcome::Action)
+            tracing::debug!(tick, pos = ?pos, block = %block_spec, "Placed block");
+            settings.pause().await;
+            Ok(Outcome::Action)
         }
 
         ActionType::PlaceEach { blocks } => {
             for placement in blocks {
                 let world_pos = apply_offset(placement.pos, offset);
                 let block_spec = placement.block.to_command();
-                let cmd = format!(
-                    "setblock {} {} {} {}",
-                    world_pos[0], world_pos[1], world_pos[2], block_spec
-                );
-                bot.send_command(&cmd).await?;
-                if verbose {
-                    println!(
-                        "    {} Tick {}: place at [{}, {}, {}] = {}",
-                        "→".blue(),
-                        tick,
-                        placement.pos[0],
-                        placement.pos[1],
-                        placement.pos[2],
-                        block_spec.dimmed()
-                    );
+                let cmd = setblock_command(world_pos, &block_spec);
+                bot.send_command_checked(&cmd).await?;
+                if settings.verify_writes {
+                    verify_write(bot, &cmd, &[world_pos], &placement.block.id).await?;
                 }
-                tokio::time::sleep(tokio::time::Duration::from_millis(PLACE_EACH_DELAY_MS)).await;
+                tracing::debug!(tick, pos = ?placement.pos, block = %block_spec, "Placed block");
             }
-            Ok(ActionOutcome::Action)
+            Ok(Outcome::Action)
         }
 
         ActionType::Fill { region, with } => {
             let world_min = apply_offset(region[0], offset);
             let world_max = apply_offset(region[1], offset);
             let block_spec = with.to_command();
-            let cmd = format!(
-                "fill {} {} {} {} {} {} {}",
-                world_min[0],
-                world_min[1],
-                world_min[2],
-                world_max[0],
-                world_max[1],
-                world_max[2],
-                block_spec
-            );
-            bot.send_command(&cmd).await?;
-            if verbose {
-                println!(
-                    "    {} Tick {}: fill [{},{},{}] to [{},{},{}] = {}",
-                    "→".blue(),
-                    tick,
-                    region[0][0],
-                    region[0][1],
-                    region[0][2],
-                    region[1][0],
-                    region[1][1],
-                    region[1][2],
-                    block_spec.dimmed()
-                );
+            // Regions over the fill limit go out as several fills
+            for (min, max) in split_fill_region(world_min, world_max) {
+                let cmd = fill_command(min, max, &block_spec);
+                bot.send_command_checked(&cmd).await?;
+                // Both corners of each fill stand in for the whole box
+                if settings.verify_writes {
+                    verify_write(bot, &cmd, &[min, max], &with.id).await?;
+                }
             }
-            tokio::time::sleep(tokio::time::Duration::from_millis(action_delay_ms)).await;
-            Ok(ActionOutcome::Action)
+            tracing::debug!(
+                tick,
+                from = ?region[0],
+                to = ?region[1],
+                block = %block_spec,
+                "Filled region"
+            );
+            settings.pause().await;
+            Ok(Outcome::Action)
         }
 
         ActionType::Remove { pos } => {
             let world_pos = apply_offset(*pos, offset);
-            let cmd = format!(
-                "setblock {} {} {} air",
-                world_pos[0], world_pos[1], world_pos[2]
-            );
+            let cmd = setblock_command(world_pos, "air");
             bot.send_command(&cmd).await?;
-            if verbose {
-                println!(
-                    "    {} Tick {}: remove at [{}, {}, {}]",
-                    "→".blue(),
-                    tick,
-                    pos[0],
-                    pos[1],
-                    pos[2]
-                );
+            if settings.verify_writes {
+                verify_write(bot, &cmd, &[world_pos], "minecraft:air").await?;
             }
-            tokio::time::sleep(tokio::time::Duration::from_millis(action_delay_ms)).await;
-            Ok(ActionOutcome::Action)
+            tracing::debug!(tick, pos = ?pos, "Removed block");
+            settings.pause().await;
+            Ok(Outcome::Action)
         }
 
         ActionType::Assert { checks } => {
+            // Every check runs, so a failed tick reports all of its wrong blocks at once
+            let mut failures = Vec::new();
             for check in checks {
-                let world_pos = apply_offset(check.pos, offset);
-
-                // Poll with retries to handle timing issues in CI environments
-                let actual_block = poll_block_with_retry(bot, world_pos, &check.is.id).await?;
-
-                // Check block type
-                let matches = actual_block
-                    .as_ref()
-                    .is_some_and(|actual| block_matches(actual, &check.is.id));
-
-                if !matches {
-                    let actual_name = actual_block
-                        .as_ref()
-                        .map(|s| extract_block_id(s))
-                        .unwrap_or_else(|| "none".to_string());
-
-                    if verbose {
-                        println!(
-                            "    {} Tick {}: assert block at [{}, {}, {}] expected {}, got {}",
-                            "✗".red().bold(),
-                            tick,
-                            check.pos[0],
-                            check.pos[1],
-                            check.pos[2],
-                            check.is.id.green(),
-                            actual_name.red()
-                        );
-                    }
+                failures.extend(check_block(bot, tick, check, offset, flags).await?);
+            }
+            Ok(Outcome::from_failures(failures))
+        }
+    }
+}
 
-                    return Ok(ActionOutcome::AssertFailed(AssertFailure {
-                        tick,
-                        expected: InfoType::String(check.is.id.clone()),
-                        actual: InfoType::String(actual_name),
-                        position: check.pos,
-                        error_message: "Block was different".to_string(),
-                        execution_time_ms: None,
-                    }));
-                }
+/// How an expected property value is compared with the block's own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    const ALL: [Comparison; 6] = [
+        Comparison::Eq,
+        Comparison::Ne,
+        Comparison::Lt,
+        Comparison::Le,
+        Comparison::Gt,
+        Comparison::Ge,
+    ];
+
+    fn symbol(self) -> &'static str {
+        match self {
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        }
+    }
+
+    fn holds(self, ordering: std::cmp::Ordering) -> bool {
+        match self {
+            Comparison::Eq => ordering.is_eq(),
+            Comparison::Ne => ordering.is_ne(),
+            Comparison::Lt => ordering.is_lt(),
+            Comparison::Le => ordering.is_le(),
+            Comparison::Gt => ordering.is_gt(),
+            Comparison::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+/// Split an expected property value into its comparison and operand
+/// `{">=": 5}` is (Ge, "5"); a plain value like `"north"` or `15` is (Eq, value).
+fn property_expectation(value: &serde_json::Value) -> Result<(Comparison, String)> {
+    let serde_json::Value::Object(map) = value else {
+        return Ok((Comparison::Eq, property_value_string(value)));
+    };
+    let mut entries = map.iter();
+    let (Some((op, operand)), None) = (entries.next(), entries.next()) else {
+        bail!(
+            "a property comparison needs exactly one operator, got {}",
+            value
+        );
+    };
+    let Some(comparison) = Comparison::ALL
+        .into_iter()
+        .find(|c| c.symbol() == op.as_str())
+    else {
+        bail!(
+            "unknown property comparison '{}', expected one of ==, !=, <, <=, >, >=",
+            op
+        );
+    };
+    Ok((comparison, property_value_string(operand)))
+}
+
+/// An expected property value as failures show it: `15`, or `>=5` for a comparison
+fn expectation_label(value: &serde_json::Value) -> String {
+    match property_expectation(value) {
+        Ok((Comparison::Eq, operand)) => operand,
+        Ok((comparison, operand)) => format!("{}{}", comparison.symbol(), operand),
+        Err(_) => value.to_string(),
+    }
+}
+
+/// A property expectation as a failure's expected side: `power=15`, or `power>=5`
+fn property_label(prop_name: &str, value: &serde_json::Value) -> String {
+    match property_expectation(value) {
+        Ok((Comparison::Eq, operand)) => format!("{}={}", prop_name, operand),
+        _ => format!("{}{}", prop_name, expectation_label(value)),
+    }
+}
+
+/// Check an actual property (from `block_properties`) against its expected value
+/// Numbers on both sides are compared numerically; anything else only by `==` and `!=`.
+fn property_matches(
+    actual: &HashMap<String, String>,
+    prop_name: &str,
+    expected: &serde_json::Value,
+) -> bool {
+    let Ok((comparison, operand)) = property_expectation(expected) else {
+        return false;
+    };
+    let Some(value) = actual.get(&prop_name.to_lowercase()) else {
+        return false;
+    };
+    let (value, operand) = (
+        normalize_property_value(value),
+        normalize_property_value(&operand),
+    );
+    match (value.parse::<f64>(), operand.parse::<f64>()) {
+        (Ok(value), Ok(operand)) => value
+            .partial_cmp(&operand)
+            .is_some_and(|ordering| comparison.holds(ordering)),
+        _ => match comparison {
+            Comparison::Eq => value == operand,
+            Comparison::Ne => value != operand,
+            _ => false,
+        },
+    }
+}
+
+/// Whether a block state has the expected id and every expected property
+fn state_matches(block_state_str: &str, expected: &Block) -> bool {
+    if !block_matches(block_state_str, &expected.id) {
+        return false;
+    }
+    let actual = block_properties(block_state_str);
+    expected
+        .properties
+        .iter()
+        .all(|(name, value)| property_matches(&actual, name, value))
+}
+
+/// Properties of a block state the expected block doesn't list, as `name=value`
+/// Listed properties are compared by `state_matches`; these are what `strict_properties` adds.
+fn unlisted_properties(block_state_str: &str, expected: &Block) -> Vec<String> {
+    extract_all_properties(block_state_str)
+        .into_iter()
+        .filter(|(name, _)| {
+            !expected
+                .properties
+                .keys()
+                .any(|k| k.eq_ignore_ascii_case(name))
+        })
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect()
+}
+
+/// Convert an expected property value from the test spec to its string form
+fn property_value_string(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string().trim_matches('"').to_string(),
+    }
+}
+
+/// Two-column expected-vs-actual property table, differing keys marked with `*`
+/// With `strict`, actual properties the check doesn't list are marked too.
+fn property_diff(
+    expected: &HashMap<String, serde_json::Value>,
+    block_state_str: &str,
+    strict: bool,
+) -> String {
+    let actual = block_properties(block_state_str);
 
-                // Check state properties if any are specified
-                if !check.is.properties.is_empty() {
-                    let actual_str = actual_block.as_ref().unwrap();
-
-                    for (prop_name, prop_value) in &check.is.properties {
-                        // Convert the expected value to string for comparison
-                        let expected_value = match prop_value {
-                            serde_json::Value::String(s) => s.clone(),
-                            other => other.to_string().trim_matches('"').to_string(),
-                        };
-
-                        // Check if the property value is in the block state string
-                        let actual_lower = actual_str.to_lowercase();
-                        let prop_pattern =
-                            format!("{}: {}", prop_name, expected_value).to_lowercase();
-                        let prop_pattern_quoted =
-                            format!("{}: \"{}\"", prop_name, expected_value).to_lowercase();
-                        // Handle numeric values with underscore prefix (e.g., level: _0)
-                        let prop_pattern_underscore =
-                            format!("{}: _{}", prop_name, expected_value).to_lowercase();
-
-                        let prop_matches = actual_lower.contains(&prop_pattern)
-                            || actual_lower.contains(&prop_pattern_quoted)
-                            || actual_lower.contains(&prop_pattern_underscore);
-
-                        if !prop_matches {
-                            // Try to extract the actual property value from the block state string
-                            let actual_prop = extract_property_value(actual_str, prop_name)
-                                .unwrap_or_else(|| "?".to_string());
-
-                            if verbose {
-                                println!(
-                                    "    {} Tick {}: assert block at [{}, {}, {}] state {} expected {}, got {}",
-                                    "✗".red().bold(),
-                                    tick,
-                                    check.pos[0],
-                                    check.pos[1],
-                                    check.pos[2],
-                                    prop_name.dimmed(),
-                                    expected_value.green(),
-                                    actual_prop.red()
-                                );
-                            }
-
-                            return Ok(ActionOutcome::AssertFailed(AssertFailure {
-                                tick,
-                                expected: InfoType::String(format!(
-                                    "{}={}",
-                                    prop_name, expected_value
-                                )),
-                                actual: InfoType::String(format!("{}={}", prop_name, actual_prop)),
-                                position: check.pos,
-                                error_message: "Block was different".to_string(),
-                                execution_time_ms: None,
-                            }));
-                        }
-
-                        if verbose {
-                            println!(
-                                "    {} Tick {}: assert block at [{}, {}, {}] state {} = {}",
-                                "✓".green(),
-                                tick,
-                                check.pos[0],
-                                check.pos[1],
-                                check.pos[2],
-                                prop_name.dimmed(),
-                                expected_value.dimmed()
-                            );
-                        }
+    let mut names: Vec<String> = expected
+        .keys()
+        .map(|k| k.to_lowercase())
+        .chain(actual.keys().cloned())
+        .collect();
+    names.sort();
+    names.dedup();
+
+    let name_width = names.iter().map(String::len).max().unwrap_or(0).max(8);
+    let mut lines = vec![format!(
+        "  {:<name_width$}  {:<12}  actual",
+        "property", "expected"
+    )];
+    for name in &names {
+        let expected_value = expected
+            .iter()
+            .find(|(k, _)| k.to_lowercase() == *name)
+            .map(|(_, v)| v);
+        let actual_value = actual.get(name).cloned();
+
+        let expected_str = expected_value
+            .map(expectation_label)
+            .unwrap_or_else(|| "-".to_string());
+        let actual_str = actual_value.unwrap_or_else(|| "-".to_string());
+        let differs = match expected_value {
+            Some(e) => !property_matches(&actual, name, e),
+            None => strict,
+        };
+
+        let marker = if differs { '*' } else { ' ' };
+        lines.push(format!(
+            "{} {:<name_width$}  {:<12}  {}",
+            marker, name, expected_str, actual_str
+        ));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill_commands_split_large_regions() {
+        assert_eq!(
+            fill_commands([0, 64, 0], [2, 66, 2], "stone"),
+            vec!["fill 0 64 0 2 66 2 stone".to_string()]
+        );
+
+        // 50x50x50 = 125,000 blocks: layers of 50x13x50 (32,500 blocks), the last one 11 high
+        let boxes = split_fill_region([49, 149, 49], [0, 100, 0]);
+        assert_eq!(boxes.len(), 4);
+        assert_eq!(boxes[0], ([0, 100, 0], [49, 112, 49]));
+        assert_eq!(boxes[3], ([0, 139, 0], [49, 149, 49]));
+        let mut covered = std::collections::HashSet::new();
+        for (min, max) in &boxes {
+            let volume: i64 = (0..3).map(|a| (max[a] - min[a] + 1) as i64).product();
+            assert!(volume <= MAX_FILL_VOLUME);
+            for x in min[0]..=max[0] {
+                for y in min[1]..=max[1] {
+                    for z in min[2]..=max[2] {
+                        assert!(covered.insert([x, y, z]), "overlap at {:?}", [x, y, z]);
                     }
-                } else if verbose {
-                    println!(
-                        "    {} Tick {}: assert block at [{}, {}, {}] is {}",
-                        "✓".green(),
-                        tick,
-                        check.pos[0],
-                        check.pos[1],
-                        check.pos[2],
-                        check.is.id.dimmed()
-                    );
                 }
             }
-            Ok(ActionOutcome::AssertPassed)
         }
+        assert_eq!(covered.len(), 50 * 50 * 50);
+
+        // Wider than a fill in x and z: rows and pieces of rows
+        let wide = split_fill_region([0, 0, 0], [40_000, 0, 1]);
+        assert!(
+            wide.iter()
+                .all(|(min, max)| (max[0] - min[0] + 1) as i64 <= MAX_FILL_VOLUME)
+        );
+        assert_eq!(wide.len(), 4);
     }
-}
 
-/// Extract a property value from an Azalea block state debug string
-/// Input: "BlockState(id: 6795, OakFence { east: false, north: true })", "east"
-/// Output: Some("false")
-fn extract_property_value(block_state_str: &str, prop_name: &str) -> Option<String> {
-    let lower = block_state_str.to_lowercase();
-    let prop_lower = prop_name.to_lowercase();
+    #[test]
+    fn test_extract_all_properties() {
+        let input = "BlockState(id: 6795, OakFence { east: false, north: true, power: _15 })";
+        assert_eq!(
+            extract_all_properties(input),
+            vec![
+                ("east".to_string(), "false".to_string()),
+                ("north".to_string(), "true".to_string()),
+                ("power".to_string(), "15".to_string()),
+            ]
+        );
+    }
 
-    // Look for "prop_name: value" pattern
-    let pattern = format!("{}: ", prop_lower);
-    if let Some(start) = lower.find(&pattern) {
-        let value_start = start + pattern.len();
-        let rest = &block_state_str[value_start..];
-        // Value ends at comma, space before }, or }
-        let end = rest
-            .find(|c: char| c == ',' || c == '}')
-            .unwrap_or(rest.len());
-        let value = rest[..end].trim().trim_matches('_');
-        if !value.is_empty() {
-            return Some(value.to_string());
-        }
+    #[test]
+    fn test_extract_all_properties_none() {
+        assert!(extract_all_properties("BlockState(id: 1, Stone)").is_empty());
+    }
+
+    #[test]
+    fn test_block_properties_by_name() {
+        let lever = "BlockState(id: 5626, Lever { face: Wall, facing: North, powered: true })";
+        let props = block_properties(lever);
+        assert_eq!(props.get("powered").map(String::as_str), Some("true"));
+        assert_eq!(props.get("facing").map(String::as_str), Some("North"));
+        // Whole names only: "power" is not a prefix of "powered"
+        assert_eq!(props.get("power"), None);
+        assert_eq!(props.get("waterlogged"), None);
+        assert!(block_properties("BlockState(id: 1, Stone)").is_empty());
+    }
+
+    #[test]
+    fn test_entity_matches() {
+        assert!(entity_matches("ItemFrame", "minecraft:item_frame"));
+        assert!(entity_matches("Zombie", "zombie"));
+        assert!(!entity_matches("ZombieVillager", "minecraft:zombie"));
     }
 
-    None
+    #[test]
+    fn test_state_matches() {
+        let state = "BlockState(id: 6795, OakFence { east: false, north: true })";
+        let fence = |properties: serde_json::Value| Block {
+            id: "minecraft:oak_fence".to_string(),
+            properties: serde_json::from_value(properties).unwrap(),
+        };
+        assert!(state_matches(state, &fence(serde_json::json!({}))));
+        assert!(state_matches(
+            state,
+            &fence(serde_json::json!({ "north": "true" }))
+        ));
+        assert!(!state_matches(
+            state,
+            &fence(serde_json::json!({ "east": true }))
+        ));
+        assert!(!state_matches(
+            state,
+            &Block {
+                id: "minecraft:lava".to_string(),
+                properties: HashMap::new(),
+            }
+        ));
+    }
+
+    #[test]
+    fn test_state_matches_numeric_properties() {
+        let wire = "BlockState(id: 3000, RedstoneWire { east: None, north: Side, power: _15, south: None, west: Up })";
+        let dust = |properties: serde_json::Value| Block {
+            id: "minecraft:redstone_wire".to_string(),
+            properties: serde_json::from_value(properties).unwrap(),
+        };
+        assert!(state_matches(
+            wire,
+            &dust(serde_json::json!({ "power": 15 }))
+        ));
+        assert!(state_matches(
+            wire,
+            &dust(serde_json::json!({ "power": "15" }))
+        ));
+        // Not a prefix match: power 1 is not power 15
+        assert!(!state_matches(
+            wire,
+            &dust(serde_json::json!({ "power": 1 }))
+        ));
+
+        let water = "BlockState(id: 86, Water { level: _0 })";
+        let level = |value: serde_json::Value| Block {
+            id: "minecraft:water".to_string(),
+            properties: [("level".to_string(), value)].into_iter().collect(),
+        };
+        assert!(state_matches(water, &level(serde_json::json!(0))));
+        assert!(!state_matches(water, &level(serde_json::json!(10))));
+    }
+
+    #[test]
+    fn test_property_comparisons() {
+        let actual: HashMap<String, String> = [
+            ("power".to_string(), "7".to_string()),
+            ("facing".to_string(), "North".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let matches =
+            |name: &str, expected: serde_json::Value| property_matches(&actual, name, &expected);
+        assert!(matches("power", serde_json::json!({ ">=": 5 })));
+        assert!(matches("power", serde_json::json!({ "<=": "7" })));
+        assert!(matches("power", serde_json::json!({ "!=": 0 })));
+        assert!(!matches("power", serde_json::json!({ ">": 7 })));
+        assert!(!matches("power", serde_json::json!({ "<": 7 })));
+        assert!(matches("power", serde_json::json!({ "==": 7 })));
+        // Non-numeric values only support == and !=
+        assert!(matches("facing", serde_json::json!({ "==": "north" })));
+        assert!(matches("facing", serde_json::json!({ "!=": "south" })));
+        assert!(!matches("facing", serde_json::json!({ ">": "east" })));
+
+        assert_eq!(expectation_label(&serde_json::json!({ ">=": 5 })), ">=5");
+        assert_eq!(
+            property_label("power", &serde_json::json!({ ">=": 5 })),
+            "power>=5"
+        );
+        assert_eq!(property_label("power", &serde_json::json!(15)), "power=15");
+        assert!(property_expectation(&serde_json::json!({ "=>": 5 })).is_err());
+        assert!(property_expectation(&serde_json::json!({ ">": 1, "<": 9 })).is_err());
+    }
+
+    #[test]
+    fn test_state_matches_enum_properties() {
+        let state = "BlockState(id: 1, OakStairs { facing: North, half: Bottom, shape: OuterLeft, waterlogged: false })";
+        let stairs = |properties: serde_json::Value| Block {
+            id: "minecraft:oak_stairs".to_string(),
+            properties: serde_json::from_value(properties).unwrap(),
+        };
+        assert!(state_matches(
+            state,
+            &stairs(serde_json::json!({ "facing": "north", "shape": "outer_left" }))
+        ));
+        assert!(state_matches(
+            state,
+            &stairs(serde_json::json!({ "FACING": "NORTH", "waterlogged": false }))
+        ));
+        assert!(!state_matches(
+            state,
+            &stairs(serde_json::json!({ "facing": "south" }))
+        ));
+        assert!(!state_matches(
+            state,
+            &stairs(serde_json::json!({ "shape": "outer_right" }))
+        ));
+        // A property the block doesn't have never matches
+        assert!(!state_matches(
+            state,
+            &stairs(serde_json::json!({ "open": "false" }))
+        ));
+    }
+
+    #[test]
+    fn test_property_diff_marks_differing_keys() {
+        let expected: HashMap<String, serde_json::Value> =
+            [("east".to_string(), serde_json::json!("true"))]
+                .into_iter()
+                .collect();
+        let state = "BlockState(id: 6795, OakFence { east: false, north: true })";
+        let diff = property_diff(&expected, state, false);
+        let lines: Vec<&str> = diff.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("* east"));
+        assert!(lines[2].starts_with("  north"));
+
+        let strict = property_diff(&expected, state, true);
+        assert!(strict.lines().nth(2).unwrap().starts_with("* north"));
+    }
+
+    #[test]
+    fn test_unlisted_properties() {
+        let state = "BlockState(id: 1, OakStairs { facing: North, half: Bottom, shape: OuterLeft, waterlogged: false })";
+        let stairs = |properties: serde_json::Value| Block {
+            id: "minecraft:oak_stairs".to_string(),
+            properties: serde_json::from_value(properties).unwrap(),
+        };
+        let listed = stairs(serde_json::json!({ "facing": "north" }));
+        assert!(state_matches(state, &listed));
+        assert_eq!(unlisted_properties(state, &listed).len(), 3);
+
+        let full = stairs(serde_json::json!({
+            "facing": "north", "half": "bottom", "shape": "outer_left", "waterlogged": false
+        }));
+        assert!(unlisted_properties(state, &full).is_empty());
+        assert!(unlisted_properties("BlockState(id: 1, Stone)", &listed).is_empty());
+    }
 }
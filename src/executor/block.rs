@@ -127,6 +127,28 @@ pub fn normalize_block_name(name: &str) -> String {
         .replace('_', "")
 }
 
+/// Blocks whose contents are an `Items` list, as `data get block` reads it
+const CONTAINER_BLOCKS: &[&str] = &[
+    "chest",
+    "trapped_chest",
+    "barrel",
+    "hopper",
+    "dispenser",
+    "dropper",
+    "furnace",
+    "blast_furnace",
+    "smoker",
+    "brewing_stand",
+    "crafter",
+];
+
+/// Whether a block id (with or without namespace and state) is a container with `Items`
+pub fn is_container(block: &str) -> bool {
+    let id = block.trim_start_matches("minecraft:");
+    let id = id.split('[').next().unwrap_or(id);
+    CONTAINER_BLOCKS.contains(&id) || id.ends_with("shulker_box")
+}
+
 /// Check if actual block matches expected block name
 pub fn block_matches(actual: &str, expected: &str) -> bool {
     let actual_lower = actual.to_lowercase();
@@ -135,10 +157,94 @@ pub fn block_matches(actual: &str, expected: &str) -> bool {
         || actual_lower.replace('_', "").contains(&expected_normalized)
 }
 
+/// Normalize a property value for comparison, whether it comes from a test or from Azalea
+/// Azalea prints enum values in PascalCase and numbers with a leading `_`, so
+/// "OuterLeft", "outer_left" and "\"outer_left\"" all become "outerleft", and "_15" becomes "15".
+pub fn normalize_property_value(value: &str) -> String {
+    value
+        .trim()
+        .trim_matches('"')
+        .trim_start_matches('_')
+        .to_lowercase()
+        .replace('_', "")
+}
+
+/// Extract every property from an Azalea block state debug string, names lowercased
+/// Input: "BlockState(id: 6795, OakFence { east: false, north: true, power: _15 })"
+/// Output: [("east", "false"), ("north", "true"), ("power", "15")]
+pub fn extract_all_properties(block_state_str: &str) -> Vec<(String, String)> {
+    let Some(start) = block_state_str.find('{') else {
+        return Vec::new();
+    };
+    let end = block_state_str.rfind('}').unwrap_or(block_state_str.len());
+    if end <= start {
+        return Vec::new();
+    }
+
+    block_state_str[start + 1..end]
+        .split(',')
+        .filter_map(|part| part.split_once(':'))
+        .map(|(name, value)| {
+            (
+                name.trim().to_lowercase(),
+                value.trim().trim_matches('_').to_string(),
+            )
+        })
+        .filter(|(name, value)| !name.is_empty() && !value.is_empty())
+        .collect()
+}
+
+/// Every property of a block state by lowercased name, see `extract_all_properties`
+pub fn block_properties(block_state_str: &str) -> HashMap<String, String> {
+    extract_all_properties(block_state_str)
+        .into_iter()
+        .collect()
+}
+
+/// One property of a block state by name (case-insensitive), see `extract_all_properties`
+/// Input: "BlockState(id: 5626, Lever { face: Wall, facing: North, powered: true })", "powered"
+/// Output: Some("true")
+pub fn block_state_property(block_state_str: &str, property: &str) -> Option<String> {
+    block_properties(block_state_str).remove(&property.to_lowercase())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_block_state_property_present() {
+        let state = "BlockState(id: 5626, Lever { face: Wall, facing: North, powered: true })";
+        assert_eq!(
+            block_state_property(state, "powered"),
+            Some("true".to_string())
+        );
+        assert_eq!(
+            block_state_property(state, "Facing"),
+            Some("North".to_string())
+        );
+    }
+
+    #[test]
+    fn test_block_state_property_missing() {
+        assert_eq!(
+            block_state_property("BlockState(id: 1, Stone)", "powered"),
+            None
+        );
+        let lever = "BlockState(id: 5626, Lever { face: Wall, facing: North, powered: true })";
+        assert_eq!(block_state_property(lever, "waterlogged"), None);
+        // Whole names only: "power" is not a prefix match for "powered"
+        assert_eq!(block_state_property(lever, "power"), None);
+    }
+
+    #[test]
+    fn test_normalize_property_value() {
+        assert_eq!(normalize_property_value("OuterLeft"), "outerleft");
+        assert_eq!(normalize_property_value("outer_left"), "outerleft");
+        assert_eq!(normalize_property_value("\"north\""), "north");
+        assert_eq!(normalize_property_value("_15"), "15");
+    }
+
     #[test]
     fn test_extract_block_id_simple() {
         let input = "BlockState(id: 1, Stone)";
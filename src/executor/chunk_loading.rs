@@ -0,0 +1,312 @@
+//! Chunk loading - keeps the chunks under every test area loaded while the test runs, so tests
+//! far out on the grid tick, clean up and read like tests next to spawn
+
+use anyhow::Result;
+use colored::Colorize;
+use flint_core::test_spec::TestSpec;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant};
+
+use super::TestExecutor;
+use super::cleanup::{bounding, world_corners};
+
+/// How long the bot's view may take to fill in after teleporting to the test areas
+const CHUNK_LOAD_TIMEOUT_MS: u64 = 10_000;
+const CHUNK_LOAD_POLL_MS: u64 = 100;
+
+/// How test chunks are kept loaded (`--keep-chunks-loaded`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ChunkLoading {
+    /// Force-load them if the server allows `/forceload`, otherwise teleport the bot (default)
+    #[default]
+    Auto,
+    /// Only force-load them
+    Forceload,
+    /// Only teleport the bot over the test areas and wait for their chunks to arrive
+    Teleport,
+    /// Leave chunk loading to the server
+    Off,
+}
+
+/// Most chunks one `forceload add` may mark
+pub const MAX_FORCELOAD_CHUNKS: i32 = 256;
+
+/// Chunk coordinates (x, z)
+pub type ChunkPos = [i32; 2];
+
+/// Chunks a world region (min, max) lies in
+fn region_chunks((min, max): ([i32; 3], [i32; 3])) -> impl Iterator<Item = ChunkPos> {
+    (min[0] >> 4..=max[0] >> 4).flat_map(move |x| (min[2] >> 4..=max[2] >> 4).map(move |z| [x, z]))
+}
+
+/// Cover a set of chunks with rectangles of at most MAX_FORCELOAD_CHUNKS, as (min, max) corners
+/// Rows along x are cut into runs, then equal runs on neighbouring rows are stacked along z.
+pub fn chunk_rects(chunks: &BTreeSet<ChunkPos>) -> Vec<(ChunkPos, ChunkPos)> {
+    let mut rows: BTreeMap<i32, Vec<i32>> = BTreeMap::new();
+    for [x, z] in chunks {
+        rows.entry(*z).or_default().push(*x);
+    }
+
+    // (x0, x1) -> (z0, z1) of the rectangle still growing along z
+    let mut open: BTreeMap<(i32, i32), (i32, i32)> = BTreeMap::new();
+    let mut rects = Vec::new();
+    for (z, mut xs) in rows {
+        xs.sort_unstable();
+        let mut runs: Vec<(i32, i32)> = Vec::new();
+        for x in xs {
+            match runs.last_mut() {
+                Some((x0, x1)) if *x1 + 1 == x && x - *x0 < MAX_FORCELOAD_CHUNKS => *x1 = x,
+                _ => runs.push((x, x)),
+            }
+        }
+        let mut still_open = BTreeMap::new();
+        for (x0, x1) in runs {
+            let width = x1 - x0 + 1;
+            let span = match open.remove(&(x0, x1)) {
+                Some((z0, z1)) if z1 + 1 == z && width * (z - z0 + 1) <= MAX_FORCELOAD_CHUNKS => {
+                    (z0, z)
+                }
+                Some((z0, z1)) => {
+                    rects.push(([x0, z0], [x1, z1]));
+                    (z, z)
+                }
+                None => (z, z),
+            };
+            still_open.insert((x0, x1), span);
+        }
+        // Runs that didn't continue on this row are finished
+        for ((x0, x1), (z0, z1)) in std::mem::replace(&mut open, still_open) {
+            rects.push(([x0, z0], [x1, z1]));
+        }
+    }
+    for ((x0, x1), (z0, z1)) in open {
+        rects.push(([x0, z0], [x1, z1]));
+    }
+    rects.sort_unstable();
+    rects
+}
+
+/// `forceload add|remove` for a chunk rectangle, in block coordinates
+fn forceload_command(action: &str, (min, max): (ChunkPos, ChunkPos)) -> String {
+    format!(
+        "forceload {} {} {} {} {}",
+        action,
+        min[0] * 16,
+        min[1] * 16,
+        max[0] * 16 + 15,
+        max[1] * 16 + 15
+    )
+}
+
+impl TestExecutor {
+    /// Make sure the chunks under these tests' cleanup regions are loaded before the areas are
+    /// touched, with whichever mechanism `--keep-chunks-loaded` allows and the server supports
+    pub(super) async fn ensure_loaded(
+        &mut self,
+        tests_with_offsets: &[(TestSpec, [i32; 3])],
+    ) -> Result<()> {
+        let forceload = match self.chunk_loading {
+            ChunkLoading::Off => return Ok(()),
+            ChunkLoading::Teleport => false,
+            ChunkLoading::Auto | ChunkLoading::Forceload => self.forceload_supported().await?,
+        };
+        if forceload {
+            self.forceload_test_areas(tests_with_offsets).await
+        } else if self.chunk_loading == ChunkLoading::Forceload {
+            Ok(())
+        } else {
+            self.teleport_to_test_areas(tests_with_offsets).await
+        }
+    }
+
+    /// Whether the server lets the bot force-load chunks, asked once per connection
+    async fn forceload_supported(&mut self) -> Result<bool> {
+        if let Some(supported) = self.forceload_supported {
+            return Ok(supported);
+        }
+        let supported = match self.bot.send_command_checked("forceload query").await {
+            Ok(()) => true,
+            Err(_) if self.bot.is_connected() => {
+                let instead = if self.chunk_loading == ChunkLoading::Auto {
+                    "teleporting the bot to the tests instead"
+                } else {
+                    "test chunks load only while the bot is near them"
+                };
+                eprintln!(
+                    "{} The server rejected /forceload; {}",
+                    "Warning:".yellow().bold(),
+                    instead
+                );
+                false
+            }
+            Err(e) => return Err(e),
+        };
+        self.forceload_supported = Some(supported);
+        Ok(supported)
+    }
+
+    /// Force-load the chunks under these tests' cleanup regions
+    /// Chunks are counted per test, so one shared by neighbours stays loaded until both finish.
+    pub(super) async fn forceload_test_areas(
+        &mut self,
+        tests_with_offsets: &[(TestSpec, [i32; 3])],
+    ) -> Result<()> {
+        if !self.forceload_supported().await? {
+            return Ok(());
+        }
+        let mut added = BTreeSet::new();
+        for (test, offset) in tests_with_offsets {
+            for chunk in region_chunks(world_corners(test, *offset)) {
+                let users = self.forced_chunks.entry(chunk).or_default();
+                *users += 1;
+                if *users == 1 {
+                    added.insert(chunk);
+                }
+            }
+        }
+        let rects = chunk_rects(&added);
+        for rect in &rects {
+            self.bot
+                .send_command(&forceload_command("add", *rect))
+                .await?;
+        }
+        tracing::debug!(
+            chunks = added.len(),
+            commands = rects.len(),
+            "Force-loaded test chunks"
+        );
+        Ok(())
+    }
+
+    /// Teleport the bot above the middle of these tests' areas, unless it already has every chunk
+    /// under them, and wait until it does. Chunks beyond the server's view distance never arrive,
+    /// so after CHUNK_LOAD_TIMEOUT_MS the run goes on with a warning.
+    async fn teleport_to_test_areas(
+        &mut self,
+        tests_with_offsets: &[(TestSpec, [i32; 3])],
+    ) -> Result<()> {
+        let Some(region) = tests_with_offsets
+            .iter()
+            .map(|(test, offset)| world_corners(test, *offset))
+            .reduce(bounding)
+        else {
+            return Ok(());
+        };
+        if self.missing_chunks(region).await? == 0 {
+            return Ok(());
+        }
+        let (min, max) = region;
+        let target = [(min[0] + max[0]) / 2, max[1] + 1, (min[2] + max[2]) / 2];
+        tracing::debug!(pos = ?target, "Teleporting bot to load test chunks");
+        self.bot
+            .send_command(&format!("tp @s {} {} {}", target[0], target[1], target[2]))
+            .await?;
+
+        let deadline = Instant::now() + Duration::from_millis(CHUNK_LOAD_TIMEOUT_MS);
+        loop {
+            let missing = self.missing_chunks(region).await?;
+            if missing == 0 {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                eprintln!(
+                    "{} {} chunks of the test areas still aren't loaded after teleporting; the grid may be wider than the server's view distance",
+                    "Warning:".yellow().bold(),
+                    missing
+                );
+                return Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(CHUNK_LOAD_POLL_MS)).await;
+        }
+    }
+
+    /// How many chunks of a world region the bot has no blocks for
+    async fn missing_chunks(&self, (min, max): ([i32; 3], [i32; 3])) -> Result<usize> {
+        let mut missing = 0;
+        for [x, z] in region_chunks((min, max)) {
+            if self
+                .bot
+                .get_block([x * 16, min[1], z * 16])
+                .await?
+                .is_none()
+            {
+                missing += 1;
+            }
+        }
+        Ok(missing)
+    }
+
+    /// Stop force-loading the chunks of the tests at `indices` that no other running test uses
+    pub(super) async fn release_test_chunks(
+        &mut self,
+        tests_with_offsets: &[(TestSpec, [i32; 3])],
+        indices: &[usize],
+    ) -> Result<()> {
+        if self.forceload_supported != Some(true) {
+            return Ok(());
+        }
+        let mut released = BTreeSet::new();
+        for &idx in indices {
+            let (test, offset) = &tests_with_offsets[idx];
+            for chunk in region_chunks(world_corners(test, *offset)) {
+                let Some(users) = self.forced_chunks.get_mut(&chunk) else {
+                    continue;
+                };
+                *users -= 1;
+                if *users == 0 {
+                    self.forced_chunks.remove(&chunk);
+                    released.insert(chunk);
+                }
+            }
+        }
+        // Nothing to talk to after losing the server
+        if !self.bot.is_connected() {
+            return Ok(());
+        }
+        for rect in chunk_rects(&released) {
+            self.bot
+                .send_command(&forceload_command("remove", rect))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_rects() {
+        // A 3x2 block of chunks and a lone chunk
+        let mut chunks: BTreeSet<ChunkPos> =
+            (0..3).flat_map(|x| (-1..1).map(move |z| [x, z])).collect();
+        chunks.insert([10, 10]);
+        assert_eq!(
+            chunk_rects(&chunks),
+            vec![([0, -1], [2, 0]), ([10, 10], [10, 10])]
+        );
+
+        // 20x20 chunks need more than one command, and every chunk is covered exactly once
+        let square: BTreeSet<ChunkPos> =
+            (0..20).flat_map(|x| (0..20).map(move |z| [x, z])).collect();
+        let rects = chunk_rects(&square);
+        assert!(rects.len() > 1);
+        let mut covered = 0;
+        for (min, max) in &rects {
+            let area = (max[0] - min[0] + 1) * (max[1] - min[1] + 1);
+            assert!(area <= MAX_FORCELOAD_CHUNKS);
+            covered += area;
+        }
+        assert_eq!(covered, 400);
+    }
+
+    #[test]
+    fn test_region_chunks_negative() {
+        let chunks: Vec<ChunkPos> = region_chunks(([-1, 64, 15], [16, 70, 16])).collect();
+        assert_eq!(
+            chunks,
+            vec![[-1, 0], [-1, 1], [0, 0], [0, 1], [1, 0], [1, 1]]
+        );
+    }
+}
@@ -0,0 +1,275 @@
+//! Test area cleanup - clearing, leaving or restoring each test's cleanup region around a run,
+//! optionally merging nearby regions into fewer fills (`--fill-cleanup-batch`)
+
+use anyhow::Result;
+use flint_core::test_spec::TestSpec;
+use std::collections::{HashMap, HashSet};
+
+use super::actions::{MAX_FILL_VOLUME, apply_offset, fill_commands, setblock_command};
+use super::{COMMAND_DELAY_MS, TestExecutor};
+
+/// Blocks outside every test region that a merged cleanup fill may also clear
+const FILL_MERGE_SLACK: i64 = 1024;
+
+/// What happens to a test's cleanup region around the run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum CleanupMode {
+    /// Fill the region with air before and after the test (default)
+    #[default]
+    Air,
+    /// Leave the region untouched, for tests that build everything they check
+    Skip,
+    /// Save the region's blocks, clear it for the test, and put the blocks back afterwards
+    Restore,
+}
+
+/// Non-air blocks of a cleanup region saved before the run, in world coordinates
+pub type SavedArea = HashMap<[i32; 3], String>;
+
+/// World corners of a test's cleanup region, as (min, max)
+pub(super) fn world_corners(test: &TestSpec, offset: [i32; 3]) -> ([i32; 3], [i32; 3]) {
+    let region = test.cleanup_region();
+    let (a, b) = (
+        apply_offset(region[0], offset),
+        apply_offset(region[1], offset),
+    );
+    (
+        [0, 1, 2].map(|axis| a[axis].min(b[axis])),
+        [0, 1, 2].map(|axis| a[axis].max(b[axis])),
+    )
+}
+
+type Region = ([i32; 3], [i32; 3]);
+
+fn volume((min, max): Region) -> i64 {
+    (0..3)
+        .map(|axis| (max[axis] as i64 - min[axis] as i64 + 1).max(0))
+        .product()
+}
+
+pub(super) fn bounding(a: Region, b: Region) -> Region {
+    (
+        [0, 1, 2].map(|axis| a.0[axis].min(b.0[axis])),
+        [0, 1, 2].map(|axis| a.1[axis].max(b.1[axis])),
+    )
+}
+
+pub(super) fn overlap(a: Region, b: Region) -> i64 {
+    volume((
+        [0, 1, 2].map(|axis| a.0[axis].max(b.0[axis])),
+        [0, 1, 2].map(|axis| a.1[axis].min(b.1[axis])),
+    ))
+}
+
+/// Merge regions whose bounding box adds at most FILL_MERGE_SLACK blocks outside them
+/// and still fits in one fill; regions that don't qualify are kept as they are.
+/// A bounding box that overlaps one of the `keep` regions (tests still running) is never used.
+fn merge_regions(mut regions: Vec<Region>, keep: &[Region]) -> Vec<Region> {
+    let mut merged = true;
+    while merged {
+        merged = false;
+        'search: for i in 0..regions.len() {
+            for j in i + 1..regions.len() {
+                let (a, b) = (regions[i], regions[j]);
+                let union = bounding(a, b);
+                let covered = volume(a) + volume(b) - overlap(a, b);
+                if volume(union) <= MAX_FILL_VOLUME
+                    && volume(union) - covered <= FILL_MERGE_SLACK
+                    && keep.iter().all(|kept| overlap(union, *kept) == 0)
+                {
+                    regions[i] = union;
+                    regions.swap_remove(j);
+                    merged = true;
+                    break 'search;
+                }
+            }
+        }
+    }
+    regions
+}
+
+impl TestExecutor {
+    /// Fill a world region with air, split to stay under the fill limit
+    async fn fill_air(&self, (min, max): Region) -> Result<()> {
+        for command in fill_commands(min, max, "air") {
+            self.bot.send_command(&command).await?;
+        }
+        Ok(())
+    }
+
+    /// Whether cleanup fills of several tests are merged (`--fill-cleanup-batch`, air mode only)
+    fn batch_cleanup(&self) -> bool {
+        self.fill_cleanup_batch && self.cleanup_mode == CleanupMode::Air
+    }
+
+    /// Pause after cleaning up `count` tests: once per batch, or once per test
+    pub(super) fn cleanup_pause_ms(&self, count: usize) -> u64 {
+        if self.batch_cleanup() {
+            COMMAND_DELAY_MS
+        } else {
+            COMMAND_DELAY_MS * count as u64
+        }
+    }
+
+    /// Get every test's area ready before tick 0, see `prepare_test_area`
+    pub(super) async fn prepare_test_areas(
+        &mut self,
+        tests_with_offsets: &[(TestSpec, [i32; 3])],
+    ) -> Result<Vec<Option<SavedArea>>> {
+        self.ensure_loaded(tests_with_offsets).await?;
+        if self.batch_cleanup() {
+            self.fill_batch(tests_with_offsets, 0..tests_with_offsets.len())
+                .await?;
+            return Ok(vec![None; tests_with_offsets.len()]);
+        }
+        let mut saved_areas = Vec::with_capacity(tests_with_offsets.len());
+        for (test, offset) in tests_with_offsets {
+            saved_areas.push(self.prepare_test_area(test, *offset).await?);
+        }
+        Ok(saved_areas)
+    }
+
+    /// Clean up the areas of the tests at `indices` once they have finished, see `finish_test_area`,
+    /// and release their chunks
+    pub(super) async fn finish_test_areas(
+        &mut self,
+        tests_with_offsets: &[(TestSpec, [i32; 3])],
+        indices: &[usize],
+        saved_areas: &[Option<SavedArea>],
+    ) -> Result<()> {
+        if self.batch_cleanup() {
+            self.fill_batch(tests_with_offsets, indices.iter().copied())
+                .await?;
+        } else {
+            for &idx in indices {
+                let (test, offset) = &tests_with_offsets[idx];
+                self.finish_test_area(test, *offset, saved_areas[idx].as_ref())
+                    .await?;
+            }
+        }
+        self.release_test_chunks(tests_with_offsets, indices).await
+    }
+
+    /// Clear the areas of several tests with merged fills
+    /// Merged fills stay clear of every other test's area, which may still be running.
+    async fn fill_batch(
+        &self,
+        tests_with_offsets: &[(TestSpec, [i32; 3])],
+        indices: impl Iterator<Item = usize>,
+    ) -> Result<()> {
+        let indices: HashSet<usize> = indices.collect();
+        let (regions, others): (Vec<_>, Vec<_>) = tests_with_offsets
+            .iter()
+            .enumerate()
+            .map(|(idx, (test, offset))| (idx, world_corners(test, *offset)))
+            .partition(|(idx, _)| indices.contains(idx));
+        let regions: Vec<Region> = regions.into_iter().map(|(_, region)| region).collect();
+        let others: Vec<Region> = others.into_iter().map(|(_, region)| region).collect();
+        let count = regions.len();
+        let merged = merge_regions(regions, &others);
+        tracing::debug!(tests = count, fills = merged.len(), "Batched cleanup fills");
+        for region in merged {
+            self.fill_air(region).await?;
+        }
+        Ok(())
+    }
+
+    /// Get a test's area ready before tick 0
+    /// Returns the blocks to put back afterwards in restore mode.
+    pub(super) async fn prepare_test_area(
+        &mut self,
+        test: &TestSpec,
+        offset: [i32; 3],
+    ) -> Result<Option<SavedArea>> {
+        let corners = world_corners(test, offset);
+        let saved = match self.cleanup_mode {
+            CleanupMode::Skip => return Ok(None),
+            CleanupMode::Air => None,
+            CleanupMode::Restore => {
+                let saved = self.scan_region(corners.0, corners.1).await?;
+                tracing::debug!(test = %test.name, blocks = saved.len(), "Saved test area");
+                Some(saved)
+            }
+        };
+        self.fill_air(corners).await?;
+        Ok(saved)
+    }
+
+    /// Clear a test's area again for a step-back replay (left alone in skip mode)
+    pub(super) async fn reset_test_area(
+        &mut self,
+        test: &TestSpec,
+        offset: [i32; 3],
+    ) -> Result<()> {
+        if self.cleanup_mode == CleanupMode::Skip {
+            return Ok(());
+        }
+        self.fill_air(world_corners(test, offset)).await
+    }
+
+    /// Clean up a test's area once it has finished, putting `saved` blocks back in restore mode
+    pub(super) async fn finish_test_area(
+        &mut self,
+        test: &TestSpec,
+        offset: [i32; 3],
+        saved: Option<&SavedArea>,
+    ) -> Result<()> {
+        if self.cleanup_mode == CleanupMode::Skip {
+            return Ok(());
+        }
+        self.fill_air(world_corners(test, offset)).await?;
+        let Some(saved) = saved.filter(|_| self.cleanup_mode == CleanupMode::Restore) else {
+            return Ok(());
+        };
+
+        // Bottom-up, so sand and gravel land on what was under them
+        let mut blocks: Vec<(&[i32; 3], &String)> = saved.iter().collect();
+        blocks.sort_by_key(|(pos, _)| (pos[1], pos[0], pos[2]));
+        for (pos, block) in blocks {
+            self.bot
+                .send_command(&setblock_command(*pos, block))
+                .await?;
+        }
+        tracing::debug!(test = %test.name, blocks = saved.len(), "Restored test area");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_regions() {
+        // Two 4x4x4 areas one block apart merge (16 gap blocks), a far one doesn't
+        let merged = merge_regions(
+            vec![
+                ([0, 64, 0], [3, 67, 3]),
+                ([5, 64, 0], [8, 67, 3]),
+                ([500, 64, 0], [503, 67, 3]),
+            ],
+            &[],
+        );
+        assert_eq!(merged.len(), 2);
+        assert!(merged.contains(&([0, 64, 0], [8, 67, 3])));
+
+        // Regions that together exceed one fill stay separate
+        let large = merge_regions(
+            vec![([0, 0, 0], [31, 31, 19]), ([0, 0, 20], [31, 31, 39])],
+            &[],
+        );
+        assert_eq!(large.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_regions_spares_running_test_between() {
+        // Two finished areas close enough to merge, with a running test's area in the gap
+        let finished = vec![([0, 64, 0], [3, 67, 3]), ([7, 64, 0], [10, 67, 3])];
+        let running = ([5, 64, 1], [5, 64, 1]);
+        assert_eq!(merge_regions(finished.clone(), &[]).len(), 1);
+
+        let merged = merge_regions(finished.clone(), &[running]);
+        assert_eq!(merged.len(), 2);
+        assert!(merged.iter().all(|region| overlap(*region, running) == 0));
+    }
+}
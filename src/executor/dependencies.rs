@@ -0,0 +1,135 @@
+//! Test dependency resolution - orders tests so prerequisites run first
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Group tests into dependency layers
+/// Input: (test_name, dependencies) per test
+/// Output: layers of test indices, where every test only depends on tests in earlier layers.
+/// Dependencies on tests outside the given set are ignored. Errors on a dependency cycle.
+pub fn dependency_layers(tests: &[(&str, &[String])]) -> Result<Vec<Vec<usize>>> {
+    let index: HashMap<&str, usize> = tests
+        .iter()
+        .enumerate()
+        .map(|(idx, (name, _))| (*name, idx))
+        .collect();
+    let deps: Vec<Vec<usize>> = tests
+        .iter()
+        .map(|(_, dependencies)| {
+            dependencies
+                .iter()
+                .filter_map(|dep| index.get(dep.as_str()).copied())
+                .collect()
+        })
+        .collect();
+
+    let mut placed = vec![false; tests.len()];
+    let mut remaining = tests.len();
+    let mut layers = Vec::new();
+
+    while remaining > 0 {
+        let layer: Vec<usize> = (0..tests.len())
+            .filter(|&idx| !placed[idx] && deps[idx].iter().all(|&dep| placed[dep]))
+            .collect();
+
+        if layer.is_empty() {
+            let cycle = find_cycle(&deps, &placed);
+            let names: Vec<&str> = cycle.iter().map(|&idx| tests[idx].0).collect();
+            anyhow::bail!("Dependency cycle detected: {}", names.join(" -> "));
+        }
+
+        for &idx in &layer {
+            placed[idx] = true;
+        }
+        remaining -= layer.len();
+        layers.push(layer);
+    }
+
+    Ok(layers)
+}
+
+/// Test indices in an order where every test comes after its dependencies
+/// The layers flattened, so a run split into chunks still judges prerequisites first.
+pub fn dependency_order(tests: &[(&str, &[String])]) -> Result<Vec<usize>> {
+    Ok(dependency_layers(tests)?.into_iter().flatten().collect())
+}
+
+/// Walk unplaced dependencies until a test repeats, returning the cycle (first test repeated at the end)
+/// Every unplaced test has at least one unplaced dependency, so the walk always finds one
+fn find_cycle(deps: &[Vec<usize>], placed: &[bool]) -> Vec<usize> {
+    let Some(start) = placed.iter().position(|p| !p) else {
+        return Vec::new();
+    };
+
+    let mut path = vec![start];
+    let mut current = start;
+    loop {
+        let Some(&next) = deps[current].iter().find(|&&dep| !placed[dep]) else {
+            return path;
+        };
+        if let Some(pos) = path.iter().position(|&idx| idx == next) {
+            let mut cycle = path[pos..].to_vec();
+            cycle.push(next);
+            return cycle;
+        }
+        path.push(next);
+        current = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deps(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_layers_respect_dependencies() {
+        let furnace = deps(&[]);
+        let smelting = deps(&["furnace_setup"]);
+        let hopper = deps(&["smelting", "furnace_setup"]);
+        let tests = [
+            ("hopper", hopper.as_slice()),
+            ("smelting", smelting.as_slice()),
+            ("furnace_setup", furnace.as_slice()),
+        ];
+        let layers = dependency_layers(&tests).unwrap();
+        assert_eq!(layers, vec![vec![2], vec![1], vec![0]]);
+    }
+
+    #[test]
+    fn test_order_puts_dependencies_first() {
+        let furnace = deps(&[]);
+        let smelting = deps(&["furnace_setup"]);
+        let lamp = deps(&[]);
+        let tests = [
+            ("smelting", smelting.as_slice()),
+            ("furnace_setup", furnace.as_slice()),
+            ("lamp", lamp.as_slice()),
+        ];
+        assert_eq!(dependency_order(&tests).unwrap(), vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn test_unknown_dependencies_are_ignored() {
+        let lever = deps(&["basic_block_placement"]);
+        let tests = [("lever_basic", lever.as_slice())];
+        assert_eq!(dependency_layers(&tests).unwrap(), vec![vec![0]]);
+    }
+
+    #[test]
+    fn test_cycle_is_reported() {
+        let a = deps(&["b"]);
+        let b = deps(&["a"]);
+        let c = deps(&[]);
+        let tests = [
+            ("a", a.as_slice()),
+            ("b", b.as_slice()),
+            ("c", c.as_slice()),
+        ];
+        let err = dependency_layers(&tests).unwrap_err().to_string();
+        assert!(err.contains("a -> b -> a"), "{}", err);
+    }
+}
@@ -0,0 +1,125 @@
+//! Failure dumps (`--dump-on-failure`) - the blocks of a failing test's area at its first failure,
+//! written before cleanup so CI artifacts show what the world looked like
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use flint_core::results::AssertFailure;
+use flint_core::test_spec::TestSpec;
+use std::path::{Path, PathBuf};
+
+use super::TestExecutor;
+use super::actions::apply_offset;
+use crate::snapshot::{self, Snapshot};
+
+/// Dump file of a failing test in `dir`
+fn dump_path(dir: &Path, test_name: &str) -> PathBuf {
+    dir.join(format!("{}.failure.json", snapshot::file_name(test_name)))
+}
+
+/// JSON document of one dump; positions in `failure` and `area` are local to `origin`
+fn dump_document(
+    test_name: &str,
+    failure: &AssertFailure,
+    origin: [i32; 3],
+    area: &Snapshot,
+) -> serde_json::Value {
+    serde_json::json!({
+        "test": test_name,
+        "tick": failure.tick,
+        "failure": {
+            "position": failure.position,
+            "expected": String::from(&failure.expected),
+            "actual": String::from(&failure.actual),
+            "message": failure.error_message,
+        },
+        "origin": origin,
+        "area": area,
+    })
+}
+
+impl TestExecutor {
+    /// Dump a test's cleanup region to the `--dump-on-failure` directory, if one is set
+    /// A dump that can't be written is a warning, not a test failure.
+    pub(super) async fn dump_failure(
+        &self,
+        test: &TestSpec,
+        offset: [i32; 3],
+        failure: &AssertFailure,
+    ) {
+        let Some(dir) = self.dump_dir.as_deref() else {
+            return;
+        };
+        match self.write_failure_dump(dir, test, offset, failure).await {
+            Ok(path) => {
+                tracing::info!(test = %test.name, path = %path.display(), "Wrote failure dump")
+            }
+            Err(e) => eprintln!(
+                "{} [{}] failure dump: {:#}",
+                "Warning:".yellow().bold(),
+                test.name,
+                e
+            ),
+        }
+    }
+
+    async fn write_failure_dump(
+        &self,
+        dir: &Path,
+        test: &TestSpec,
+        offset: [i32; 3],
+        failure: &AssertFailure,
+    ) -> Result<PathBuf> {
+        let region = test.cleanup_region();
+        let blocks = self
+            .scan_region(
+                apply_offset(region[0], offset),
+                apply_offset(region[1], offset),
+            )
+            .await?;
+        let area = Snapshot::from_world(region, &blocks, offset);
+
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dump_path(dir, &test.name);
+        let json =
+            serde_json::to_string_pretty(&dump_document(&test.name, failure, offset, &area))?;
+        std::fs::write(&path, json)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flint_core::results::InfoType;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_dump_document() {
+        let failure = AssertFailure {
+            tick: 12,
+            expected: InfoType::String("minecraft:redstone_lamp".to_string()),
+            actual: InfoType::String("minecraft:air".to_string()),
+            position: [1, 0, 0],
+            error_message: "Block was different".to_string(),
+            execution_time_ms: None,
+        };
+        let blocks: HashMap<[i32; 3], String> = [([100, 64, 0], "minecraft:stone".to_string())]
+            .into_iter()
+            .collect();
+        let area = Snapshot::from_world([[0, 0, 0], [2, 2, 2]], &blocks, [100, 64, 0]);
+        let document = dump_document("lamp/on", &failure, [100, 64, 0], &area);
+
+        assert_eq!(document["tick"], 12);
+        assert_eq!(document["failure"]["actual"], "minecraft:air");
+        assert_eq!(
+            document["area"]["blocks"][0]["pos"],
+            serde_json::json!([0, 0, 0])
+        );
+        assert_eq!(
+            dump_path(Path::new("dumps"), "lamp/on"),
+            PathBuf::from("dumps/lamp_on.failure.json")
+        );
+    }
+}
@@ -0,0 +1,160 @@
+//! Run events - incremental progress reported while tests execute
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+const SINK_CONNECT_TIMEOUT_MS: u64 = 2000;
+const SINK_WRITE_TIMEOUT_MS: u64 = 1000;
+
+/// A lifecycle event emitted during a test run
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum RunEvent {
+    /// Emitted once before the first test starts
+    RunStart { total: usize },
+    /// Emitted as soon as a test has finished and been judged
+    TestResult {
+        name: String,
+        success: bool,
+        tick: u32,
+    },
+    /// Emitted each time the merged timeline advances to a new tick
+    /// Only sent to the event sink, so `--format ndjson` output stays one line per test
+    Tick { tick: u32, max_tick: u32 },
+    /// Emitted when a test is not run, e.g. because a dependency did not pass
+    TestSkipped { name: String, reason: String },
+    /// Emitted once after the last test has finished
+    RunEnd {
+        total: usize,
+        passed: usize,
+        failed: usize,
+        skipped: usize,
+        duration_secs: f64,
+    },
+}
+
+impl RunEvent {
+    /// Log the event with structured fields, so `--log-format json` captures the run lifecycle
+    pub fn log(&self) {
+        match self {
+            RunEvent::RunStart { total } => tracing::debug!(total, "Run started"),
+            RunEvent::TestResult {
+                name,
+                success,
+                tick,
+            } => tracing::debug!(test = %name, success, tick, "Test finished"),
+            RunEvent::Tick { tick, max_tick } => tracing::debug!(tick, max_tick, "Tick advanced"),
+            RunEvent::TestSkipped { name, reason } => {
+                tracing::debug!(test = %name, reason = %reason, "Test skipped")
+            }
+            RunEvent::RunEnd {
+                total,
+                passed,
+                failed,
+                skipped,
+                duration_secs,
+            } => tracing::debug!(
+                total,
+                passed,
+                failed,
+                skipped,
+                duration_secs,
+                "Run finished"
+            ),
+        }
+    }
+}
+
+/// Callback invoked for every emitted event
+pub type EventHandler = Box<dyn FnMut(&RunEvent)>;
+
+/// Open an event sink from a `tcp://host:port` or `unix:///path/to/socket` address
+fn open_sink(address: &str) -> Result<Box<dyn Write>> {
+    if let Some(addr) = address.strip_prefix("tcp://") {
+        let stream = connect_tcp_sink(addr)
+            .with_context(|| format!("Failed to connect to event sink {}", address))?;
+        stream.set_write_timeout(Some(Duration::from_millis(SINK_WRITE_TIMEOUT_MS)))?;
+        return Ok(Box::new(stream));
+    }
+    if let Some(path) = address.strip_prefix("unix://") {
+        return open_unix_sink(path)
+            .with_context(|| format!("Failed to connect to event sink {}", address));
+    }
+    anyhow::bail!(
+        "Unsupported event sink '{}', expected tcp://host:port or unix:///path",
+        address
+    )
+}
+
+/// Connect to the first address `host:port` resolves to that answers in time
+/// An unreachable sink must not hold up the run, so each attempt is bounded.
+fn connect_tcp_sink(addr: &str) -> Result<TcpStream> {
+    let timeout = Duration::from_millis(SINK_CONNECT_TIMEOUT_MS);
+    let mut last_error = None;
+    for socket_addr in addr.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&socket_addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    match last_error {
+        Some(e) => Err(e.into()),
+        None => anyhow::bail!("'{}' did not resolve to an address", addr),
+    }
+}
+
+#[cfg(unix)]
+fn open_unix_sink(path: &str) -> Result<Box<dyn Write>> {
+    let stream = std::os::unix::net::UnixStream::connect(path)?;
+    stream.set_write_timeout(Some(Duration::from_millis(SINK_WRITE_TIMEOUT_MS)))?;
+    Ok(Box::new(stream))
+}
+
+#[cfg(not(unix))]
+fn open_unix_sink(_path: &str) -> Result<Box<dyn Write>> {
+    anyhow::bail!("Unix socket event sinks are not supported on this platform")
+}
+
+/// Build a handler that streams events as JSON lines to a socket
+/// If the sink drops mid-run a warning is logged once and further events are discarded
+pub fn sink_handler(address: &str) -> Result<EventHandler> {
+    let mut sink = Some(open_sink(address)?);
+    let address = address.to_string();
+    Ok(Box::new(move |event: &RunEvent| {
+        if let Some(out) = sink.as_mut()
+            && let Err(e) = crate::format::write_ndjson_event(&mut **out, event)
+        {
+            tracing::warn!("Event sink {} dropped, no longer streaming: {}", address, e);
+            sink = None;
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_sink_rejects_unknown_scheme() {
+        let err = open_sink("http://localhost:9000").err().unwrap();
+        assert!(err.to_string().contains("Unsupported event sink"));
+    }
+
+    #[test]
+    fn test_sink_handler_streams_events() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("tcp://{}", listener.local_addr().unwrap());
+        let mut handler = sink_handler(&address).unwrap();
+        let (mut conn, _) = listener.accept().unwrap();
+
+        handler(&RunEvent::RunStart { total: 2 });
+        drop(handler);
+
+        let mut received = String::new();
+        std::io::Read::read_to_string(&mut conn, &mut received).unwrap();
+        assert_eq!(received, "{\"event\":\"run_start\",\"total\":2}\n");
+    }
+}
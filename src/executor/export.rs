@@ -0,0 +1,266 @@
+//! `.mcfunction` export - serializes a merged timeline's block changes for manual replay in-game
+
+use anyhow::{Context, Result};
+use flint_core::test_spec::{ActionType, TestSpec, TimelineEntry};
+use flint_core::timeline::TimelineAggregate;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::spec::{CheckFlags, ExtraCheck, TestOptions};
+
+use super::actions::{apply_offset, fill_commands, setblock_command};
+
+/// Namespace used when the export path isn't inside a datapack's `data/<namespace>/function/`
+const FALLBACK_NAMESPACE: &str = "flintmc";
+
+/// Function id for a file inside a datapack, e.g.
+/// `pack/data/flint/function/replay/door.mcfunction` -> `flint:replay/door`
+fn function_id(path: &Path) -> String {
+    let stem = path.with_extension("");
+    let parts: Vec<String> = stem
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+    let datapack_id = parts.windows(3).enumerate().find_map(|(idx, window)| {
+        let is_function_dir = window[2] == "function" || window[2] == "functions";
+        (window[0] == "data" && is_function_dir && idx + 3 < parts.len())
+            .then(|| format!("{}:{}", window[1], parts[idx + 3..].join("/")))
+    });
+    datapack_id.unwrap_or_else(|| {
+        format!(
+            "{}:{}",
+            FALLBACK_NAMESPACE,
+            parts.last().map(String::as_str).unwrap_or("replay")
+        )
+    })
+}
+
+/// Commands (and comments) for one timeline entry, in world coordinates
+fn entry_lines(
+    test: &TestSpec,
+    entry: &TimelineEntry,
+    offset: [i32; 3],
+    flags: &CheckFlags,
+    nbt: Option<&str>,
+) -> Vec<String> {
+    match &entry.action_type {
+        ActionType::Place { pos, block } => {
+            vec![setblock_command(
+                apply_offset(*pos, offset),
+                &format!("{}{}", block.to_command(), nbt.unwrap_or_default()),
+            )]
+        }
+        ActionType::PlaceEach { blocks } => blocks
+            .iter()
+            .map(|placement| {
+                setblock_command(
+                    apply_offset(placement.pos, offset),
+                    &placement.block.to_command(),
+                )
+            })
+            .collect(),
+        ActionType::Fill { region, with } => fill_commands(
+            apply_offset(region[0], offset),
+            apply_offset(region[1], offset),
+            &with.to_command(),
+        ),
+        ActionType::Remove { pos } => vec![setblock_command(apply_offset(*pos, offset), "air")],
+        ActionType::Assert { checks } => checks
+            .iter()
+            .map(|check| {
+                let pos = apply_offset(check.pos, offset);
+                let relation = if flags.negated.contains(&check.pos) {
+                    "is not"
+                } else {
+                    "is"
+                };
+                let exact = if flags.strict.contains(&check.pos) {
+                    " (exact properties)"
+                } else {
+                    ""
+                };
+                format!(
+                    "# [{}] assert {} {} {} {} {}{}",
+                    test.name,
+                    pos[0],
+                    pos[1],
+                    pos[2],
+                    relation,
+                    check.is.to_command(),
+                    exact
+                )
+            })
+            .collect(),
+    }
+}
+
+/// Comment text for an extra check, in world coordinates
+fn extra_check_line(check: &ExtraCheck, offset: [i32; 3]) -> String {
+    match check {
+        ExtraCheck::AssertEntities(check) => {
+            let min = apply_offset(check.region[0], offset);
+            let max = apply_offset(check.region[1], offset);
+            format!(
+                "assert {} {} in {} {} {} .. {} {} {}",
+                check.count,
+                check.entity.as_deref().unwrap_or("entities"),
+                min[0],
+                min[1],
+                min[2],
+                max[0],
+                max[1],
+                max[2]
+            )
+        }
+        ExtraCheck::AssertContainer(check) => {
+            let pos = apply_offset(check.pos, offset);
+            format!(
+                "assert container {} {} {} slot {} holds {} x{}",
+                pos[0], pos[1], pos[2], check.slot, check.item, check.count
+            )
+        }
+        ExtraCheck::AssertStructure(check) => {
+            let pos = apply_offset(check.pos, offset);
+            format!(
+                "assert {} {} {} .. (size {} {} {}) matches structure {}",
+                pos[0],
+                pos[1],
+                pos[2],
+                check.size[0],
+                check.size[1],
+                check.size[2],
+                check.structure
+            )
+        }
+        ExtraCheck::AssertLight(check) => {
+            let pos = apply_offset(check.pos, offset);
+            format!(
+                "assert light at {} {} {} is {}..{}",
+                pos[0], pos[1], pos[2], check.min, check.max
+            )
+        }
+        ExtraCheck::AssertBiome(check) => {
+            let pos = apply_offset(check.pos, offset);
+            format!(
+                "assert biome at {} {} {} is {}",
+                pos[0], pos[1], pos[2], check.biome
+            )
+        }
+        ExtraCheck::AssertFill(check) => {
+            let min = apply_offset(check.region[0], offset);
+            let max = apply_offset(check.region[1], offset);
+            format!(
+                "assert {} {} {} .. {} {} {} all {}",
+                min[0], min[1], min[2], max[0], max[1], max[2], check.block
+            )
+        }
+    }
+}
+
+/// Lines per tick of the merged timeline: block changes, with asserts and breakpoints as comments
+fn tick_lines(
+    tests_with_offsets: &[(TestSpec, [i32; 3])],
+    options: &[TestOptions],
+    extra_offset: [i32; 3],
+) -> BTreeMap<u32, Vec<String>> {
+    let aggregate = TimelineAggregate::from_tests(tests_with_offsets);
+    let mut ticks: BTreeMap<u32, Vec<String>> = BTreeMap::new();
+    for (tick, entries) in &aggregate.timeline {
+        let lines = ticks.entry(*tick).or_default();
+        for (test_idx, entry, _) in entries {
+            let (test, offset) = &tests_with_offsets[*test_idx];
+            let offset = apply_offset(*offset, extra_offset);
+            lines.extend(entry_lines(
+                test,
+                entry,
+                offset,
+                &options[*test_idx].check_flags(test, entry),
+                options[*test_idx].block_nbt_at(test, entry),
+            ));
+            for check in options[*test_idx]
+                .extra_checks_at(test, entry)
+                .unwrap_or_default()
+            {
+                lines.push(format!(
+                    "# [{}] {}",
+                    test.name,
+                    extra_check_line(check, offset)
+                ));
+            }
+        }
+    }
+    for tick in &aggregate.breakpoints {
+        ticks
+            .entry(*tick)
+            .or_default()
+            .push(format!("# breakpoint at tick {}", tick));
+    }
+    ticks
+}
+
+/// Write the merged timeline as a main `.mcfunction` plus one scheduled function per later tick
+/// Returns the written files. Scheduled files sit next to `path` as `<name>_tick_<N>.mcfunction`.
+pub fn export_mcfunction(
+    tests_with_offsets: &[(TestSpec, [i32; 3])],
+    test_options: &[TestOptions],
+    extra_offset: [i32; 3],
+    path: &Path,
+) -> Result<Vec<PathBuf>> {
+    let id = function_id(path);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "replay".to_string());
+    let names: Vec<&str> = tests_with_offsets
+        .iter()
+        .map(|(test, _)| test.name.as_str())
+        .collect();
+
+    let mut main = vec![
+        format!("# FlintMC replay of: {}", names.join(", ")),
+        format!("# Function id: {}", id),
+    ];
+    let mut written = Vec::new();
+    for (tick, lines) in tick_lines(tests_with_offsets, test_options, extra_offset) {
+        if tick == 0 {
+            main.extend(lines);
+            continue;
+        }
+        let tick_path = path.with_file_name(format!("{}_tick_{}.mcfunction", stem, tick));
+        std::fs::write(
+            &tick_path,
+            format!("# tick {}\n{}\n", tick, lines.join("\n")),
+        )
+        .with_context(|| format!("Failed to write {}", tick_path.display()))?;
+        written.push(tick_path);
+        main.push(format!("schedule function {}_tick_{} {}t", id, tick, tick));
+    }
+    std::fs::write(path, main.join("\n") + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    written.insert(0, path.to_path_buf());
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_function_id() {
+        assert_eq!(
+            function_id(Path::new("pack/data/flint/function/replay/door.mcfunction")),
+            "flint:replay/door"
+        );
+        assert_eq!(
+            function_id(Path::new("data/tests/functions/lamp.mcfunction")),
+            "tests:lamp"
+        );
+        assert_eq!(
+            function_id(Path::new("out/door.mcfunction")),
+            "flintmc:door"
+        );
+    }
+}
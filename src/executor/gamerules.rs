@@ -0,0 +1,138 @@
+//! Gamerule setup - standardizes the world before a run and restores it afterwards
+
+use crate::bot::{ChatKind, ChatMessage};
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use super::{COMMAND_DELAY_MS, TestExecutor, tick};
+
+const GAMERULE_QUERY_TIMEOUT_MS: u64 = 2000;
+
+/// Parse a `key=value` gamerule argument
+pub fn parse_gamerule(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() && !value.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(format!("expected RULE=VALUE, got '{}'", arg)),
+    }
+}
+
+/// Extract the value from a `/gamerule <rule>` query reply
+/// Input: "Gamerule doDaylightCycle is currently set to: true", "doDaylightCycle"
+/// Output: Some("true")
+fn parse_gamerule_reply(message: &str, rule: &str) -> Option<String> {
+    let prefix = format!("Gamerule {} is currently set to: ", rule);
+    let start = message.find(&prefix)? + prefix.len();
+    let value = message[start..].trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+impl TestExecutor {
+    /// Apply gamerules, remembering the previous values so they can be restored
+    /// Each original value is saved before its change is sent, so a failure partway through
+    /// still leaves every rule changed so far in `saved_gamerules` for `restore_gamerules`.
+    pub async fn apply_gamerules(&mut self, rules: &[(String, String)]) -> Result<()> {
+        for (rule, value) in rules {
+            match self.query_gamerule(rule).await? {
+                Some(original) => self.saved_gamerules.push((rule.clone(), original)),
+                None => eprintln!(
+                    "{} Could not read gamerule {}, it won't be restored",
+                    "Warning:".yellow().bold(),
+                    rule
+                ),
+            }
+            self.bot
+                .send_command_checked(&format!("gamerule {} {}", rule, value))
+                .await
+                .with_context(|| format!("Failed to set gamerule {} to {}", rule, value))?;
+            tracing::debug!(rule = %rule, value = %value, "Set gamerule");
+        }
+        Ok(())
+    }
+
+    /// Restore gamerules changed by `apply_gamerules`
+    /// Every saved rule is attempted even if one fails; the first error is returned.
+    pub async fn restore_gamerules(&mut self) -> Result<()> {
+        // Nothing to talk to after losing the server; keep the original error
+        if !self.bot.is_connected() {
+            self.saved_gamerules.clear();
+            return Ok(());
+        }
+        let mut first_error = None;
+        for (rule, value) in std::mem::take(&mut self.saved_gamerules).into_iter().rev() {
+            if let Err(e) = self
+                .bot
+                .send_command(&format!("gamerule {} {}", rule, value))
+                .await
+            {
+                tracing::warn!(rule = %rule, "Failed to restore gamerule: {:#}", e);
+                first_error.get_or_insert(
+                    e.context(format!("Failed to restore gamerule {} to {}", rule, value)),
+                );
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(COMMAND_DELAY_MS)).await;
+        }
+        first_error.map_or(Ok(()), Err)
+    }
+
+    /// Ask the server for a gamerule's current value
+    async fn query_gamerule(&mut self, rule: &str) -> Result<Option<String>> {
+        tick::drain_chat_messages(&mut self.bot, ChatKind::System).await;
+        self.bot.send_command(&format!("gamerule {}", rule)).await?;
+
+        let deadline = tokio::time::Instant::now()
+            + std::time::Duration::from_millis(GAMERULE_QUERY_TIMEOUT_MS);
+        loop {
+            let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            let Some(message) = self
+                .bot
+                .recv_chat_timeout(ChatKind::System, remaining)
+                .await
+            else {
+                return Ok(None);
+            };
+            if let ChatMessage::System(message) = message
+                && let Some(value) = parse_gamerule_reply(&message, rule)
+            {
+                return Ok(Some(value));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_gamerule() {
+        assert_eq!(
+            parse_gamerule("randomTickSpeed=0"),
+            Ok(("randomTickSpeed".to_string(), "0".to_string()))
+        );
+        assert!(parse_gamerule("randomTickSpeed").is_err());
+        assert!(parse_gamerule("=0").is_err());
+    }
+
+    #[test]
+    fn test_parse_gamerule_reply() {
+        assert_eq!(
+            parse_gamerule_reply(
+                "Gamerule doDaylightCycle is currently set to: false",
+                "doDaylightCycle"
+            ),
+            Some("false".to_string())
+        );
+        assert_eq!(
+            parse_gamerule_reply(
+                "Gamerule mobGriefing is currently set to: true",
+                "doDaylightCycle"
+            ),
+            None
+        );
+    }
+}
@@ -1,13 +1,31 @@
 //! Command handlers for interactive mode
 
 use anyhow::Result;
+use colored::Colorize;
 use flint_core::loader::TestLoader;
+use flint_core::results::AssertFailure;
 use flint_core::spatial::calculate_test_offset_default;
-use flint_core::test_spec::TestSpec;
 
-use super::{
-    COMMAND_DELAY_MS, DEFAULT_TESTS_DIR, TEST_RESULT_DELAY_MS, TestExecutor, block, recorder,
-};
+use super::actions::MAX_FILL_VOLUME;
+use super::{COMMAND_DELAY_MS, TEST_RESULT_DELAY_MS, TestExecutor, block, cleanup, recorder, snbt};
+
+/// One-line failure detail for chat
+/// Output: "[lever_basic] tick 5 at (10, 101, 10): expected powered=true, got powered=false"
+fn failure_line(test_name: &str, failure: &AssertFailure) -> String {
+    format!(
+        "[{}] tick {} at ({}, {}, {}): expected {}, got {}",
+        test_name,
+        failure.tick,
+        failure.position[0],
+        failure.position[1],
+        failure.position[2],
+        String::from(&failure.expected),
+        String::from(&failure.actual)
+    )
+}
+
+/// Chat lines per `!diff` page
+const DIFF_PAGE_SIZE: usize = 8;
 
 /// Parse command parts from a chat message
 /// Returns (command, args) if a valid command was found
@@ -37,6 +55,76 @@ pub fn parse_command(message: &str) -> Option<(String, Vec<String>)> {
     Some((command, args))
 }
 
+/// Parse three integer coordinates from command arguments
+pub fn parse_coords(args: &[String]) -> Option<[i32; 3]> {
+    match args {
+        [x, y, z] => Some([x.parse().ok()?, y.parse().ok()?, z.parse().ok()?]),
+        _ => None,
+    }
+}
+
+/// Parse a recorder scan radius, which must be between 1 and `MAX_SCAN_RADIUS`
+pub fn parse_scan_radius(arg: &str) -> Option<i32> {
+    arg.parse()
+        .ok()
+        .filter(|radius| (1..=recorder::MAX_SCAN_RADIUS).contains(radius))
+}
+
+/// Chat warning for a radius whose cube is slow to scan every `!tick`
+fn scan_radius_warning(radius: i32) -> Option<String> {
+    (radius > recorder::LARGE_SCAN_RADIUS).then(|| {
+        let side = 2 * radius + 1;
+        format!(
+            "say Warning: radius {} scans {} blocks every !tick and may lag the server",
+            radius,
+            side * side * side
+        )
+    })
+}
+
+/// Where `!run` places a test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunPlacement {
+    /// The standard grid position
+    Grid,
+    /// `at <x> <y> <z>`: explicit world coordinates
+    At([i32; 3]),
+    /// `here`: the bot's current position
+    Here,
+}
+
+/// Split `!run` arguments into (test_name, step_mode, placement)
+/// Trailing `step`, `here` and `at <x> <y> <z>` are accepted in any order after the name
+pub fn parse_run_args(args: &[String]) -> Result<(String, bool, RunPlacement), String> {
+    let mut rest = args;
+    let mut step_mode = false;
+    let mut placement = RunPlacement::Grid;
+    loop {
+        match rest {
+            [head @ .., last] if !head.is_empty() && last == "step" => {
+                step_mode = true;
+                rest = head;
+            }
+            [head @ .., last] if !head.is_empty() && last == "here" => {
+                placement = RunPlacement::Here;
+                rest = head;
+            }
+            [head @ .., at, x, y, z] if !head.is_empty() && at == "at" => {
+                let coords = [x.clone(), y.clone(), z.clone()];
+                let pos = parse_coords(&coords)
+                    .ok_or_else(|| format!("Invalid coordinates: {}", coords.join(" ")))?;
+                placement = RunPlacement::At(pos);
+                rest = head;
+            }
+            _ => break,
+        }
+    }
+    if rest.is_empty() {
+        return Err("Usage: !run <test_name> [step] [at <x> <y> <z> | here]".to_string());
+    }
+    Ok((rest.join(" "), step_mode, placement))
+}
+
 impl TestExecutor {
     // Command handlers
 
@@ -46,7 +134,9 @@ impl TestExecutor {
             .send_command("say !search <pattern> - Search tests by name")
             .await?;
         self.bot
-            .send_command("say !run <test_name> [step] - Run a specific test")
+            .send_command(
+                "say !run <test_name> [step] [at <x> <y> <z> | here] - Run a specific test",
+            )
             .await?;
         self.bot
             .send_command("say !run-all - Run all tests")
@@ -54,15 +144,28 @@ impl TestExecutor {
         self.bot
             .send_command("say !run-tags <tag1,tag2> - Run tests with tags")
             .await?;
+        self.bot
+            .send_command("say !results [test_name] - Repeat the last run's results")
+            .await?;
+        self.bot
+            .send_command("say !goto <test_name> - Teleport to a test's area")
+            .await?;
+        self.bot
+            .send_command("say !verbose <on|off> - Per-test result lines in chat")
+            .await?;
         self.bot.send_command("say !list - List all tests").await?;
         self.bot
             .send_command("say !reload - Reload test files")
             .await?;
         self.bot
-            .send_command("say Recorder: !record <name>, !tick/!next, !save, !cancel")
+            .send_command(
+                "say Recorder: !record <name>, !origin <x> <y> <z>, !radius <n>, !tick/!next, !undo, !diff [page], !save, !cancel",
+            )
             .await?;
         self.bot
-            .send_command("say Recorder actions: !assert <x> <y> <z>, !assert_changes")
+            .send_command(
+                "say Recorder actions: !assert <x> <y> <z>, !assert_region <x> <y> <z> (twice, one per corner), !assert_changes",
+            )
             .await?;
         self.bot
             .send_command("say !stop - Exit interactive mode")
@@ -78,7 +181,7 @@ impl TestExecutor {
             .send_command(&format!("say Found {} tests:", all_test_files.len()))
             .await?;
         for test_file in all_test_files {
-            if let Ok(test) = TestSpec::from_file(test_file) {
+            if let Ok((test, _)) = crate::spec::load_test(test_file) {
                 let tags = if test.tags.is_empty() {
                     String::new()
                 } else {
@@ -101,7 +204,7 @@ impl TestExecutor {
         let pattern_lower = pattern.to_lowercase();
         let mut found = 0;
         for test_file in all_test_files {
-            if let Ok(test) = TestSpec::from_file(test_file)
+            if let Ok((test, _)) = crate::spec::load_test(test_file)
                 && test.name.to_lowercase().contains(&pattern_lower)
             {
                 let tags = if test.tags.is_empty() {
@@ -133,16 +236,34 @@ impl TestExecutor {
         all_test_files: &[std::path::PathBuf],
         test_name: &str,
         step_mode: bool,
+        placement: RunPlacement,
     ) -> Result<()> {
         let name_lower = test_name.to_lowercase();
 
+        // Resolve and validate the placement before looking anything up
+        let offset = match placement {
+            RunPlacement::Grid => calculate_test_offset_default(0, 1),
+            RunPlacement::At(pos) => pos,
+            RunPlacement::Here => self.bot.get_position()?,
+        };
+        let (min_y, max_y) = self.dimension.y_range();
+        if !(min_y..=max_y).contains(&offset[1]) {
+            self.bot
+                .send_command(&format!(
+                    "say Base y {} is outside the world ({}..{})",
+                    offset[1], min_y, max_y
+                ))
+                .await?;
+            return Ok(());
+        }
+
         // First pass: look for exact match
         let mut found_test = None;
         for test_file in all_test_files {
-            if let Ok(test) = TestSpec::from_file(test_file)
+            if let Ok((test, options)) = crate::spec::load_test(test_file)
                 && test.name.to_lowercase() == name_lower
             {
-                found_test = Some(test);
+                found_test = Some((test, options));
                 break;
             }
         }
@@ -150,16 +271,16 @@ impl TestExecutor {
         // Second pass: fall back to partial match if no exact match
         if found_test.is_none() {
             for test_file in all_test_files {
-                if let Ok(test) = TestSpec::from_file(test_file)
+                if let Ok((test, options)) = crate::spec::load_test(test_file)
                     && test.name.to_lowercase().contains(&name_lower)
                 {
-                    found_test = Some(test);
+                    found_test = Some((test, options));
                     break;
                 }
             }
         }
 
-        if let Some(test) = found_test {
+        if let Some((test, options)) = found_test {
             if step_mode {
                 self.bot
                     .send_command(&format!(
@@ -173,18 +294,26 @@ impl TestExecutor {
                     .await?;
             }
 
-            let offset = calculate_test_offset_default(0, 1);
             let tests_with_offsets = vec![(test, offset)];
             let output = self
-                .run_tests_parallel(&tests_with_offsets, step_mode)
+                .run_tests_parallel(&tests_with_offsets, &[options], step_mode)
                 .await?;
 
-            for result in &output.results {
-                let status = if result.success { "PASS" } else { "FAIL" };
+            let per_test = if self.chat_per_test {
+                output.results.as_slice()
+            } else {
+                &[]
+            };
+            for result in per_test {
+                let status = crate::format::status_label(
+                    result,
+                    output.expected_failures.contains(&result.test_name),
+                );
                 self.bot
                     .send_command(&format!("say [{}] {}", status, result.test_name))
                     .await?;
             }
+            self.last_output = Some(output);
         } else {
             self.bot
                 .send_command(&format!("say Test '{}' not found", test_name))
@@ -204,15 +333,21 @@ impl TestExecutor {
             ))
             .await?;
 
+        let tests = crate::order::load_ordered(all_test_files.to_vec(), self.seed);
+        let count = tests.len();
         let mut tests_with_offsets = Vec::new();
-        for (idx, test_file) in all_test_files.iter().enumerate() {
-            if let Ok(test) = TestSpec::from_file(test_file) {
-                let offset = calculate_test_offset_default(idx, all_test_files.len());
+        let mut test_options = Vec::new();
+        for (idx, loaded) in tests.into_iter().enumerate() {
+            if let Ok((test, options)) = loaded.loaded {
+                let offset = calculate_test_offset_default(idx, count);
                 tests_with_offsets.push((test, offset));
+                test_options.push(options);
             }
         }
 
-        let output = self.run_tests_parallel(&tests_with_offsets, false).await?;
+        let output = self
+            .run_tests_parallel(&tests_with_offsets, &test_options, false)
+            .await?;
 
         let passed = output.results.iter().filter(|r| r.success).count();
         let failed = output.results.len() - passed;
@@ -222,6 +357,7 @@ impl TestExecutor {
                 passed, failed
             ))
             .await?;
+        self.last_output = Some(output);
         Ok(())
     }
 
@@ -230,9 +366,9 @@ impl TestExecutor {
         test_loader: &TestLoader,
         tags: &[String],
     ) -> Result<()> {
-        let test_files = test_loader.collect_by_tags(tags)?;
+        let tests = crate::order::load_ordered(test_loader.collect_by_tags(tags)?, self.seed);
 
-        if test_files.is_empty() {
+        if tests.is_empty() {
             self.bot
                 .send_command(&format!("say No tests found with tags: {:?}", tags))
                 .await?;
@@ -242,20 +378,25 @@ impl TestExecutor {
         self.bot
             .send_command(&format!(
                 "say Running {} tests with tags {:?}...",
-                test_files.len(),
+                tests.len(),
                 tags
             ))
             .await?;
 
+        let count = tests.len();
         let mut tests_with_offsets = Vec::new();
-        for (idx, test_file) in test_files.iter().enumerate() {
-            if let Ok(test) = TestSpec::from_file(test_file) {
-                let offset = calculate_test_offset_default(idx, test_files.len());
+        let mut test_options = Vec::new();
+        for (idx, loaded) in tests.into_iter().enumerate() {
+            if let Ok((test, options)) = loaded.loaded {
+                let offset = calculate_test_offset_default(idx, count);
                 tests_with_offsets.push((test, offset));
+                test_options.push(options);
             }
         }
 
-        let output = self.run_tests_parallel(&tests_with_offsets, false).await?;
+        let output = self
+            .run_tests_parallel(&tests_with_offsets, &test_options, false)
+            .await?;
 
         let passed = output.results.iter().filter(|r| r.success).count();
         let failed = output.results.len() - passed;
@@ -265,6 +406,127 @@ impl TestExecutor {
                 passed, failed
             ))
             .await?;
+        self.last_output = Some(output);
+        Ok(())
+    }
+
+    /// Teleport `player` above the center of a test's area, where `!run-all` places it
+    pub(super) async fn handle_goto(
+        &mut self,
+        all_test_files: &[std::path::PathBuf],
+        player: &str,
+        test_name: &str,
+    ) -> Result<()> {
+        let name_lower = test_name.to_lowercase();
+        let tests = crate::order::load_ordered(all_test_files.to_vec(), self.seed);
+        let count = tests.len();
+        let tests: Vec<_> = tests
+            .into_iter()
+            .enumerate()
+            .filter_map(|(idx, test)| test.loaded.ok().map(|loaded| (idx, loaded)))
+            .collect();
+        // Exact name first, then the first partial match, like !run
+        let found = tests
+            .iter()
+            .find(|(_, (test, _))| test.name.to_lowercase() == name_lower)
+            .or_else(|| {
+                tests
+                    .iter()
+                    .find(|(_, (test, _))| test.name.to_lowercase().contains(&name_lower))
+            });
+        let Some((idx, (test, options))) = found else {
+            self.bot
+                .send_command(&format!(
+                    "say Test '{}' not found, try !search {}",
+                    test_name, test_name
+                ))
+                .await?;
+            return Ok(());
+        };
+
+        let offset = calculate_test_offset_default(*idx, count);
+        let (min, max) = cleanup::world_corners(test, offset);
+        let target = [(min[0] + max[0]) / 2, max[1] + 1, (min[2] + max[2]) / 2];
+        let dimension = options.dimension;
+        self.bot
+            .send_command(&format!(
+                "execute in {} run tp {} {} {} {}",
+                dimension.id(),
+                player,
+                target[0],
+                target[1],
+                target[2]
+            ))
+            .await?;
+        self.bot
+            .send_command(&format!(
+                "say {}: ({}, {}, {}) to ({}, {}, {})",
+                test.name, min[0], min[1], min[2], max[0], max[1], max[2]
+            ))
+            .await?;
+        Ok(())
+    }
+
+    /// Repeat the last run's summary and failures, or one test's detail with `!results <name>`
+    pub(super) async fn handle_results(&mut self, args: &[String]) -> Result<()> {
+        let Some(output) = self.last_output.as_ref() else {
+            self.bot
+                .send_command("say No run yet. Use !run, !run-all or !run-tags first.")
+                .await?;
+            return Ok(());
+        };
+
+        let mut lines = Vec::new();
+        if args.is_empty() {
+            let passed = output.results.iter().filter(|r| r.success).count();
+            lines.push(format!(
+                "Last run: {}/{} passed, {} failed, {} skipped",
+                passed,
+                output.results.len(),
+                output.results.len() - passed,
+                output.skipped.len()
+            ));
+            lines.extend(
+                output
+                    .failures
+                    .iter()
+                    .map(|(name, failure)| failure_line(name, failure)),
+            );
+        } else {
+            let name = args.join(" ");
+            let name_lower = name.to_lowercase();
+            let matches_name = |test_name: &str| test_name.to_lowercase() == name_lower;
+            if let Some(result) = output.results.iter().find(|r| matches_name(&r.test_name)) {
+                let status = crate::format::status_label(
+                    result,
+                    output.expected_failures.contains(&result.test_name),
+                );
+                lines.push(format!(
+                    "[{}] {} ({} ticks, {} ms)",
+                    status, result.test_name, result.total_ticks, result.execution_time_ms
+                ));
+                lines.extend(
+                    output
+                        .failures
+                        .iter()
+                        .filter(|(test_name, _)| matches_name(test_name))
+                        .map(|(test_name, failure)| failure_line(test_name, failure)),
+                );
+            } else if let Some((test_name, reason)) = output
+                .skipped
+                .iter()
+                .find(|(test_name, _)| matches_name(test_name))
+            {
+                lines.push(format!("[SKIP] {}: {}", test_name, reason));
+            } else {
+                lines.push(format!("'{}' was not in the last run", name));
+            }
+        }
+
+        for line in lines {
+            self.bot.send_command(&format!("say {}", line)).await?;
+            tokio::time::sleep(tokio::time::Duration::from_millis(TEST_RESULT_DELAY_MS)).await;
+        }
         Ok(())
     }
 
@@ -275,6 +537,8 @@ impl TestExecutor {
         test_name: &str,
         _test_loader: &TestLoader,
         player_name: Option<String>,
+        origin: Option<[i32; 3]>,
+        scan_radius: Option<i32>,
     ) -> Result<()> {
         if self.recorder.is_some() {
             self.bot
@@ -283,10 +547,12 @@ impl TestExecutor {
             return Ok(());
         }
 
-        let tests_root = std::path::Path::new(DEFAULT_TESTS_DIR);
-        let mut recorder_state = recorder::RecorderState::new(test_name, tests_root);
+        let mut recorder_state = recorder::RecorderState::new(test_name, &self.tests_dir);
         // Default to @p if nothing works
         recorder_state.player_name = player_name.or_else(|| Some("@p".to_string()));
+        if let Some(origin) = origin {
+            recorder_state.set_explicit_origin(origin);
+        }
 
         // Get bot position to set scan center
         let scan_center = match self.bot.get_position() {
@@ -300,7 +566,12 @@ impl TestExecutor {
         };
 
         recorder_state.set_scan_center(scan_center);
-        recorder_state.scan_radius = 10; // 10 block radius for scanning
+        if let Some(radius) = scan_radius {
+            recorder_state.scan_radius = radius;
+            if let Some(warning) = scan_radius_warning(radius) {
+                self.bot.send_command(&warning).await?;
+            }
+        }
 
         // Take initial snapshot of blocks
         let initial_blocks = self
@@ -384,6 +655,7 @@ impl TestExecutor {
             let block_id = block::extract_block_id(&block_str);
             let recorder = self.recorder.as_mut().unwrap();
             recorder.add_assertion(block_pos, &block_id);
+            self.record_container_items(block_pos, &block_id).await;
 
             self.bot
                 .send_command(&format!(
@@ -403,6 +675,57 @@ impl TestExecutor {
         Ok(())
     }
 
+    /// `!assert_region <x> <y> <z>`: the first call sets one corner, the second records one
+    /// assertion for every block between the two
+    pub(super) async fn handle_record_assert_region(&mut self, args: &[String]) -> Result<()> {
+        let Some(corner) = parse_coords(args) else {
+            self.bot
+                .send_command("say Usage: !assert_region <x> <y> <z>")
+                .await?;
+            return Ok(());
+        };
+        let Some(recorder) = self.recorder.as_mut() else {
+            self.bot
+                .send_command("say No recording in progress. Use !record <name> to start.")
+                .await?;
+            return Ok(());
+        };
+        let Some(pos1) = recorder.pos1.take() else {
+            recorder.pos1 = Some(corner);
+            self.bot
+                .send_command(&format!(
+                    "say First corner set at [{}, {}, {}]. Run !assert_region at the opposite corner.",
+                    corner[0], corner[1], corner[2]
+                ))
+                .await?;
+            return Ok(());
+        };
+
+        let volume: i64 = (0..3)
+            .map(|axis| (pos1[axis] as i64 - corner[axis] as i64).abs() + 1)
+            .product();
+        if volume > MAX_FILL_VOLUME {
+            self.bot
+                .send_command(&format!(
+                    "say Region has {} blocks, the limit is {}. Corners cleared.",
+                    volume, MAX_FILL_VOLUME
+                ))
+                .await?;
+            return Ok(());
+        }
+
+        let blocks = self.scan_region(pos1, corner).await?;
+        let recorder = self.recorder.as_mut().unwrap();
+        recorder.add_region_assertion(pos1, corner, &blocks);
+        self.bot
+            .send_command(&format!(
+                "say Added region assert [{}, {}, {}] to [{}, {}, {}] ({} blocks)",
+                pos1[0], pos1[1], pos1[2], corner[0], corner[1], corner[2], volume
+            ))
+            .await?;
+        Ok(())
+    }
+
     pub(super) async fn handle_record_assert_changes(&mut self) -> Result<()> {
         let Some(recorder) = self.require_recorder() else {
             self.bot
@@ -436,7 +759,7 @@ impl TestExecutor {
                 .await?;
         }
 
-        match recorder.save() {
+        let saved_path = match recorder.save_as(self.record_format) {
             Ok(path) => {
                 self.bot
                     .send_command(&format!(
@@ -454,6 +777,7 @@ impl TestExecutor {
                     "To execute this test locally:\ncargo run -- --server localhost:25565 {}",
                     recorder.test_name
                 );
+                path
             }
             Err(e) => {
                 self.bot
@@ -462,14 +786,70 @@ impl TestExecutor {
                 eprintln!("Failed to save: {}", e);
                 return Err(e);
             }
-        }
+        };
 
         // Unfreeze time after recording
         self.bot.send_command("tick unfreeze").await?;
 
+        if self.verify_recordings {
+            self.verify_recording(&saved_path, recorder.origin).await?;
+        }
+
         Ok(true)
     }
 
+    /// Replay a just-saved recording in the grid area and report whether it passes.
+    /// Skipped when that area overlaps the recorded build, which clearing it would destroy.
+    async fn verify_recording(
+        &mut self,
+        path: &std::path::Path,
+        origin: Option<[i32; 3]>,
+    ) -> Result<()> {
+        let (test, options) = match crate::spec::load_test(path) {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.bot
+                    .send_command(&format!("say Verify: saved test doesn't load: {:#}", e))
+                    .await?;
+                return Ok(());
+            }
+        };
+        let Some(origin) = origin.filter(|_| !test.timeline.is_empty()) else {
+            return Ok(());
+        };
+
+        let offset = calculate_test_offset_default(0, 1);
+        let build = cleanup::world_corners(&test, origin);
+        if cleanup::overlap(build, cleanup::world_corners(&test, offset)) > 0 {
+            self.bot
+                .send_command(
+                    "say Verify skipped: the replay area overlaps your build. Record further from the test grid to verify.",
+                )
+                .await?;
+            return Ok(());
+        }
+
+        self.bot
+            .send_command(&format!("say Verifying {}...", test.name))
+            .await?;
+        let output = self
+            .run_tests_parallel(&[(test, offset)], &[options], false)
+            .await?;
+        if let Some(result) = output.results.first() {
+            let line = if result.success {
+                format!("say [PASS] {} replays cleanly", result.test_name)
+            } else {
+                format!(
+                    "say [FAIL] {} doesn't pass on replay; !results {} for details",
+                    result.test_name, result.test_name
+                )
+            };
+            self.bot.send_command(&line).await?;
+        }
+        self.last_output = Some(output);
+        Ok(())
+    }
+
     pub(super) async fn handle_record_snapshot(&mut self) -> Result<()> {
         let recorder = match self.recorder.as_mut() {
             Some(r) => r,
@@ -493,6 +873,7 @@ impl TestExecutor {
 
         // Compare with initial snapshot and record differences
         let mut changes = 0;
+        let mut placed_containers = Vec::new();
         let recorder = self.recorder.as_mut().unwrap();
         let initial_snapshot = recorder.snapshot.clone();
 
@@ -511,6 +892,7 @@ impl TestExecutor {
                     recorder.record_remove(*pos);
                 } else {
                     recorder.record_place(*pos, current_block);
+                    placed_containers.push((*pos, current_block));
                 }
                 changes += 1;
             }
@@ -534,12 +916,183 @@ impl TestExecutor {
             }
         }
 
+        for (pos, block_id) in placed_containers {
+            self.record_container_items(pos, block_id).await;
+        }
+
         self.bot
             .send_command(&format!("say Found {} block changes", changes))
             .await?;
         Ok(())
     }
 
+    /// Keep a just-recorded container's contents with it, so its items are recorded too
+    /// Other blocks and empty containers are left alone; a failed query only warns.
+    async fn record_container_items(&mut self, pos: [i32; 3], block_id: &str) {
+        if !block::is_container(block_id) {
+            return;
+        }
+        let items = match self.bot.query_block_data(pos, "Items").await {
+            Ok(Some(items)) if snbt::parse(&items).is_ok() => items,
+            Ok(_) => return,
+            Err(e) => {
+                tracing::warn!(?pos, "Could not read container contents: {:#}", e);
+                return;
+            }
+        };
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record_items(pos, &items);
+        }
+    }
+
+    pub(super) async fn handle_record_origin(&mut self, args: &[String]) -> Result<()> {
+        let Some(origin) = parse_coords(args) else {
+            self.bot
+                .send_command("say Usage: !origin <x> <y> <z>")
+                .await?;
+            return Ok(());
+        };
+        let Some(recorder) = self.require_recorder() else {
+            self.bot
+                .send_command("say No recording in progress. Use !record <name> to start.")
+                .await?;
+            return Ok(());
+        };
+
+        let message = if recorder.set_explicit_origin(origin) {
+            format!(
+                "say Origin set to [{}, {}, {}]",
+                origin[0], origin[1], origin[2]
+            )
+        } else {
+            let existing = recorder.origin.unwrap_or(origin);
+            format!(
+                "say Origin is already [{}, {}, {}] and can't change mid-recording. Use !cancel to start over.",
+                existing[0], existing[1], existing[2]
+            )
+        };
+        self.bot.send_command(&message).await?;
+        Ok(())
+    }
+
+    pub(super) async fn handle_record_radius(&mut self, args: &[String]) -> Result<()> {
+        let Some(radius) = args.first().and_then(|arg| parse_scan_radius(arg)) else {
+            self.bot
+                .send_command(&format!(
+                    "say Usage: !radius <1-{}>",
+                    recorder::MAX_SCAN_RADIUS
+                ))
+                .await?;
+            return Ok(());
+        };
+        let Some(recorder) = self.recorder.as_ref() else {
+            self.bot
+                .send_command("say No recording in progress. Use !record <name> to start.")
+                .await?;
+            return Ok(());
+        };
+
+        // Only a larger cube has blocks the snapshot hasn't seen yet
+        let scanned = if radius > recorder.scan_radius {
+            let scan_center = recorder.scan_center.unwrap_or([0, 64, 0]);
+            self.scan_blocks_around(scan_center, radius).await?
+        } else {
+            std::collections::HashMap::new()
+        };
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.set_scan_radius(radius, scanned);
+        }
+
+        self.bot
+            .send_command(&format!("say Scan radius set to {}", radius))
+            .await?;
+        if let Some(warning) = scan_radius_warning(radius) {
+            self.bot.send_command(&warning).await?;
+        }
+        Ok(())
+    }
+
+    pub(super) async fn handle_record_undo(&mut self) -> Result<()> {
+        let Some(recorder) = self.require_recorder() else {
+            self.bot
+                .send_command("say No recording in progress.")
+                .await?;
+            return Ok(());
+        };
+
+        let message = match recorder.undo_last_action() {
+            Some((tick, recorder::RecordedAction::Place { pos, block })) => format!(
+                "say Undid place at [{}, {}, {}] = {} (tick {})",
+                pos[0], pos[1], pos[2], block, tick
+            ),
+            Some((tick, recorder::RecordedAction::Remove { pos })) => format!(
+                "say Undid remove at [{}, {}, {}] (tick {})",
+                pos[0], pos[1], pos[2], tick
+            ),
+            Some((tick, recorder::RecordedAction::Assert { pos, block })) => format!(
+                "say Undid assert at [{}, {}, {}] = {} (tick {})",
+                pos[0], pos[1], pos[2], block, tick
+            ),
+            None => "say Nothing to undo.".to_string(),
+        };
+        self.bot.send_command(&message).await?;
+        Ok(())
+    }
+
+    /// Show what the recording has captured so far, `DIFF_PAGE_SIZE` lines per chat page
+    pub(super) async fn handle_record_diff(&mut self, args: &[String]) -> Result<()> {
+        let Some(recorder) = self.require_recorder() else {
+            self.bot
+                .send_command("say No recording in progress.")
+                .await?;
+            return Ok(());
+        };
+        let lines = recorder.diff_lines();
+        let current_tick = recorder.current_tick;
+
+        if lines.is_empty() {
+            self.bot
+                .send_command(&format!(
+                    "say Nothing recorded yet (tick {}).",
+                    current_tick
+                ))
+                .await?;
+            return Ok(());
+        }
+
+        // Full diff on stdout, one page in chat
+        println!("{} Pending changes:", "→".blue());
+        for line in &lines {
+            println!("  {}", line);
+        }
+
+        let pages = lines.len().div_ceil(DIFF_PAGE_SIZE);
+        let page = args
+            .first()
+            .and_then(|arg| arg.parse::<usize>().ok())
+            .unwrap_or(1)
+            .clamp(1, pages);
+        for line in lines
+            .iter()
+            .skip((page - 1) * DIFF_PAGE_SIZE)
+            .take(DIFF_PAGE_SIZE)
+        {
+            self.bot.send_command(&format!("say {}", line)).await?;
+            tokio::time::sleep(tokio::time::Duration::from_millis(TEST_RESULT_DELAY_MS)).await;
+        }
+        if pages > 1 {
+            let more = if page < pages {
+                format!(" - !diff {} for more", page + 1)
+            } else {
+                String::new()
+            };
+            self.bot
+                .send_command(&format!("say Page {}/{}{}", page, pages, more))
+                .await?;
+        }
+        Ok(())
+    }
+
     pub(super) async fn handle_record_cancel(&mut self) -> Result<()> {
         if self.recorder.take().is_some() {
             // Unfreeze time after cancelling
@@ -553,3 +1106,31 @@ impl TestExecutor {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn test_parse_run_args() {
+        assert_eq!(
+            parse_run_args(&args("fence connect")),
+            Ok(("fence connect".to_string(), false, RunPlacement::Grid))
+        );
+        assert_eq!(
+            parse_run_args(&args("lever at 100 64 -20 step")),
+            Ok(("lever".to_string(), true, RunPlacement::At([100, 64, -20])))
+        );
+        assert_eq!(
+            parse_run_args(&args("lever step here")),
+            Ok(("lever".to_string(), true, RunPlacement::Here))
+        );
+        assert!(parse_run_args(&args("lever at 1 two 3")).is_err());
+        assert!(parse_run_args(&args("step")).is_ok());
+        assert!(parse_run_args(&[]).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! Command hooks - `--before-all`/`--after-all` around the whole run,
+//! and a test's own `before`/`after` around its part of the merged timeline
+
+use anyhow::Result;
+use colored::Colorize;
+use flint_core::test_spec::TestSpec;
+
+use super::{COMMAND_DELAY_MS, TestExecutor};
+
+/// Run a test hook at the test's origin, so `~ ~ ~` coordinates are relative to its local 0 0 0
+fn positioned_command(command: &str, origin: [i32; 3]) -> String {
+    format!(
+        "execute positioned {} {} {} run {}",
+        origin[0],
+        origin[1],
+        origin[2],
+        command.trim().trim_start_matches('/')
+    )
+}
+
+impl TestExecutor {
+    /// Send the `--before-all` commands, arming `after_all` for `run_after_all` first
+    /// so the after-all commands still run if one of these is rejected.
+    pub async fn run_before_all(
+        &mut self,
+        before_all: &[String],
+        after_all: &[String],
+    ) -> Result<()> {
+        self.after_all = after_all.to_vec();
+        for command in before_all {
+            self.bot.send_command_checked(command).await?;
+            tracing::debug!(command = %command, "Ran before-all command");
+            tokio::time::sleep(tokio::time::Duration::from_millis(COMMAND_DELAY_MS)).await;
+        }
+        Ok(())
+    }
+
+    /// Send a test's `before` or `after` commands, positioned at its grid offset
+    pub(super) async fn run_test_hooks(
+        &mut self,
+        test: &TestSpec,
+        offset: [i32; 3],
+        commands: &[String],
+    ) -> Result<()> {
+        for command in commands {
+            self.bot
+                .send_command_checked(&positioned_command(command, offset))
+                .await?;
+            tracing::debug!(test = %test.name, command = %command, "Ran test hook");
+        }
+        Ok(())
+    }
+
+    /// Send a test's `after` commands; a rejected one is only a warning, the verdict is in
+    pub(super) async fn run_after_hooks(
+        &mut self,
+        test: &TestSpec,
+        offset: [i32; 3],
+        commands: &[String],
+    ) {
+        if let Err(e) = self.run_test_hooks(test, offset, commands).await {
+            eprintln!(
+                "{} [{}] after: {:#}",
+                "Warning:".yellow().bold(),
+                test.name,
+                e
+            );
+        }
+    }
+
+    /// Send the armed `--after-all` commands once
+    /// A rejected command is reported and the rest still run, so an earlier error isn't masked.
+    pub async fn run_after_all(&mut self) {
+        let commands = std::mem::take(&mut self.after_all);
+        if commands.is_empty() {
+            return;
+        }
+        if !self.bot.is_connected() {
+            eprintln!(
+                "{} Lost the server, {} after-all command(s) not run",
+                "Warning:".yellow().bold(),
+                commands.len()
+            );
+            return;
+        }
+        for command in &commands {
+            match self.bot.send_command_checked(command).await {
+                Ok(()) => tracing::debug!(command = %command, "Ran after-all command"),
+                Err(e) => eprintln!("{} after-all: {:#}", "Warning:".yellow().bold(), e),
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(COMMAND_DELAY_MS)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positioned_command() {
+        assert_eq!(
+            positioned_command("/summon minecraft:zombie ~1 ~ ~2", [100, 64, -20]),
+            "execute positioned 100 64 -20 run summon minecraft:zombie ~1 ~ ~2"
+        );
+    }
+}
@@ -1,35 +1,82 @@
 //! Test executor module - core test orchestration
 
 mod actions;
-mod block;
+pub(crate) mod block;
+mod chunk_loading;
+mod cleanup;
+mod dependencies;
+mod dump;
+mod events;
+mod export;
+mod gamerules;
 mod handlers;
+mod hooks;
+mod perf;
+mod profile;
 mod recorder;
+mod snbt;
 mod tick;
-
-use crate::bot::TestBot;
+mod trace;
+mod watch;
+
+use crate::bot::{ChatKind, ChatMessage, TestBot};
+use crate::format;
+use crate::snapshot::{self, Snapshot, SnapshotMode};
+use crate::spec::{self, Dimension, TestOptions};
+use crate::validate;
 use anyhow::Result;
 use colored::Colorize;
 use flint_core::loader::TestLoader;
-use flint_core::results::{ActionOutcome, AssertFailure, TestResult};
-use flint_core::test_spec::{TestSpec, TimelineEntry};
+use flint_core::results::{AssertFailure, InfoType, TestResult};
+use flint_core::test_spec::{ActionType, TestSpec, TimelineEntry};
 use flint_core::timeline::TimelineAggregate;
+use std::collections::{HashMap, HashSet};
 use std::io::Write;
-
-pub use tick::{COMMAND_DELAY_MS, MIN_RETRY_DELAY_MS};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::Instrument;
+
+pub use actions::apply_offset;
+pub use chunk_loading::ChunkLoading;
+pub use cleanup::CleanupMode;
+pub use dependencies::dependency_order;
+pub use events::{EventHandler, RunEvent, sink_handler};
+pub use export::export_mcfunction;
+pub use gamerules::parse_gamerule;
+pub use perf::{MsptStat, PerfGate, PerfSummary};
+use profile::Phase;
+pub use profile::RunProfile;
+pub use recorder::{BoundingBox, RecordFormat};
+pub use tick::{COMMAND_DELAY_MS, MIN_RETRY_DELAY_MS, StepKeys};
 
 // Timing constants
 const CLEANUP_DELAY_MS: u64 = 200;
 const TEST_RESULT_DELAY_MS: u64 = 50;
-const DEFAULT_TESTS_DIR: &str = "FlintBenchmark/tests";
+/// Where tests are loaded from without a PATH, and where the recorder saves (`--tests-dir`)
+pub const DEFAULT_TESTS_DIR: &str = "FlintBenchmark/tests";
+const DIMENSION_CHANGE_DELAY_MS: u64 = 1000;
+
+/// Mismatched positions listed per failing snapshot
+const SNAPSHOT_DIFF_LINES: usize = 10;
 
 // Progress bar constants
 const PROGRESS_BAR_WIDTH: usize = 40;
 
 /// Output from a test run, including results and failure details
+#[derive(Default)]
 pub struct TestRunOutput {
     pub results: Vec<TestResult>,
-    /// First failure detail per failed test: (test_name, failure_detail)
+    /// Every failure detail of every failed test: (test_name, failure_detail), grouped by test in
+    /// the order they happened, so a test's first entry is its first failure
     pub failures: Vec<(String, AssertFailure)>,
+    /// Tests that were not run because a dependency did not pass, or were cut short by an abort:
+    /// (test_name, reason)
+    pub skipped: Vec<(String, String)>,
+    /// Names of the run tests marked `expect_failure`, so their results read as XFAIL/XPASS
+    pub expected_failures: HashSet<String>,
+    /// The run was aborted from a breakpoint before every test finished
+    pub aborted: bool,
 }
 
 pub struct TestExecutor {
@@ -39,17 +86,104 @@ pub struct TestExecutor {
     verbose: bool,
     quiet: bool,
     fail_fast: bool,
+    fail_fast_per_test: bool,
+    /// Read back each place, fill and remove, resending it once if it didn't land
+    verify_writes: bool,
+    /// Stop the timeline once this many tests have failed (`--bail-after`)
+    bail_after: Option<usize>,
+    /// Tests that did not pass in earlier chunks of the run; their dependents are skipped
+    blocked_tests: HashSet<String>,
+    break_on_failure: bool,
+    /// Keep finished test areas until the player continues, with time still frozen
+    break_before_cleanup: bool,
+    /// Most tests to place on the grid at once; larger sets run in batches
+    max_parallel: Option<usize>,
+    dimension: Dimension,
+    /// Gamerule values from before the run, restored afterwards: (rule, value)
+    saved_gamerules: Vec<(String, String)>,
+    /// `--after-all` commands still to send when the run ends
+    after_all: Vec<String>,
+    progress_mode: ProgressMode,
+    /// Draw the progress bar with `#` instead of block characters (uncolored output)
+    ascii_progress: bool,
+    /// Server ms-per-tick samples from sprints during the run
+    perf: perf::PerfStats,
+    /// Wall time per phase of the merged timeline (`--profile`)
+    profile: profile::ProfileStats,
+    step_keys: tick::StepKeys,
+    /// Set by the Ctrl-C handler; the running timeline stops and cleans up like an abort
+    interrupted: Arc<AtomicBool>,
+    event_handlers: Vec<EventHandler>,
+    record_format: recorder::RecordFormat,
+    /// Replay each recording once in the grid area right after `!save`
+    verify_recordings: bool,
+    /// Directory recorded tests are saved under
+    tests_dir: PathBuf,
+    /// Output of the last interactive run, for `!results`
+    last_output: Option<TestRunOutput>,
+    /// Send a chat line per test result, not just the summary (`!verbose on/off`)
+    chat_per_test: bool,
+    snapshot_mode: SnapshotMode,
+    /// Fail instead of warning when a test touches blocks outside its cleanup region
+    strict: bool,
+    /// Shuffles grid placement reproducibly (`--seed`); tests are name-sorted without it
+    seed: Option<u64>,
+    cleanup_mode: CleanupMode,
+    /// Merge nearby cleanup fills and pause once per batch (`--fill-cleanup-batch`)
+    fill_cleanup_batch: bool,
+    /// Directory for block dumps of failing tests (`--dump-on-failure`)
+    dump_dir: Option<PathBuf>,
+    /// Per-tick record of commands, assertions and tick advances (`--trace-log`)
+    trace: Option<trace::TraceLog>,
+    /// How test chunks are kept loaded (`--keep-chunks-loaded`)
+    chunk_loading: ChunkLoading,
+    /// Whether the server accepts `/forceload`; None until asked
+    forceload_supported: Option<bool>,
+    /// Force-loaded chunks and how many running tests use each
+    forced_chunks: HashMap<chunk_loading::ChunkPos, usize>,
 }
 
 impl Default for TestExecutor {
     fn default() -> Self {
         Self {
             bot: TestBot::new(),
-            action_delay_ms: COMMAND_DELAY_MS,
+            action_delay_ms: 0,
             recorder: None,
             verbose: false,
             quiet: false,
             fail_fast: false,
+            fail_fast_per_test: false,
+            bail_after: None,
+            blocked_tests: HashSet::new(),
+            verify_writes: false,
+            break_on_failure: false,
+            break_before_cleanup: false,
+            max_parallel: None,
+            dimension: Dimension::Overworld,
+            saved_gamerules: Vec::new(),
+            after_all: Vec::new(),
+            progress_mode: ProgressMode::default(),
+            ascii_progress: false,
+            perf: perf::PerfStats::default(),
+            profile: profile::ProfileStats::default(),
+            step_keys: tick::StepKeys::default(),
+            interrupted: Arc::new(AtomicBool::new(false)),
+            event_handlers: Vec::new(),
+            record_format: recorder::RecordFormat::default(),
+            verify_recordings: false,
+            tests_dir: PathBuf::from(DEFAULT_TESTS_DIR),
+            last_output: None,
+            chat_per_test: true,
+            snapshot_mode: SnapshotMode::default(),
+            strict: false,
+            seed: None,
+            cleanup_mode: CleanupMode::default(),
+            fill_cleanup_batch: false,
+            dump_dir: None,
+            trace: None,
+            chunk_loading: ChunkLoading::default(),
+            forceload_supported: None,
+            forced_chunks: HashMap::new(),
         }
     }
 }
@@ -63,10 +197,42 @@ impl TestExecutor {
         self.action_delay_ms = delay_ms;
     }
 
+    /// Write a per-tick trace of the run to `path` (`--trace-log`)
+    pub fn set_trace_log(&mut self, path: &Path) -> Result<()> {
+        self.trace = Some(trace::TraceLog::create(path)?);
+        self.bot.set_record_commands(true);
+        Ok(())
+    }
+
+    /// Cap on commands sent per second (`--max-commands-per-sec`, 0 for no cap)
+    pub fn set_max_commands_per_sec(&mut self, per_sec: u32) {
+        self.bot.set_max_commands_per_sec(per_sec);
+    }
+
+    pub fn set_assert_timeout(&mut self, timeout: Option<std::time::Duration>) {
+        self.bot.set_poll_timeout(timeout);
+    }
+
     pub fn set_verbose(&mut self, verbose: bool) {
         self.verbose = verbose;
     }
 
+    pub fn set_snapshot_mode(&mut self, mode: SnapshotMode) {
+        self.snapshot_mode = mode;
+    }
+
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn set_seed(&mut self, seed: Option<u64>) {
+        self.seed = seed;
+    }
+
+    pub fn set_cleanup_mode(&mut self, mode: CleanupMode) {
+        self.cleanup_mode = mode;
+    }
+
     pub fn set_quiet(&mut self, quiet: bool) {
         self.quiet = quiet;
     }
@@ -75,8 +241,111 @@ impl TestExecutor {
         self.fail_fast = fail_fast;
     }
 
-    pub async fn connect(&mut self, server: &str) -> Result<()> {
-        self.bot.connect(server).await
+    pub fn set_fail_fast_per_test(&mut self, fail_fast_per_test: bool) {
+        self.fail_fast_per_test = fail_fast_per_test;
+    }
+
+    pub fn set_verify_writes(&mut self, verify_writes: bool) {
+        self.verify_writes = verify_writes;
+    }
+
+    pub fn set_bail_after(&mut self, bail_after: Option<usize>) {
+        self.bail_after = bail_after;
+    }
+
+    pub fn set_blocked_tests(&mut self, blocked_tests: HashSet<String>) {
+        self.blocked_tests = blocked_tests;
+    }
+
+    pub fn set_break_on_failure(&mut self, break_on_failure: bool) {
+        self.break_on_failure = break_on_failure;
+    }
+
+    pub fn set_break_before_cleanup(&mut self, break_before_cleanup: bool) {
+        self.break_before_cleanup = break_before_cleanup;
+    }
+
+    pub fn set_max_parallel(&mut self, max_parallel: Option<usize>) {
+        self.max_parallel = max_parallel;
+    }
+
+    pub fn set_progress_mode(&mut self, mode: ProgressMode) {
+        self.progress_mode = mode;
+    }
+
+    pub fn set_chunk_loading(&mut self, mode: ChunkLoading) {
+        self.chunk_loading = mode;
+    }
+
+    pub fn set_fill_cleanup_batch(&mut self, batch: bool) {
+        self.fill_cleanup_batch = batch;
+    }
+
+    pub fn set_dump_dir(&mut self, dir: Option<PathBuf>) {
+        self.dump_dir = dir;
+    }
+
+    pub fn set_ascii_progress(&mut self, ascii: bool) {
+        self.ascii_progress = ascii;
+    }
+
+    pub fn set_step_keys(&mut self, keys: StepKeys) {
+        self.step_keys = keys;
+    }
+
+    pub fn set_chat_per_test(&mut self, chat_per_test: bool) {
+        self.chat_per_test = chat_per_test;
+    }
+
+    pub fn set_record_format(&mut self, format: RecordFormat) {
+        self.record_format = format;
+    }
+
+    pub fn set_verify_recordings(&mut self, verify_recordings: bool) {
+        self.verify_recordings = verify_recordings;
+    }
+
+    pub fn set_tests_dir(&mut self, tests_dir: PathBuf) {
+        self.tests_dir = tests_dir;
+    }
+
+    /// Flag that interrupts the run when set, e.g. from a Ctrl-C handler
+    pub fn interrupt_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.interrupted)
+    }
+
+    pub fn is_interrupted(&self) -> bool {
+        self.interrupted.load(Ordering::SeqCst)
+    }
+
+    /// Server tick timing across everything run so far, if any sprint reported it
+    pub fn perf_summary(&self) -> Option<PerfSummary> {
+        self.perf.summary()
+    }
+
+    /// Where the time of everything run so far went
+    pub fn profile_summary(&self) -> RunProfile {
+        self.profile.summary()
+    }
+
+    /// Register a callback that receives run events as they happen
+    pub fn add_event_handler(&mut self, handler: EventHandler) {
+        self.event_handlers.push(handler);
+    }
+
+    /// Send an event to every registered handler
+    pub fn emit(&mut self, event: RunEvent) {
+        event.log();
+        for handler in &mut self.event_handlers {
+            handler(&event);
+        }
+    }
+
+    pub async fn connect(&mut self, server: &str, timeout: std::time::Duration) -> Result<()> {
+        // A new connection may be a new server; a rerun after a kick forces its chunks again
+        self.forceload_supported = None;
+        self.forced_chunks.clear();
+        self.bot.connect(server, timeout).await
     }
 
     /// Helper to get a mutable reference to the recorder, or return an error
@@ -86,12 +355,12 @@ impl TestExecutor {
 
     /// Helper to apply the standard command delay
     async fn delay(&self) {
-        tokio::time::sleep(tokio::time::Duration::from_millis(self.action_delay_ms)).await;
+        tokio::time::sleep(tokio::time::Duration::from_millis(COMMAND_DELAY_MS)).await;
     }
 
     /// Interactive mode: listen for chat commands and execute them
     pub async fn interactive_mode(&mut self, test_loader: &mut TestLoader) -> Result<()> {
-        // Interactive mode always uses verbose output
+        // Interactive mode always logs actions (see main), so no progress bar
         self.verbose = true;
 
         // Send help message to chat (without ! to avoid self-triggering)
@@ -102,17 +371,27 @@ impl TestExecutor {
         self.bot.send_command("say Type: help, search, run, run-all, run-tags, list, reload, stop (prefix with !)").await?;
         tokio::time::sleep(tokio::time::Duration::from_millis(COMMAND_DELAY_MS)).await;
 
-        // Drain any messages (including our own welcome messages)
-        tick::drain_chat_messages(&mut self.bot).await;
+        // Drain any commands sent before the welcome messages
+        tick::drain_chat_messages(&mut self.bot, ChatKind::Player).await;
 
         // Collect all tests upfront (mutable to allow reload)
         let mut all_test_files = test_loader.collect_all_test_files()?;
 
         loop {
+            if self.is_interrupted() {
+                self.bot
+                    .send_command("say Interrupted, exiting interactive mode")
+                    .await?;
+                return Ok(());
+            }
+
             // Poll for chat messages
-            if let Some((sender, message)) = self
+            if let Some(ChatMessage::Player { sender, message }) = self
                 .bot
-                .recv_chat_timeout(std::time::Duration::from_millis(tick::CHAT_POLL_TIMEOUT_MS))
+                .recv_chat_timeout(
+                    ChatKind::Player,
+                    std::time::Duration::from_millis(tick::CHAT_POLL_TIMEOUT_MS),
+                )
                 .await
             {
                 let Some((command, args)) = handlers::parse_command(&message) else {
@@ -140,29 +419,60 @@ impl TestExecutor {
                     }
 
                     "!run" => {
-                        if args.is_empty() {
+                        // Optional step flag and placement after the test name
+                        let (test_name, step_mode, placement) =
+                            match handlers::parse_run_args(&args) {
+                                Ok(parsed) => parsed,
+                                Err(message) => {
+                                    self.bot.send_command(&format!("say {}", message)).await?;
+                                    continue;
+                                }
+                            };
+
+                        self.handle_run(&all_test_files, &test_name, step_mode, placement)
+                            .await?;
+                    }
+
+                    "!verbose" => match args.first().map(|arg| arg.to_lowercase()).as_deref() {
+                        Some("on") => {
+                            self.chat_per_test = true;
                             self.bot
-                                .send_command("say Usage: !run <test_name> [step]")
+                                .send_command("say Per-test results will be sent to chat")
                                 .await?;
-                            continue;
                         }
+                        Some("off") => {
+                            self.chat_per_test = false;
+                            self.bot
+                                .send_command("say Only run summaries will be sent to chat")
+                                .await?;
+                        }
+                        _ => {
+                            self.bot
+                                .send_command("say Usage: !verbose <on|off>")
+                                .await?;
+                        }
+                    },
 
-                        // Check for step flag
-                        let (test_name, step_mode) =
-                            if args.last().map(|s| s.as_str()) == Some("step") && args.len() > 1 {
-                                (args[..args.len() - 1].join(" "), true)
-                            } else {
-                                (args.join(" "), false)
-                            };
-
-                        self.handle_run(&all_test_files, &test_name, step_mode)
-                            .await?;
+                    "!results" => {
+                        self.handle_results(&args).await?;
                     }
 
                     "!run-all" => {
                         self.handle_run_all(&all_test_files).await?;
                     }
 
+                    "!goto" => {
+                        if args.is_empty() {
+                            self.bot
+                                .send_command("say Usage: !goto <test_name>")
+                                .await?;
+                            continue;
+                        }
+                        let test_name = args.join(" ");
+                        self.handle_goto(&all_test_files, &sender, &test_name)
+                            .await?;
+                    }
+
                     "!run-tags" => {
                         if args.is_empty() {
                             self.bot
@@ -194,7 +504,9 @@ impl TestExecutor {
                     "!record" => {
                         if args.is_empty() {
                             self.bot
-                                .send_command("say Usage: !record <test_name> [player_name]")
+                                .send_command(
+                                    "say Usage: !record <test_name> [player_name] [x y z] [radius=<n>]",
+                                )
                                 .await?;
                             self.bot
                                 .send_command(
@@ -204,15 +516,55 @@ impl TestExecutor {
                             continue;
                         }
                         let test_name = args[0].clone();
-                        // If player name not provided, use sender if available
-                        let player_name = args.get(1).cloned().or_else(|| sender.clone());
-                        self.handle_record_start(&test_name, test_loader, player_name)
-                            .await?;
+                        // Optional `radius=<n>` anywhere after the name
+                        let (radius_args, rest): (Vec<String>, Vec<String>) = args[1..]
+                            .iter()
+                            .cloned()
+                            .partition(|arg| arg.starts_with("radius="));
+                        let scan_radius = match radius_args.last() {
+                            Some(arg) => match handlers::parse_scan_radius(&arg["radius=".len()..])
+                            {
+                                Some(radius) => Some(radius),
+                                None => {
+                                    self.bot
+                                        .send_command(&format!(
+                                            "say Usage: radius=<1-{}>",
+                                            recorder::MAX_SCAN_RADIUS
+                                        ))
+                                        .await?;
+                                    continue;
+                                }
+                            },
+                            None => None,
+                        };
+                        // Optional trailing origin coordinates
+                        let mut rest = &rest[..];
+                        let origin = rest
+                            .len()
+                            .checked_sub(3)
+                            .and_then(|start| handlers::parse_coords(&rest[start..]));
+                        if origin.is_some() {
+                            rest = &rest[..rest.len() - 3];
+                        }
+                        // If player name not provided, use the sender
+                        let player_name = Some(rest.first().cloned().unwrap_or(sender));
+                        self.handle_record_start(
+                            &test_name,
+                            test_loader,
+                            player_name,
+                            origin,
+                            scan_radius,
+                        )
+                        .await?;
                     }
                     "!assert_changes" => {
                         self.handle_record_assert_changes().await?;
                     }
 
+                    "!assert_region" => {
+                        self.handle_record_assert_region(&args).await?;
+                    }
+
                     "!tick" | "!next" => {
                         self.handle_record_tick().await?;
                     }
@@ -235,6 +587,22 @@ impl TestExecutor {
                         }
                     }
 
+                    "!origin" => {
+                        self.handle_record_origin(&args).await?;
+                    }
+
+                    "!radius" => {
+                        self.handle_record_radius(&args).await?;
+                    }
+
+                    "!undo" => {
+                        self.handle_record_undo().await?;
+                    }
+
+                    "!diff" => {
+                        self.handle_record_diff(&args).await?;
+                    }
+
                     "!cancel" => {
                         self.handle_record_cancel().await?;
                     }
@@ -259,12 +627,28 @@ impl TestExecutor {
         &self,
         center: [i32; 3],
         radius: i32,
+    ) -> Result<std::collections::HashMap<[i32; 3], String>> {
+        self.scan_region(
+            [center[0] - radius, center[1] - radius, center[2] - radius],
+            [center[0] + radius, center[1] + radius, center[2] + radius],
+        )
+        .await
+    }
+
+    /// Scan blocks between two corners, inclusive (ignores air)
+    async fn scan_region(
+        &self,
+        corner_a: [i32; 3],
+        corner_b: [i32; 3],
     ) -> Result<std::collections::HashMap<[i32; 3], String>> {
         let mut blocks = std::collections::HashMap::new();
+        let (min_y, max_y) = self.dimension.y_range();
+        let min = [0, 1, 2].map(|axis| corner_a[axis].min(corner_b[axis]));
+        let max = [0, 1, 2].map(|axis| corner_a[axis].max(corner_b[axis]));
 
-        for x in (center[0] - radius)..=(center[0] + radius) {
-            for y in (center[1] - radius).max(-64)..=(center[1] + radius).min(319) {
-                for z in (center[2] - radius)..=(center[2] + radius) {
+        for x in min[0]..=max[0] {
+            for y in min[1].max(min_y)..=max[1].min(max_y) {
+                for z in min[2]..=max[2] {
                     let pos = [x, y, z];
                     if let Ok(Some(block)) = self.bot.get_block(pos).await {
                         let block_id = block::extract_block_id(&block);
@@ -281,220 +665,840 @@ impl TestExecutor {
     }
 
     /// Run tests in parallel with merged timeline
+    /// Tests with dependencies run in later waves, after their prerequisites have passed.
+    /// Each wave is split by dimension, with the bot moved into that dimension first.
+    /// `test_options` holds each test's loaded options, in the same order as the tests.
     pub async fn run_tests_parallel(
         &mut self,
         tests_with_offsets: &[(TestSpec, [i32; 3])],
+        test_options: &[TestOptions],
         break_after_setup: bool,
     ) -> Result<TestRunOutput> {
-        let verbose = self.verbose;
+        // Blocks outside a test's cleanup region survive cleanup and can break its neighbors
+        for (test, offset) in tests_with_offsets {
+            if let Some(escape) = validate::cleanup_escape(test, *offset) {
+                if self.strict {
+                    anyhow::bail!("[{}] {} (--strict)", test.name, escape);
+                }
+                tracing::warn!(test = %test.name, "{}", escape);
+            }
+        }
 
-        if verbose {
-            println!(
-                "{} Running {} tests in parallel\n",
-                "→".blue().bold(),
-                tests_with_offsets.len()
-            );
+        let names_and_deps: Vec<(&str, &[String])> = tests_with_offsets
+            .iter()
+            .map(|(test, _)| (test.name.as_str(), test.dependencies.as_slice()))
+            .collect();
+        let layers = dependencies::dependency_layers(&names_and_deps)?;
+        let dimensions: Vec<Dimension> = test_options
+            .iter()
+            .map(|options| options.dimension)
+            .collect();
+
+        let depends_on_blocked = tests_with_offsets.iter().any(|(test, _)| {
+            test.dependencies
+                .iter()
+                .any(|dep| self.blocked_tests.contains(dep))
+        });
+
+        if layers.len() <= 1
+            && !depends_on_blocked
+            && dimensions.iter().all(|d| *d == self.dimension)
+        {
+            return self
+                .run_batched(tests_with_offsets, test_options, break_after_setup)
+                .await;
         }
 
-        // Build global merged timeline using flint-core
-        let aggregate = TimelineAggregate::from_tests(tests_with_offsets);
+        let mut output = TestRunOutput::default();
+        // Tests that failed or were skipped, here or in an earlier chunk - their dependents are skipped too
+        let mut blocked: HashSet<String> = self.blocked_tests.clone();
+        let start_dimension = self.dimension;
+        let mut first_run = true;
+        let bail_after = self.bail_after;
+
+        'layers: for (layer_idx, layer) in layers.iter().enumerate() {
+            let mut wave: Vec<usize> = Vec::new();
+            for &idx in layer {
+                let test = &tests_with_offsets[idx].0;
+                if let Some(dep) = test.dependencies.iter().find(|dep| blocked.contains(*dep)) {
+                    let reason = format!("dependency '{}' did not pass", dep);
+                    tracing::info!(test = %test.name, reason = %reason, "Skipped");
+                    blocked.insert(test.name.clone());
+                    self.emit(RunEvent::TestSkipped {
+                        name: test.name.clone(),
+                        reason: reason.clone(),
+                    });
+                    output.skipped.push((test.name.clone(), reason));
+                    continue;
+                }
+                wave.push(idx);
+            }
 
-        if verbose {
-            println!("  Global timeline: {} ticks", aggregate.max_tick);
-            println!(
-                "  {} unique tick steps with actions",
-                aggregate.unique_tick_count()
-            );
-            if !aggregate.breakpoints.is_empty() {
-                let mut sorted_breakpoints: Vec<_> = aggregate.breakpoints.iter().collect();
-                sorted_breakpoints.sort();
-                println!(
-                    "  {} breakpoints at ticks: {:?}",
-                    aggregate.breakpoints.len(),
-                    sorted_breakpoints
+            if wave.is_empty() {
+                continue;
+            }
+
+            if layers.len() > 1 {
+                tracing::debug!(
+                    wave = layer_idx + 1,
+                    waves = layers.len(),
+                    "Dependency wave"
                 );
             }
-            if break_after_setup {
-                println!("  {} Break after setup enabled", "→".yellow());
+
+            // Offsets stay as assigned, so tests sharing a dimension keep separate grid cells
+            let mut wave_dimensions: Vec<Dimension> = Vec::new();
+            for &idx in &wave {
+                if !wave_dimensions.contains(&dimensions[idx]) {
+                    wave_dimensions.push(dimensions[idx]);
+                }
+            }
+
+            for dimension in wave_dimensions {
+                let members: Vec<usize> = wave
+                    .iter()
+                    .copied()
+                    .filter(|&idx| dimensions[idx] == dimension)
+                    .collect();
+                let group: Vec<(TestSpec, [i32; 3])> = members
+                    .iter()
+                    .map(|&idx| tests_with_offsets[idx].clone())
+                    .collect();
+                let group_options: Vec<TestOptions> = members
+                    .iter()
+                    .map(|&idx| test_options[idx].clone())
+                    .collect();
+
+                self.enter_dimension(dimension).await?;
+                self.bail_after = remaining_bail_budget(bail_after, &output.results);
+                let group_output = self
+                    .run_batched(&group, &group_options, break_after_setup && first_run)
+                    .await?;
+                first_run = false;
+
+                for result in group_output.results.iter().filter(|r| !r.success) {
+                    blocked.insert(result.test_name.clone());
+                }
+                output.results.extend(group_output.results);
+                output.failures.extend(group_output.failures);
+                output.skipped.extend(group_output.skipped);
+                output
+                    .expected_failures
+                    .extend(group_output.expected_failures);
+
+                if group_output.aborted {
+                    output.aborted = true;
+                    break 'layers;
+                }
+                if self.fail_fast && !output.failures.is_empty() {
+                    break 'layers;
+                }
+                if remaining_bail_budget(bail_after, &output.results) == Some(0) {
+                    break 'layers;
+                }
+            }
+        }
+        self.bail_after = bail_after;
+
+        // Tests in waves that never started are reported as aborted, or cut by --bail-after, too
+        let not_started = if output.aborted {
+            Some("run aborted before this test started")
+        } else if remaining_bail_budget(bail_after, &output.results) == Some(0) {
+            Some("not run: --bail-after limit reached")
+        } else {
+            None
+        };
+        if let Some(reason) = not_started {
+            for (test, _) in tests_with_offsets {
+                let seen = output.results.iter().any(|r| r.test_name == test.name)
+                    || output.skipped.iter().any(|(name, _)| *name == test.name);
+                if !seen {
+                    let reason = reason.to_string();
+                    self.emit(RunEvent::TestSkipped {
+                        name: test.name.clone(),
+                        reason: reason.clone(),
+                    });
+                    output.skipped.push((test.name.clone(), reason));
+                }
             }
-            println!();
         }
 
-        // Clean all test areas before starting
-        if verbose {
-            println!("{} Cleaning all test areas...", "→".blue());
+        self.enter_dimension(start_dimension).await?;
+        Ok(output)
+    }
+
+    /// Run independent tests through merged timelines, at most `max_parallel` at a time
+    /// Each batch is built, run and cleaned up before the next one is placed on the grid.
+    async fn run_batched(
+        &mut self,
+        tests_with_offsets: &[(TestSpec, [i32; 3])],
+        test_options: &[TestOptions],
+        break_after_setup: bool,
+    ) -> Result<TestRunOutput> {
+        let batch_size = self.max_parallel.unwrap_or(tests_with_offsets.len()).max(1);
+        if tests_with_offsets.len() <= batch_size {
+            return self
+                .run_merged_timeline(tests_with_offsets, test_options, break_after_setup)
+                .await;
         }
-        for (test, offset) in tests_with_offsets.iter() {
-            let region = test.cleanup_region();
-            let world_min = actions::apply_offset(region[0], *offset);
-            let world_max = actions::apply_offset(region[1], *offset);
-            let cmd = format!(
-                "fill {} {} {} {} {} {} air",
-                world_min[0], world_min[1], world_min[2], world_max[0], world_max[1], world_max[2]
+
+        let batches: Vec<&[(TestSpec, [i32; 3])]> = tests_with_offsets.chunks(batch_size).collect();
+        let batch_options: Vec<&[TestOptions]> = test_options.chunks(batch_size).collect();
+        let mut output = TestRunOutput::default();
+        let bail_after = self.bail_after;
+
+        for (batch_idx, batch) in batches.iter().enumerate() {
+            tracing::debug!(
+                batch = batch_idx + 1,
+                batches = batches.len(),
+                tests = batch.len(),
+                "Starting batch"
             );
-            self.bot.send_command(&cmd).await?;
+            // Failures in earlier batches use up part of the --bail-after budget
+            self.bail_after = remaining_bail_budget(bail_after, &output.results);
+            let batch_output = self
+                .run_merged_timeline(
+                    batch,
+                    batch_options[batch_idx],
+                    break_after_setup && batch_idx == 0,
+                )
+                .await?;
+            output.results.extend(batch_output.results);
+            output.failures.extend(batch_output.failures);
+            output.skipped.extend(batch_output.skipped);
+            output
+                .expected_failures
+                .extend(batch_output.expected_failures);
+
+            let bailed = remaining_bail_budget(bail_after, &output.results) == Some(0);
+            if batch_output.aborted || bailed {
+                output.aborted |= batch_output.aborted;
+                let reason = if batch_output.aborted {
+                    "run aborted before this test started"
+                } else {
+                    "not run: --bail-after limit reached"
+                };
+                for (test, _) in batches[batch_idx + 1..]
+                    .iter()
+                    .flat_map(|batch| batch.iter())
+                {
+                    self.emit(RunEvent::TestSkipped {
+                        name: test.name.clone(),
+                        reason: reason.to_string(),
+                    });
+                    output.skipped.push((test.name.clone(), reason.to_string()));
+                }
+                break;
+            }
+            if self.fail_fast && !output.failures.is_empty() {
+                break;
+            }
+        }
+        self.bail_after = bail_after;
+
+        Ok(output)
+    }
+
+    /// Per-test `before` hooks on the cleaned areas, then freeze time and run the warmup sprint
+    /// Shared by the first run and step-back replays, so a replay starts from the same state.
+    /// Tests marked in `skip` are left out. Returns which tests had a `before` command rejected.
+    async fn set_up_tests(
+        &mut self,
+        tests_with_offsets: &[(TestSpec, [i32; 3])],
+        test_options: &[TestOptions],
+        skip: &[bool],
+    ) -> Result<Vec<bool>> {
+        let mut hook_failed: Vec<bool> = vec![false; tests_with_offsets.len()];
+        for (test_idx, (test, offset)) in tests_with_offsets.iter().enumerate() {
+            if skip[test_idx] {
+                continue;
+            }
+            let before = &test_options[test_idx].before;
+            if let Err(e) = self.run_test_hooks(test, *offset, before).await {
+                eprintln!("{} [{}] before: {:#}", "Error:".red().bold(), test.name, e);
+                hook_failed[test_idx] = true;
+            }
         }
         tokio::time::sleep(tokio::time::Duration::from_millis(CLEANUP_DELAY_MS)).await;
 
-        // Freeze time globally
+        // Freeze time globally (a replay finds it frozen already)
         self.bot.send_command("tick freeze").await?;
         tokio::time::sleep(tokio::time::Duration::from_millis(COMMAND_DELAY_MS)).await;
 
+        // Warm up: let the world settle before tick 0 (the longest warmup in the batch wins)
+        let warmup_ticks = test_options
+            .iter()
+            .zip(skip)
+            .filter(|(_, skip)| !**skip)
+            .map(|(options, _)| options.warmup_ticks)
+            .max()
+            .unwrap_or(0);
+        if warmup_ticks > 0 {
+            tracing::debug!(ticks = warmup_ticks, "Warming up");
+            let timing = tick::sprint_ticks(&mut self.bot, warmup_ticks).await?;
+            self.trace(
+                0,
+                trace::TraceRecord::Advance {
+                    mode: "warmup",
+                    ticks: warmup_ticks,
+                    ms: timing.total_ms,
+                    mspt: timing.mspt,
+                },
+            );
+            tokio::time::sleep(tokio::time::Duration::from_millis(
+                timing.total_ms.max(MIN_RETRY_DELAY_MS),
+            ))
+            .await;
+        }
+        Ok(hook_failed)
+    }
+
+    /// Best-effort step back: clean the active test areas and replay the timeline up to `target`
+    /// Minecraft can't un-tick, so this re-runs the setup and ticks 0..=target from scratch
+    /// (without assertions). Nondeterministic mechanics may end up in a different state than the
+    /// first time.
+    async fn replay_to(
+        &mut self,
+        tests_with_offsets: &[(TestSpec, [i32; 3])],
+        test_options: &[TestOptions],
+        aggregate: &TimelineAggregate,
+        target: u32,
+        tests_cleaned: &[bool],
+    ) -> Result<()> {
+        tracing::info!(target, "Replaying ticks into freshly cleaned test areas");
+        for (idx, (test, offset)) in tests_with_offsets.iter().enumerate() {
+            if tests_cleaned[idx] {
+                continue;
+            }
+            self.reset_test_area(test, *offset).await?;
+        }
+        tokio::time::sleep(tokio::time::Duration::from_millis(CLEANUP_DELAY_MS)).await;
+        // The tests were judged on their first setup; a hook rejected now is only reported
+        self.set_up_tests(tests_with_offsets, test_options, tests_cleaned)
+            .await?;
+
+        let mut replay_tick = 0;
+        loop {
+            if let Some(entries) = aggregate.timeline.get(&replay_tick) {
+                for (test_idx, entry, value_idx) in entries {
+                    if tests_cleaned[*test_idx]
+                        || matches!(entry.action_type, ActionType::Assert { .. })
+                    {
+                        continue;
+                    }
+                    let (test, offset) = &tests_with_offsets[*test_idx];
+                    self.execute_action(
+                        replay_tick,
+                        entry,
+                        *value_idx,
+                        *offset,
+                        &spec::CheckFlags::default(),
+                        test_options[*test_idx].block_nbt_at(test, entry),
+                    )
+                    .await?;
+                }
+            }
+            if replay_tick >= target {
+                break;
+            }
+            let next_tick = aggregate
+                .next_event_tick(replay_tick)
+                .filter(|&tick| tick > replay_tick)
+                .map_or(target, |tick| tick.min(target));
+            tick::sprint_ticks(&mut self.bot, next_tick - replay_tick).await?;
+            replay_tick = next_tick;
+        }
+        Ok(())
+    }
+
+    /// Move the bot into a dimension so commands and block reads happen there
+    /// Keeps the bot's x/y/z so it stays next to the test grid
+    async fn enter_dimension(&mut self, dimension: Dimension) -> Result<()> {
+        if self.dimension == dimension {
+            return Ok(());
+        }
+        let pos = self.bot.get_position()?;
+        tracing::debug!(dimension = dimension.id(), "Moving bot to dimension");
+        self.bot
+            .send_command(&format!(
+                "execute in {} run tp @s {} {} {}",
+                dimension.id(),
+                pos[0],
+                pos[1],
+                pos[2]
+            ))
+            .await?;
+        tokio::time::sleep(tokio::time::Duration::from_millis(
+            DIMENSION_CHANGE_DELAY_MS,
+        ))
+        .await;
+        self.dimension = dimension;
+        Ok(())
+    }
+
+    /// Run a set of independent tests in parallel with a single merged timeline
+    async fn run_merged_timeline(
+        &mut self,
+        tests_with_offsets: &[(TestSpec, [i32; 3])],
+        test_options: &[TestOptions],
+        break_after_setup: bool,
+    ) -> Result<TestRunOutput> {
+        // Build global merged timeline using flint-core
+        let aggregate = TimelineAggregate::from_tests(tests_with_offsets);
+        // The aggregate only has the ticks; where a declared range ends comes from the options
+        let breakpoint_ranges: Vec<(u32, u32)> = test_options
+            .iter()
+            .flat_map(|options| options.breakpoint_ranges.iter().copied())
+            .collect();
+
+        let mut sorted_breakpoints: Vec<_> = aggregate.breakpoints.iter().collect();
+        sorted_breakpoints.sort();
+        tracing::debug!(
+            tests = tests_with_offsets.len(),
+            max_tick = aggregate.max_tick,
+            unique_ticks = aggregate.unique_tick_count(),
+            breakpoints = ?sorted_breakpoints,
+            break_after_setup,
+            "Running tests in parallel"
+        );
+
+        self.trace(
+            0,
+            trace::TraceRecord::Start {
+                tests: tests_with_offsets
+                    .iter()
+                    .map(|(test, _)| test.name.clone())
+                    .collect(),
+            },
+        );
+
+        let run_started = std::time::Instant::now();
+        let setup_started = std::time::Instant::now();
+
+        // Clean all test areas before starting (saving them first with --cleanup-mode restore)
+        tracing::debug!(mode = ?self.cleanup_mode, "Cleaning all test areas");
+        let saved_areas = self.prepare_test_areas(tests_with_offsets).await?;
+
+        // A rejected `before` hook fails its test
+        let hook_failed = self
+            .set_up_tests(
+                tests_with_offsets,
+                test_options,
+                &vec![false; tests_with_offsets.len()],
+            )
+            .await?;
+
+        self.profile.record(Phase::Setup, setup_started);
+
         // Break after setup if requested
         let mut stepping_mode = false;
+        // Set when the player aborts at a breakpoint: stop the timeline and clean up
+        let mut aborted = false;
+        // Stopped by `--bail-after`: unfinished tests without failures count as not run
+        let mut bailed = false;
         if break_after_setup {
-            let should_continue = tick::wait_for_step(
+            let paused = std::time::Instant::now();
+            match tick::wait_for_step_command(
                 &mut self.bot,
+                &self.step_keys,
                 "After test setup (cleanup complete, time frozen)",
+                false,
+                &self.interrupted,
             )
-            .await?;
-            stepping_mode = !should_continue;
+            .await?
+            {
+                tick::StepCommand::Abort => aborted = true,
+                command => stepping_mode = command != tick::StepCommand::Continue,
+            }
+            self.profile.record(Phase::Paused, paused);
         }
 
         // Track results per test: (passed_assertions, failed_assertions)
         let mut test_results: Vec<(usize, usize)> = vec![(0, 0); tests_with_offsets.len()];
+        // Rejected hooks and actions per test: a broken setup fails even an `expect_failure` test
+        let mut test_errors: Vec<usize> = hook_failed
+            .iter()
+            .map(|failed| usize::from(*failed))
+            .collect();
 
-        // Track first failure detail per test
-        let mut test_failures: Vec<Option<AssertFailure>> =
-            (0..tests_with_offsets.len()).map(|_| None).collect();
+        // Track every failure detail per test
+        let mut test_failures: Vec<Vec<AssertFailure>> = vec![Vec::new(); tests_with_offsets.len()];
 
         // Track which tests have been cleaned up
         let mut tests_cleaned: Vec<bool> = vec![false; tests_with_offsets.len()];
+        // Finished tests whose areas wait for the end of the run (--break-before-cleanup)
+        let mut deferred_cleanup: Vec<usize> = Vec::new();
 
-        // Calculate max tick for each test
-        let mut test_max_ticks: Vec<u32> = vec![0; tests_with_offsets.len()];
-        for (tick_num, entries) in &aggregate.timeline {
-            for (test_idx, _, _) in entries {
-                test_max_ticks[*test_idx] = test_max_ticks[*test_idx].max(*tick_num);
-            }
-        }
+        // Track wall-clock span per test: first executed action until cleanup
+        let mut test_started: Vec<Option<std::time::Instant>> =
+            vec![None; tests_with_offsets.len()];
+        let mut test_elapsed_ms: Vec<u64> = vec![0; tests_with_offsets.len()];
+
+        // Action logs are recorded inside a span per test
+        let test_spans: Vec<tracing::Span> = tests_with_offsets
+            .iter()
+            .map(|(test, _)| tracing::debug_span!("test", name = %test.name))
+            .collect();
 
-        let show_progress = !verbose && !self.quiet;
+        let test_max_ticks = test_max_ticks(&aggregate, tests_with_offsets, test_options);
+        let end_tick = test_max_ticks
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(aggregate.max_tick);
+
+        // Failed assertions still inside their `within` window: (scheduled tick, index in that tick)
+        let mut pending_asserts: Vec<(u32, usize)> = Vec::new();
+
+        // Verbose runs log every action, which would tear up the progress bar
+        let show_progress = !self.verbose && !self.quiet;
+        let bar_fill = if self.ascii_progress { "#" } else { "█" };
         let fail_fast = self.fail_fast;
+        let fail_fast_per_test = self.fail_fast_per_test;
+
+        // Highest tick whose assertions have been counted (ticks re-run after a step-back are skipped)
+        let mut judged_through: Option<u32> = None;
 
         // Execute merged timeline
         let mut current_tick = 0;
-        while current_tick <= aggregate.max_tick {
-            if let Some(entries) = aggregate.timeline.get(&current_tick) {
-                for (test_idx, entry, value_idx) in entries {
-                    let (test, offset) = &tests_with_offsets[*test_idx];
+        while !aborted && current_tick <= end_tick {
+            // Ctrl-C takes the same cleanup path as aborting at a breakpoint
+            if self.is_interrupted() {
+                aborted = true;
+                break;
+            }
 
-                    match self
-                        .execute_action(current_tick, entry, *value_idx, *offset)
+            // Re-check deferred assertions first, then run this tick's entries
+            let scheduled = aggregate.timeline.get(&current_tick).map_or(0, Vec::len);
+            let due: Vec<(u32, usize)> = std::mem::take(&mut pending_asserts)
+                .into_iter()
+                .chain((0..scheduled).map(|entry_pos| (current_tick, entry_pos)))
+                .collect();
+            for (origin_tick, entry_pos) in due {
+                let (test_idx, entry, value_idx) = &aggregate.timeline[&origin_tick][entry_pos];
+                // Mask remaining actions of a test that already failed
+                if fail_fast_per_test
+                    && (test_results[*test_idx].1 > 0 || test_errors[*test_idx] > 0)
+                {
+                    continue;
+                }
+                // Assertions replayed after a step-back were already judged
+                if origin_tick == current_tick
+                    && judged_through.is_some_and(|tick| current_tick <= tick)
+                    && matches!(entry.action_type, ActionType::Assert { .. })
+                {
+                    continue;
+                }
+                let (test, offset) = &tests_with_offsets[*test_idx];
+                let span = test_spans[*test_idx].clone();
+                if test_started[*test_idx].is_none() {
+                    tracing::debug!(test = %test.name, tick = current_tick, "Test started");
+                }
+                test_started[*test_idx].get_or_insert_with(std::time::Instant::now);
+                let deadline = test_options[*test_idx]
+                    .assert_window(test, entry)
+                    .map(|window| origin_tick + window);
+                let flags = test_options[*test_idx].check_flags(test, entry);
+
+                let entry_started = std::time::Instant::now();
+                let outcome = match test_options[*test_idx].extra_checks_at(test, entry) {
+                    Some(checks) => {
+                        actions::run_extra_checks(
+                            &mut self.bot,
+                            current_tick,
+                            checks,
+                            *offset,
+                            test_options[*test_idx].dimension,
+                        )
+                        .instrument(span)
                         .await
-                    {
-                        Ok(ActionOutcome::AssertPassed) => {
-                            test_results[*test_idx].0 += 1;
+                    }
+                    None => {
+                        let nbt = test_options[*test_idx].block_nbt_at(test, entry);
+                        self.execute_action(current_tick, entry, *value_idx, *offset, &flags, nbt)
+                            .instrument(span)
+                            .await
+                    }
+                };
+                let phase = if matches!(entry.action_type, ActionType::Assert { .. }) {
+                    Phase::Assertions
+                } else {
+                    Phase::Actions
+                };
+                self.profile.record(phase, entry_started);
+                let retry = deadline.is_some_and(|deadline| current_tick < deadline);
+                let records = trace::TraceRecord::for_outcome(&test.name, &outcome, retry);
+                if records.is_empty() {
+                    self.trace_commands(current_tick);
+                }
+                for record in records {
+                    self.trace(current_tick, record);
+                }
+                match outcome {
+                    // Not there yet, but the block may still update within the window
+                    Ok(actions::Outcome::AssertFailed(_)) if retry => {
+                        pending_asserts.push((origin_tick, entry_pos));
+                    }
+                    Ok(actions::Outcome::AssertPassed) => {
+                        test_results[*test_idx].0 += 1;
+                    }
+                    Ok(actions::Outcome::Action) => {}
+                    Ok(actions::Outcome::AssertFailed(details)) => {
+                        test_results[*test_idx].1 += details.len();
+                        for detail in &details {
+                            tracing::debug!(
+                                test = %test.name,
+                                tick = current_tick,
+                                pos = ?detail.position,
+                                expected = %String::from(&detail.expected),
+                                actual = %String::from(&detail.actual),
+                                "Assertion failed"
+                            );
                         }
-                        Ok(ActionOutcome::Action) => {}
-                        Ok(ActionOutcome::AssertFailed(detail)) => {
-                            test_results[*test_idx].1 += 1;
-                            if verbose {
-                                println!(
-                                    "    {} [{}] Tick {}: expected {}, got {}",
-                                    "✗".red().bold(),
-                                    test.name,
-                                    current_tick,
-                                    String::from(&detail.expected).green(),
-                                    String::from(&detail.actual).red()
-                                );
-                            }
-                            // Store first failure per test
-                            if test_failures[*test_idx].is_none() {
-                                test_failures[*test_idx] = Some(detail);
-                            }
-                            if fail_fast {
-                                break;
-                            }
+                        let detail = &details[0];
+                        let failure_reason = format!(
+                            "[{}] Assertion failed at tick {}: ({}, {}, {}) expected {}, got {}",
+                            test.name,
+                            current_tick,
+                            detail.position[0],
+                            detail.position[1],
+                            detail.position[2],
+                            String::from(&detail.expected),
+                            String::from(&detail.actual)
+                        );
+                        // A failure an `expect_failure` test is waiting for needs no attention
+                        let expected = test_options[*test_idx].expect_failure;
+                        // Dump the area on a test's first failure, while it's still there
+                        if test_failures[*test_idx].is_empty() && !expected {
+                            self.dump_failure(test, *offset, detail).await;
                         }
-                        Err(e) => {
-                            test_results[*test_idx].1 += 1;
-                            if verbose {
-                                println!(
-                                    "    {} [{}] Tick {}: {}",
-                                    "✗".red().bold(),
-                                    test.name,
-                                    current_tick,
-                                    e.to_string().red()
-                                );
-                            }
-                            if fail_fast {
-                                break;
+                        test_failures[*test_idx].extend(details);
+                        if self.break_on_failure && !expected {
+                            let paused = std::time::Instant::now();
+                            let command = tick::wait_for_step_command(
+                                &mut self.bot,
+                                &self.step_keys,
+                                &failure_reason,
+                                false,
+                                &self.interrupted,
+                            )
+                            .await?;
+                            self.profile.record(Phase::Paused, paused);
+                            match command {
+                                tick::StepCommand::Abort => {
+                                    aborted = true;
+                                    break;
+                                }
+                                command => stepping_mode = command != tick::StepCommand::Continue,
                             }
                         }
+                        if fail_fast && !expected {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        test_errors[*test_idx] += 1;
+                        tracing::debug!(test = %test.name, tick = current_tick, error = %e, "Action failed");
+                        // Action errors (e.g. a rejected block id) have no assert detail
+                        eprintln!(
+                            "\n{} [{}] Tick {}: {}",
+                            "Error:".red().bold(),
+                            test.name,
+                            current_tick,
+                            e
+                        );
+                        if fail_fast && !test_options[*test_idx].expect_failure {
+                            break;
+                        }
                     }
                 }
             }
 
+            if aborted {
+                break;
+            }
+
+            judged_through =
+                Some(judged_through.map_or(current_tick, |tick| tick.max(current_tick)));
+
             // Break out of the timeline loop on first failure
-            if fail_fast && test_results.iter().any(|(_, failed)| *failed > 0) {
+            let failing = |idx: usize| {
+                test_errors[idx] > 0
+                    || (test_results[idx].1 > 0 && !test_options[idx].expect_failure)
+            };
+            if fail_fast && (0..tests_with_offsets.len()).any(failing) {
                 break;
             }
 
+            if let Some(limit) = self.bail_after {
+                let failing = (0..tests_with_offsets.len())
+                    .filter(|&idx| failing(idx))
+                    .count();
+                if failing >= limit {
+                    tracing::info!(failing, limit, "Tests failed (--bail-after), cleaning up");
+                    bailed = true;
+                    break;
+                }
+            }
+
             // Clean up tests that have completed
-            for test_idx in 0..tests_with_offsets.len() {
-                if !tests_cleaned[test_idx] && current_tick > test_max_ticks[test_idx] {
-                    let (test, offset) = &tests_with_offsets[test_idx];
-                    if verbose {
-                        println!(
-                            "\n{} Cleaning up test [{}] (completed at tick {})...",
-                            "→".blue(),
-                            test.name,
-                            test_max_ticks[test_idx]
-                        );
+            let cleanup_started = std::time::Instant::now();
+            let completed: Vec<usize> = (0..tests_with_offsets.len())
+                .filter(|idx| !tests_cleaned[*idx] && current_tick > test_max_ticks[*idx])
+                .collect();
+            for &test_idx in &completed {
+                let (test, offset) = &tests_with_offsets[test_idx];
+                tracing::debug!(
+                    test = %test.name,
+                    completed_at = test_max_ticks[test_idx],
+                    "Cleaning up test"
+                );
+                if let Some(failure) = self
+                    .judge_snapshot(
+                        test,
+                        test_options[test_idx].snapshot_region,
+                        *offset,
+                        test_max_ticks[test_idx],
+                        test_results[test_idx].1 == 0 && test_errors[test_idx] == 0,
+                    )
+                    .await?
+                {
+                    test_results[test_idx].1 += 1;
+                    if test_failures[test_idx].is_empty() {
+                        self.dump_failure(test, *offset, &failure).await;
                     }
-                    let region = test.cleanup_region();
-                    let world_min = actions::apply_offset(region[0], *offset);
-                    let world_max = actions::apply_offset(region[1], *offset);
-                    let cmd = format!(
-                        "fill {} {} {} {} {} {} air",
-                        world_min[0],
-                        world_min[1],
-                        world_min[2],
-                        world_max[0],
-                        world_max[1],
-                        world_max[2]
-                    );
-                    self.bot.send_command(&cmd).await?;
+                    test_failures[test_idx].push(failure);
+                }
+                self.run_after_hooks(test, *offset, &test_options[test_idx].after)
+                    .await;
+            }
+            if !completed.is_empty() {
+                if self.break_before_cleanup {
+                    deferred_cleanup.extend(&completed);
+                } else {
+                    self.finish_test_areas(tests_with_offsets, &completed, &saved_areas)
+                        .await?;
+                }
+                for &test_idx in &completed {
                     tests_cleaned[test_idx] = true;
-                    tokio::time::sleep(tokio::time::Duration::from_millis(COMMAND_DELAY_MS)).await;
+                    test_elapsed_ms[test_idx] = elapsed_ms(test_started[test_idx]);
+                    self.emit(RunEvent::TestResult {
+                        name: tests_with_offsets[test_idx].0.name.clone(),
+                        success: judge(
+                            test_results[test_idx].1,
+                            test_errors[test_idx],
+                            test_options[test_idx].expect_failure,
+                        ),
+                        tick: test_max_ticks[test_idx],
+                    });
                 }
+                if !self.break_before_cleanup {
+                    let pause = self.cleanup_pause_ms(completed.len());
+                    tokio::time::sleep(tokio::time::Duration::from_millis(pause)).await;
+                }
+                self.profile.record(Phase::Cleanup, cleanup_started);
             }
 
             // Check for breakpoint
             if aggregate.breakpoints.contains(&current_tick) || stepping_mode {
-                let should_continue = tick::wait_for_step(
-                    &mut self.bot,
-                    &format!("End of tick {} (before step to next tick)", current_tick),
-                )
-                .await?;
-                stepping_mode = !should_continue;
+                let paused = std::time::Instant::now();
+                let command = loop {
+                    let command = tick::wait_for_step_command(
+                        &mut self.bot,
+                        &self.step_keys,
+                        &format!("End of tick {} (before step to next tick)", current_tick),
+                        current_tick > 0,
+                        &self.interrupted,
+                    )
+                    .await?;
+                    if command != tick::StepCommand::StepBack {
+                        break command;
+                    }
+                    current_tick -= 1;
+                    self.replay_to(
+                        tests_with_offsets,
+                        test_options,
+                        &aggregate,
+                        current_tick,
+                        &tests_cleaned,
+                    )
+                    .await?;
+                };
+                self.profile.record(Phase::Paused, paused);
+                if command == tick::StepCommand::Abort {
+                    aborted = true;
+                    break;
+                }
+                let should_continue = command == tick::StepCommand::Continue;
+                let range_end = ends_breakpoint_range(&breakpoint_ranges, current_tick);
+                stepping_mode = !should_continue && !range_end;
             }
 
             // Advance to next tick
-            if current_tick < aggregate.max_tick {
-                if stepping_mode {
-                    tick::step_tick(&mut self.bot, verbose).await?;
+            let advance_started = std::time::Instant::now();
+            if current_tick < end_tick {
+                // Deferred assertions are re-checked on every tick of their window
+                if stepping_mode || !pending_asserts.is_empty() {
+                    let ms = tick::step_tick(&mut self.bot).await?;
+                    self.trace(
+                        current_tick,
+                        trace::TraceRecord::Advance {
+                            mode: "step",
+                            ticks: 1,
+                            ms,
+                            mspt: None,
+                        },
+                    );
                     tokio::time::sleep(tokio::time::Duration::from_millis(CLEANUP_DELAY_MS)).await;
                     current_tick += 1;
                 } else {
+                    // Stop at the next event or breakpoint, whichever comes first
+                    let next_breakpoint = aggregate
+                        .breakpoints
+                        .iter()
+                        .copied()
+                        .filter(|&tick| tick > current_tick)
+                        .min();
                     let next_event_tick = aggregate
                         .next_event_tick(current_tick)
-                        .unwrap_or(aggregate.max_tick + 1);
+                        .into_iter()
+                        .chain(next_breakpoint)
+                        .min()
+                        .unwrap_or(end_tick + 1);
 
-                    let ticks_to_sprint = if next_event_tick <= aggregate.max_tick {
+                    let ticks_to_sprint = if next_event_tick <= end_tick {
                         next_event_tick - current_tick
                     } else {
-                        aggregate.max_tick - current_tick
+                        end_tick - current_tick
                     };
 
-                    let sprint_time_ms = if ticks_to_sprint == 1 {
-                        tick::step_tick(&mut self.bot, verbose).await?
+                    let (sprint_time_ms, mspt) = if ticks_to_sprint == 1 {
+                        (tick::step_tick(&mut self.bot).await?, None)
                     } else if ticks_to_sprint > 1 {
-                        tick::sprint_ticks(&mut self.bot, ticks_to_sprint, verbose).await?
+                        let timing = tick::sprint_ticks(&mut self.bot, ticks_to_sprint).await?;
+                        if let Some(mspt) = timing.mspt {
+                            self.perf.record(mspt, ticks_to_sprint);
+                        }
+                        (timing.total_ms, timing.mspt)
                     } else {
-                        0
+                        (0, None)
                     };
+                    if ticks_to_sprint > 0 {
+                        self.trace(
+                            current_tick,
+                            trace::TraceRecord::Advance {
+                                mode: if ticks_to_sprint == 1 {
+                                    "step"
+                                } else {
+                                    "sprint"
+                                },
+                                ticks: ticks_to_sprint,
+                                ms: sprint_time_ms,
+                                mspt,
+                            },
+                        );
+                    }
 
                     let retry_delay = sprint_time_ms.max(MIN_RETRY_DELAY_MS);
                     tokio::time::sleep(tokio::time::Duration::from_millis(retry_delay)).await;
@@ -504,10 +1508,29 @@ impl TestExecutor {
             } else {
                 current_tick += 1;
             }
+            self.profile.record(Phase::Advance, advance_started);
+
+            self.flush_trace(current_tick);
+            if current_tick <= end_tick {
+                self.emit(RunEvent::Tick {
+                    tick: current_tick,
+                    max_tick: end_tick,
+                });
+            }
 
             // Update progress bar in non-verbose mode
             if show_progress {
-                print_progress_bar(current_tick.min(aggregate.max_tick), aggregate.max_tick);
+                let tick = current_tick.min(end_tick);
+                match self.progress_mode {
+                    ProgressMode::Ticks => print_progress_bar(tick, end_tick, bar_fill),
+                    ProgressMode::Tests => print_test_progress_bar(
+                        bar_fill,
+                        tests_cleaned.iter().filter(|cleaned| **cleaned).count(),
+                        tests_with_offsets.len(),
+                        tick,
+                        end_tick,
+                    ),
+                }
             }
         }
 
@@ -516,75 +1539,128 @@ impl TestExecutor {
             println!();
         }
 
+        // Tests that hadn't completed when the run was aborted are reported as skipped
+        if aborted {
+            tracing::info!(tick = current_tick, "Run aborted, cleaning up");
+        }
+        let finished = if aborted || bailed {
+            // A test that already failed keeps its verdict
+            (0..tests_with_offsets.len())
+                .map(|idx| tests_cleaned[idx] || test_results[idx].1 > 0 || test_errors[idx] > 0)
+                .collect()
+        } else {
+            vec![true; tests_with_offsets.len()]
+        };
+        let aborted_reason = if self.is_interrupted() {
+            format!("run interrupted at tick {}", current_tick)
+        } else if bailed {
+            format!("not run: stopped at tick {} by --bail-after", current_tick)
+        } else {
+            format!("run aborted at tick {}", current_tick)
+        };
+
+        // Last look at the finished build before anything is cleaned up; any reply continues
+        if self.break_before_cleanup && !aborted && !bailed && !self.is_interrupted() {
+            let paused = std::time::Instant::now();
+            tick::wait_for_step_command(
+                &mut self.bot,
+                &self.step_keys,
+                "Before cleanup (timeline complete, time frozen, test areas intact)",
+                false,
+                &self.interrupted,
+            )
+            .await?;
+            self.profile.record(Phase::Paused, paused);
+        }
+
         // Unfreeze time
+        let cleanup_started = std::time::Instant::now();
         self.bot.send_command("tick unfreeze").await?;
 
+        if !deferred_cleanup.is_empty() {
+            self.finish_test_areas(tests_with_offsets, &deferred_cleanup, &saved_areas)
+                .await?;
+            let pause = self.cleanup_pause_ms(deferred_cleanup.len());
+            tokio::time::sleep(tokio::time::Duration::from_millis(pause)).await;
+        }
+
         // Clean up remaining tests
-        for test_idx in 0..tests_with_offsets.len() {
-            if !tests_cleaned[test_idx] {
-                let (test, offset) = &tests_with_offsets[test_idx];
-                if verbose {
-                    println!(
-                        "\n{} Cleaning up remaining test [{}]...",
-                        "→".blue(),
-                        test.name
-                    );
-                }
-                let region = test.cleanup_region();
-                let world_min = actions::apply_offset(region[0], *offset);
-                let world_max = actions::apply_offset(region[1], *offset);
-                let cmd = format!(
-                    "fill {} {} {} {} {} {} air",
-                    world_min[0],
-                    world_min[1],
-                    world_min[2],
-                    world_max[0],
-                    world_max[1],
-                    world_max[2]
-                );
-                self.bot.send_command(&cmd).await?;
+        let remaining: Vec<usize> = (0..tests_with_offsets.len())
+            .filter(|idx| !tests_cleaned[*idx])
+            .collect();
+        for &test_idx in &remaining {
+            let (test, offset) = &tests_with_offsets[test_idx];
+            tracing::debug!(test = %test.name, "Cleaning up remaining test");
+            self.run_after_hooks(test, *offset, &test_options[test_idx].after)
+                .await;
+        }
+        if !remaining.is_empty() {
+            self.finish_test_areas(tests_with_offsets, &remaining, &saved_areas)
+                .await?;
+            for &test_idx in &remaining {
+                let test = &tests_with_offsets[test_idx].0;
                 tests_cleaned[test_idx] = true;
-                tokio::time::sleep(tokio::time::Duration::from_millis(COMMAND_DELAY_MS)).await;
+                test_elapsed_ms[test_idx] = elapsed_ms(test_started[test_idx]);
+                if finished[test_idx] {
+                    self.emit(RunEvent::TestResult {
+                        name: test.name.clone(),
+                        success: judge(
+                            test_results[test_idx].1,
+                            test_errors[test_idx],
+                            test_options[test_idx].expect_failure,
+                        ),
+                        tick: current_tick.min(test_max_ticks[test_idx]),
+                    });
+                } else {
+                    self.emit(RunEvent::TestSkipped {
+                        name: test.name.clone(),
+                        reason: aborted_reason.clone(),
+                    });
+                }
             }
+            let pause = self.cleanup_pause_ms(remaining.len());
+            tokio::time::sleep(tokio::time::Duration::from_millis(pause)).await;
         }
+        self.profile.record(Phase::Cleanup, cleanup_started);
 
         // Build results
         let results: Vec<TestResult> = tests_with_offsets
             .iter()
             .enumerate()
+            .filter(|(idx, _)| finished[*idx])
             .map(|(idx, (test, _))| {
                 let (passed, failed) = test_results[idx];
-                let success = failed == 0;
-
-                if verbose {
-                    println!();
-                    if success {
-                        println!(
-                            "  {} [{}] Test passed: {} assertions",
-                            "✓".green().bold(),
-                            test.name,
-                            passed
-                        );
-                    } else {
-                        println!(
-                            "  {} [{}] Test failed: {} passed, {} failed",
-                            "✗".red().bold(),
-                            test.name,
-                            passed,
-                            failed
-                        );
-                    }
-                }
+                let errors = test_errors[idx];
+                let expect_failure = test_options[idx].expect_failure;
+                let success = judge(failed, errors, expect_failure);
+
+                tracing::debug!(test = %test.name, success, passed, failed, errors, expect_failure, "Assertions counted");
 
-                if success {
+                let mut result = if success {
                     TestResult::new(test.name.clone())
+                } else if errors > 0 {
+                    TestResult::new(test.name.clone())
+                        .with_failure_reason(format!("{} actions or hooks were rejected", errors))
+                } else if expect_failure {
+                    TestResult::new(test.name.clone())
+                        .with_failure_reason("expected to fail, but every assertion passed".to_string())
                 } else {
                     TestResult::new(test.name.clone())
                         .with_failure_reason(format!("{} assertions failed", failed))
-                }
+                };
+                result.total_ticks = test_max_ticks[idx];
+                result.execution_time_ms = test_elapsed_ms[idx];
+                result
             })
             .collect();
 
+        let expected_failures: HashSet<String> = tests_with_offsets
+            .iter()
+            .zip(test_options)
+            .filter(|(_, options)| options.expect_failure)
+            .map(|((test, _), _)| test.name.clone())
+            .collect();
+
         // Send test results summary to chat
         let total_passed = results.iter().filter(|r| r.success).count();
         let total_failed = results.len() - total_passed;
@@ -597,28 +1673,135 @@ impl TestExecutor {
         self.bot.send_command(&format!("say {}", summary)).await?;
         tokio::time::sleep(tokio::time::Duration::from_millis(COMMAND_DELAY_MS)).await;
 
-        // Send individual test results to chat
-        for result in &results {
-            let status = if result.success { "PASS" } else { "FAIL" };
-            let msg = format!("say [{}] {}", status, result.test_name);
+        // Send individual test results to chat, unless turned off with `!verbose off`
+        let per_test = if self.chat_per_test {
+            results.as_slice()
+        } else {
+            &[]
+        };
+        for result in per_test {
+            let msg = format!(
+                "say [{}] {}",
+                format::status_label(result, expected_failures.contains(&result.test_name)),
+                result.test_name
+            );
             self.bot.send_command(&msg).await?;
             tokio::time::sleep(tokio::time::Duration::from_millis(TEST_RESULT_DELAY_MS)).await;
         }
 
         tokio::time::sleep(tokio::time::Duration::from_millis(CLEANUP_DELAY_MS)).await;
+        // Cleanup and result messages, at the tick the timeline ended on
+        self.flush_trace(current_tick);
+        self.profile.record_total(run_started);
 
         // Collect failure details
         let failures: Vec<(String, AssertFailure)> = tests_with_offsets
             .iter()
             .enumerate()
-            .filter_map(|(idx, (test, _))| {
-                test_failures[idx]
-                    .take()
+            .filter(|(idx, _)| finished[*idx])
+            .flat_map(|(idx, (test, _))| {
+                // An XFAIL's failures are expected; an XPASS fails for having none
+                let details = match (
+                    test_options[idx].expect_failure,
+                    test_results[idx].1,
+                    test_errors[idx],
+                ) {
+                    (false, _, _) => std::mem::take(&mut test_failures[idx]),
+                    (true, 0, 0) => vec![unexpected_pass(test_max_ticks[idx])],
+                    (true, _, _) => Vec::new(),
+                };
+                details
+                    .into_iter()
                     .map(|detail| (test.name.clone(), detail))
             })
             .collect();
 
-        Ok(TestRunOutput { results, failures })
+        let skipped: Vec<(String, String)> = tests_with_offsets
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !finished[*idx])
+            .map(|(_, (test, _))| (test.name.clone(), aborted_reason.clone()))
+            .collect();
+
+        Ok(TestRunOutput {
+            results,
+            failures,
+            skipped,
+            expected_failures,
+            aborted,
+        })
+    }
+
+    /// Compare a finished test's `snapshot_region` with its golden file, or record it
+    /// Only passing tests are recorded. Returns the first mismatch as a failure.
+    async fn judge_snapshot(
+        &self,
+        test: &TestSpec,
+        snapshot_region: Option<[[i32; 3]; 2]>,
+        offset: [i32; 3],
+        tick: u32,
+        passed: bool,
+    ) -> Result<Option<AssertFailure>> {
+        let Some(region) = snapshot_region else {
+            return Ok(None);
+        };
+        let blocks = self
+            .scan_region(
+                actions::apply_offset(region[0], offset),
+                actions::apply_offset(region[1], offset),
+            )
+            .await?;
+        let actual = Snapshot::from_world(region, &blocks, offset);
+        let path = snapshot::path_for(&test.name);
+
+        let golden = match self.snapshot_mode {
+            SnapshotMode::Update => None,
+            SnapshotMode::Compare | SnapshotMode::Record => Snapshot::load(&path)?,
+        };
+        let Some(golden) = golden else {
+            if self.snapshot_mode == SnapshotMode::Compare {
+                tracing::warn!(test = %test.name, "No snapshot recorded, run with --snapshot to record one");
+            } else if passed {
+                actual.save(&path)?;
+                tracing::info!(test = %test.name, path = %path.display(), "Wrote snapshot");
+            } else {
+                tracing::warn!(test = %test.name, "Not recording the snapshot of a failing test");
+            }
+            return Ok(None);
+        };
+
+        let mismatches = golden.diff(&actual);
+        let Some(first) = mismatches.first() else {
+            return Ok(None);
+        };
+        eprintln!(
+            "\n{} [{}] Snapshot mismatch: {} block(s) differ from {}",
+            "✗".red().bold(),
+            test.name,
+            mismatches.len(),
+            path.display()
+        );
+        for mismatch in mismatches.iter().take(SNAPSHOT_DIFF_LINES) {
+            eprintln!(
+                "  ({}, {}, {}) expected {}, got {}",
+                mismatch.pos[0],
+                mismatch.pos[1],
+                mismatch.pos[2],
+                mismatch.expected.green(),
+                mismatch.actual.red()
+            );
+        }
+        if mismatches.len() > SNAPSHOT_DIFF_LINES {
+            eprintln!("  ... and {} more", mismatches.len() - SNAPSHOT_DIFF_LINES);
+        }
+        Ok(Some(AssertFailure {
+            tick,
+            expected: InfoType::String(first.expected.clone()),
+            actual: InfoType::String(first.actual.clone()),
+            position: first.pos,
+            error_message: format!("Snapshot mismatch: {} block(s) differ", mismatches.len()),
+            execution_time_ms: None,
+        }))
     }
 
     async fn execute_action(
@@ -627,22 +1810,129 @@ impl TestExecutor {
         entry: &TimelineEntry,
         value_idx: usize,
         offset: [i32; 3],
-    ) -> Result<ActionOutcome> {
+        flags: &spec::CheckFlags,
+        nbt: Option<&str>,
+    ) -> Result<actions::Outcome> {
+        let settings = actions::ActionSettings {
+            delay_ms: self.action_delay_ms,
+            verify_writes: self.verify_writes,
+        };
         actions::execute_action(
             &mut self.bot,
             tick,
             entry,
             value_idx,
             offset,
-            self.action_delay_ms,
-            self.verbose,
+            settings,
+            flags,
+            nbt,
         )
         .await
     }
 }
 
+/// Whether `tick` is the declared end of a breakpoint range, where stepping resumes sprinting
+/// Only a range's `to` ends stepping, unless another range goes on past it; stepping on from a
+/// lone breakpoint keeps stepping, even right next to another one.
+fn ends_breakpoint_range(ranges: &[(u32, u32)], tick: u32) -> bool {
+    ranges.iter().any(|&(_, to)| to == tick)
+        && !ranges.iter().any(|&(from, to)| from <= tick && tick < to)
+}
+
+/// What is left of a `--bail-after` budget once `results` are in; Some(0) means stop
+fn remaining_bail_budget(bail_after: Option<usize>, results: &[TestResult]) -> Option<usize> {
+    bail_after.map(|limit| limit.saturating_sub(results.iter().filter(|r| !r.success).count()))
+}
+
+/// Whether a test with `failed` failing checks and `errors` rejected actions or hooks passed
+/// An `expect_failure` test passes by failing a check, never by an error.
+fn judge(failed: usize, errors: usize, expect_failure: bool) -> bool {
+    errors == 0 && (failed > 0) == expect_failure
+}
+
+/// The failure reported for an `expect_failure` test whose assertions all passed
+fn unexpected_pass(tick: u32) -> AssertFailure {
+    AssertFailure {
+        tick,
+        expected: InfoType::String("a failed assertion (expect_failure)".to_string()),
+        actual: InfoType::String("every assertion passed".to_string()),
+        position: [0, 0, 0],
+        error_message: "Test expected to fail passed".to_string(),
+        execution_time_ms: None,
+    }
+}
+
+/// Last tick of each test in a merged timeline; an assertion's `within` window keeps it running
+fn test_max_ticks(
+    aggregate: &TimelineAggregate,
+    tests_with_offsets: &[(TestSpec, [i32; 3])],
+    test_options: &[TestOptions],
+) -> Vec<u32> {
+    let mut max_ticks = vec![0; test_options.len()];
+    for (tick_num, entries) in &aggregate.timeline {
+        for (test_idx, entry, _) in entries {
+            let window = test_options[*test_idx]
+                .assert_window(&tests_with_offsets[*test_idx].0, entry)
+                .unwrap_or(0);
+            max_ticks[*test_idx] = max_ticks[*test_idx].max(*tick_num + window);
+        }
+    }
+    max_ticks
+}
+
+/// Ticks a loaded test keeps the merged timeline running, as a run counts them
+pub fn tick_span(test: &TestSpec, options: &TestOptions) -> u32 {
+    let tests_with_offsets = [(test.clone(), [0, 0, 0])];
+    let aggregate = TimelineAggregate::from_tests(&tests_with_offsets);
+    test_max_ticks(
+        &aggregate,
+        &tests_with_offsets,
+        std::slice::from_ref(options),
+    )[0]
+}
+
+/// Milliseconds elapsed since `start`, or 0 if the test never started
+fn elapsed_ms(start: Option<std::time::Instant>) -> u64 {
+    start.map_or(0, |start| start.elapsed().as_millis() as u64)
+}
+
+/// What the progress bar measures
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProgressMode {
+    /// Current tick over the last tick of the timeline (default)
+    #[default]
+    Ticks,
+    /// Completed tests, with the tick position alongside
+    Tests,
+}
+
+/// Render the test-completion progress line, e.g. "[####    ] 12/40 tests, tick 3,400/10,000"
+fn test_progress_line(fill: &str, done: usize, total: usize, tick: u32, max_tick: u32) -> String {
+    let ratio = if total == 0 {
+        1.0
+    } else {
+        done as f64 / total as f64
+    };
+    let filled = ((ratio * PROGRESS_BAR_WIDTH as f64) as usize).min(PROGRESS_BAR_WIDTH);
+    format!(
+        "\r[{}{}] {}/{} tests, tick {}/{}",
+        fill.repeat(filled),
+        " ".repeat(PROGRESS_BAR_WIDTH - filled),
+        done,
+        total,
+        format_number(tick),
+        format_number(max_tick),
+    )
+}
+
+/// Print a progress bar keyed on completed tests to stdout
+fn print_test_progress_bar(fill: &str, done: usize, total: usize, tick: u32, max_tick: u32) {
+    print!("{}", test_progress_line(fill, done, total, tick, max_tick));
+    let _ = std::io::stdout().flush();
+}
+
 /// Print a progress bar to stdout
-fn print_progress_bar(current: u32, total: u32) {
+fn print_progress_bar(current: u32, total: u32, fill: &str) {
     if total == 0 {
         return;
     }
@@ -652,7 +1942,7 @@ fn print_progress_bar(current: u32, total: u32) {
 
     let bar = format!(
         "\r[{}{}] {}/{}",
-        "█".repeat(filled),
+        fill.repeat(filled),
         " ".repeat(empty),
         format_number(current),
         format_number(total),
@@ -673,3 +1963,63 @@ pub fn format_number(n: u32) -> String {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_test_progress_line() {
+        let line = test_progress_line("█", 12, 40, 3400, 10000);
+        assert!(line.starts_with('\r'));
+        assert!(line.ends_with("] 12/40 tests, tick 3,400/10,000"));
+        assert_eq!(line.matches('█').count(), 12);
+
+        let done = test_progress_line("█", 0, 0, 0, 0);
+        assert_eq!(done.matches('█').count(), PROGRESS_BAR_WIDTH);
+
+        let ascii = test_progress_line("#", 20, 40, 0, 0);
+        assert!(ascii.is_ascii());
+        assert_eq!(ascii.matches('#').count(), PROGRESS_BAR_WIDTH / 2);
+    }
+
+    #[test]
+    fn test_ends_breakpoint_range() {
+        // A {from: 5, to: 8} range; lone breakpoints aren't ranges at all
+        let ranges = [(5, 8)];
+        assert!(!ends_breakpoint_range(&ranges, 5));
+        assert!(!ends_breakpoint_range(&ranges, 7));
+        assert!(ends_breakpoint_range(&ranges, 8));
+        assert!(!ends_breakpoint_range(&ranges, 9));
+
+        // A one-tick range and one starting at tick 0 end like any other
+        assert!(ends_breakpoint_range(&[(10, 10)], 10));
+        assert!(ends_breakpoint_range(&[(0, 1)], 1));
+
+        // A range ending inside another one (from a different test) doesn't stop stepping
+        let nested = [(5, 12), (6, 8)];
+        assert!(!ends_breakpoint_range(&nested, 8));
+        assert!(ends_breakpoint_range(&nested, 12));
+    }
+
+    #[test]
+    fn test_judge_errors_always_fail() {
+        assert!(judge(0, 0, false));
+        assert!(!judge(1, 0, false));
+        // XFAIL needs a failed check; a rejected action is a broken test, not the bug
+        assert!(judge(1, 0, true));
+        assert!(!judge(0, 1, true));
+        assert!(!judge(1, 1, true));
+        assert!(!judge(0, 1, false));
+    }
+
+    #[test]
+    fn test_adjacent_single_breakpoints_keep_stepping() {
+        // Lone breakpoints at 10 and 11, whether from one test or two, declare no range;
+        // only the range another test declares later on ends stepping
+        let ranges = [(20, 25)];
+        assert!(!ends_breakpoint_range(&ranges, 10));
+        assert!(!ends_breakpoint_range(&ranges, 11));
+        assert!(ends_breakpoint_range(&ranges, 25));
+    }
+}
@@ -0,0 +1,141 @@
+//! Server performance stats - ms-per-tick samples reported by `tick sprint`
+
+/// Aggregate tick timing over a run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PerfSummary {
+    /// Number of sprints that reported a timing
+    pub samples: usize,
+    /// Ticks covered by those sprints
+    pub ticks: u64,
+    pub min_mspt: f64,
+    /// Average ms per tick, weighted by the length of each sprint
+    pub avg_mspt: f64,
+    pub max_mspt: f64,
+    /// Server time spent on the sprinted ticks
+    pub total_server_ms: f64,
+}
+
+/// Which ms-per-tick figure `--max-mspt` is checked against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum MsptStat {
+    /// Sprint-length weighted average (default)
+    #[default]
+    Avg,
+    /// Slowest sprint
+    Max,
+}
+
+impl MsptStat {
+    pub fn name(self) -> &'static str {
+        match self {
+            MsptStat::Avg => "avg",
+            MsptStat::Max => "max",
+        }
+    }
+}
+
+/// A run's measured ms per tick against the `--max-mspt` threshold
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PerfGate {
+    pub stat: MsptStat,
+    pub threshold: f64,
+    pub measured: f64,
+}
+
+impl PerfGate {
+    pub fn check(summary: &PerfSummary, stat: MsptStat, threshold: f64) -> Self {
+        let measured = match stat {
+            MsptStat::Avg => summary.avg_mspt,
+            MsptStat::Max => summary.max_mspt,
+        };
+        Self {
+            stat,
+            threshold,
+            measured,
+        }
+    }
+
+    pub fn passed(&self) -> bool {
+        self.measured <= self.threshold
+    }
+}
+
+/// Collected ms-per-tick samples: (ms_per_tick, ticks)
+#[derive(Debug, Default)]
+pub struct PerfStats {
+    samples: Vec<(f64, u32)>,
+}
+
+impl PerfStats {
+    pub fn record(&mut self, mspt: f64, ticks: u32) {
+        self.samples.push((mspt, ticks));
+    }
+
+    /// Aggregate the samples, or None if no sprint reported a timing
+    pub fn summary(&self) -> Option<PerfSummary> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let ticks: u64 = self.samples.iter().map(|(_, ticks)| *ticks as u64).sum();
+        let total_server_ms: f64 = self
+            .samples
+            .iter()
+            .map(|(mspt, ticks)| mspt * *ticks as f64)
+            .sum();
+        let min_mspt = self
+            .samples
+            .iter()
+            .map(|(mspt, _)| *mspt)
+            .fold(f64::INFINITY, f64::min);
+        let max_mspt = self
+            .samples
+            .iter()
+            .map(|(mspt, _)| *mspt)
+            .fold(0.0, f64::max);
+        Some(PerfSummary {
+            samples: self.samples.len(),
+            ticks,
+            min_mspt,
+            avg_mspt: if ticks == 0 {
+                0.0
+            } else {
+                total_server_ms / ticks as f64
+            },
+            max_mspt,
+            total_server_ms,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_weights_by_ticks() {
+        assert_eq!(PerfStats::default().summary(), None);
+
+        let mut stats = PerfStats::default();
+        stats.record(2.0, 30);
+        stats.record(8.0, 10);
+        let summary = stats.summary().unwrap();
+        assert_eq!(summary.samples, 2);
+        assert_eq!(summary.ticks, 40);
+        assert_eq!(summary.min_mspt, 2.0);
+        assert_eq!(summary.max_mspt, 8.0);
+        assert_eq!(summary.total_server_ms, 140.0);
+        assert_eq!(summary.avg_mspt, 3.5);
+    }
+
+    #[test]
+    fn test_gate_checks_chosen_stat() {
+        let mut stats = PerfStats::default();
+        stats.record(2.0, 30);
+        stats.record(8.0, 10);
+        let summary = stats.summary().unwrap();
+        assert!(PerfGate::check(&summary, MsptStat::Avg, 5.0).passed());
+        let peak = PerfGate::check(&summary, MsptStat::Max, 5.0);
+        assert!(!peak.passed());
+        assert_eq!(peak.measured, 8.0);
+    }
+}
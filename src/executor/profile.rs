@@ -0,0 +1,107 @@
+//! Run profile (`--profile`) - wall time spent in each phase of the merged timeline
+
+use std::time::{Duration, Instant};
+
+/// Where run time goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Clearing areas, `before` hooks, tick freeze and warmup
+    Setup,
+    /// Placing, filling and removing blocks
+    Actions,
+    /// Reading blocks and other state for assertions, including retries
+    Assertions,
+    /// `tick step` and `tick sprint`
+    Advance,
+    /// Snapshots, `after` hooks and clearing finished tests
+    Cleanup,
+    /// Waiting at breakpoints for the player
+    Paused,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 6] = [
+        Phase::Setup,
+        Phase::Actions,
+        Phase::Assertions,
+        Phase::Advance,
+        Phase::Cleanup,
+        Phase::Paused,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Setup => "setup",
+            Phase::Actions => "actions",
+            Phase::Assertions => "assertions",
+            Phase::Advance => "advance",
+            Phase::Cleanup => "cleanup",
+            Phase::Paused => "paused",
+        }
+    }
+}
+
+/// Time per phase across a run; time in none of them (logging, progress, dumps) is `other`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunProfile {
+    /// (phase, ms) in `Phase::ALL` order
+    pub phases: Vec<(Phase, u64)>,
+    pub other_ms: u64,
+    pub total_ms: u64,
+}
+
+/// Collected phase timings
+#[derive(Debug, Default)]
+pub struct ProfileStats {
+    phases: [Duration; Phase::ALL.len()],
+    total: Duration,
+}
+
+impl ProfileStats {
+    /// Add the time since `started` to `phase`
+    pub fn record(&mut self, phase: Phase, started: Instant) {
+        self.phases[phase as usize] += started.elapsed();
+    }
+
+    /// Add the time since `started` to the run total
+    pub fn record_total(&mut self, started: Instant) {
+        self.total += started.elapsed();
+    }
+
+    pub fn summary(&self) -> RunProfile {
+        let in_phases: Duration = self.phases.iter().sum();
+        RunProfile {
+            phases: Phase::ALL
+                .iter()
+                .map(|&phase| (phase, self.phases[phase as usize].as_millis() as u64))
+                .collect(),
+            other_ms: self.total.saturating_sub(in_phases).as_millis() as u64,
+            total_ms: self.total.as_millis() as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_keeps_phase_order() {
+        let mut stats = ProfileStats::default();
+        stats.phases[Phase::Advance as usize] = Duration::from_millis(300);
+        stats.phases[Phase::Assertions as usize] = Duration::from_millis(500);
+        stats.total = Duration::from_millis(1000);
+        let profile = stats.summary();
+        assert_eq!(
+            profile
+                .phases
+                .iter()
+                .map(|(phase, _)| *phase)
+                .collect::<Vec<_>>(),
+            Phase::ALL
+        );
+        assert_eq!(profile.phases[2], (Phase::Assertions, 500));
+        assert_eq!(profile.other_ms, 200);
+        assert_eq!(profile.total_ms, 1000);
+    }
+}
@@ -3,9 +3,67 @@
 /// A recorded action in the timeline
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RecordedAction {
-    Place { pos: [i32; 3], block: String },
-    Remove { pos: [i32; 3] },
-    Assert { pos: [i32; 3], block: String },
+    Place {
+        pos: [i32; 3],
+        block: String,
+    },
+    Remove {
+        pos: [i32; 3],
+    },
+    Assert {
+        pos: [i32; 3],
+        block: String,
+    },
+    /// Every block in a box: `region` is (min, max), `blocks` go x fastest, then z, then y
+    AssertRegion {
+        region: [[i32; 3]; 2],
+        blocks: Vec<String>,
+    },
+}
+
+impl RecordedAction {
+    /// Local position this action applies to (a region's lowest corner)
+    pub fn pos(&self) -> [i32; 3] {
+        match self {
+            Self::Place { pos, .. } | Self::Remove { pos } | Self::Assert { pos, .. } => *pos,
+            Self::AssertRegion { region, .. } => region[0],
+        }
+    }
+
+    /// Local (min, max) corners this action covers
+    pub fn corners(&self) -> [[i32; 3]; 2] {
+        match self {
+            Self::AssertRegion { region, .. } => *region,
+            _ => [self.pos(), self.pos()],
+        }
+    }
+
+    /// Short one-line description, e.g. "place [0, 1, 0] minecraft:stone"
+    pub fn describe(&self) -> String {
+        let [x, y, z] = self.pos();
+        match self {
+            Self::Place { block, .. } => format!("place [{}, {}, {}] {}", x, y, z, block),
+            Self::Remove { .. } => format!("remove [{}, {}, {}]", x, y, z),
+            Self::Assert { block, .. } => format!("assert [{}, {}, {}] is {}", x, y, z, block),
+            Self::AssertRegion { region, blocks } => {
+                let [mx, my, mz] = region[1];
+                let mut kinds: Vec<&String> = blocks.iter().collect();
+                kinds.sort();
+                kinds.dedup();
+                format!(
+                    "assert [{}, {}, {}]..[{}, {}, {}] ({} blocks, {} kinds)",
+                    x,
+                    y,
+                    z,
+                    mx,
+                    my,
+                    mz,
+                    blocks.len(),
+                    kinds.len()
+                )
+            }
+        }
+    }
 }
 
 /// A step in the recorded timeline
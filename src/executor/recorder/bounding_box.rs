@@ -23,6 +23,19 @@ impl BoundingBox {
         }
     }
 
+    /// Box spanning two corners, in any order
+    pub fn from_region(region: [[i32; 3]; 2]) -> Self {
+        let mut bounds = Self::new();
+        bounds.expand(region[0]);
+        bounds.expand(region[1]);
+        bounds
+    }
+
+    /// Check if `other` lies entirely inside this box
+    pub fn contains(&self, other: &BoundingBox) -> bool {
+        (0..3).all(|i| self.min[i] <= other.min[i] && other.max[i] <= self.max[i])
+    }
+
     /// Check if the bounding box has any valid points
     pub fn is_valid(&self) -> bool {
         self.min[0] <= self.max[0] && self.min[1] <= self.max[1] && self.min[2] <= self.max[2]
@@ -6,4 +6,6 @@ mod state;
 #[cfg(test)]
 mod tests;
 
-pub use state::RecorderState;
+pub use actions::RecordedAction;
+pub use bounding_box::BoundingBox;
+pub use state::{LARGE_SCAN_RADIUS, MAX_SCAN_RADIUS, RecordFormat, RecorderState};
@@ -5,17 +5,44 @@ use flint_core::test_spec::{
     ActionType, BlockCheck, BlockPlacement, CleanupSpec, SetupSpec, TestSpec, TickSpec,
     TimelineEntry,
 };
+use serde_json::{Value, json};
 use std::collections::HashMap;
 use std::path::PathBuf;
 
 use crate::executor::block::make_block;
+use crate::executor::snbt;
 
 use super::actions::{RecordedAction, TimelineStep};
 use super::bounding_box::BoundingBox;
 
 // Constants
-const DEFAULT_SCAN_RADIUS: i32 = 16;
+pub const DEFAULT_SCAN_RADIUS: i32 = 10;
+/// Radii above this scan enough blocks per `!tick` to stall the server
+pub const LARGE_SCAN_RADIUS: i32 = 24;
+pub const MAX_SCAN_RADIUS: i32 = 64;
 const DEFAULT_CLEANUP_REGION: [[i32; 3]; 2] = [[0, 0, 0], [10, 10, 10]];
+/// Palette keys for `assert_region`, after `.` for air
+const PALETTE_KEYS: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+
+/// File format used when saving a recording
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum RecordFormat {
+    /// Pretty-printed JSON (default)
+    #[default]
+    Json,
+    /// YAML, for easier hand-editing afterwards
+    Yaml,
+}
+
+impl RecordFormat {
+    /// File extension for this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+        }
+    }
+}
 
 /// State for an active recording session
 pub struct RecorderState {
@@ -39,6 +66,14 @@ pub struct RecorderState {
     pub scan_center: Option<[i32; 3]>,
     /// Scan radius around player to detect block changes
     pub scan_radius: i32,
+    /// First corner (world position) of a pending `!assert_region`
+    pub pos1: Option<[i32; 3]>,
+    /// Previous snapshot values per world position, restored on undo
+    snapshot_history: HashMap<[i32; 3], Vec<Option<String>>>,
+    /// Actions displaced by deduplication per (tick, local position), restored on undo
+    displaced: HashMap<(u32, [i32; 3]), Vec<RecordedAction>>,
+    /// Container contents (`Items` SNBT) per (tick, local position) of the action recorded there
+    block_items: HashMap<(u32, [i32; 3]), String>,
 }
 
 impl RecorderState {
@@ -68,6 +103,10 @@ impl RecorderState {
             player_name: None,
             scan_center: None,
             scan_radius: DEFAULT_SCAN_RADIUS,
+            pos1: None,
+            snapshot_history: HashMap::new(),
+            displaced: HashMap::new(),
+            block_items: HashMap::new(),
         }
     }
 
@@ -76,6 +115,21 @@ impl RecorderState {
         self.scan_center = Some(pos);
     }
 
+    /// Change the scan radius, adding `scanned` blocks that only the new cube covers to the
+    /// snapshot. Blocks already inside the old cube keep their snapshot so changes made since
+    /// the last `!tick` are still detected.
+    pub fn set_scan_radius(&mut self, radius: i32, scanned: HashMap<[i32; 3], String>) {
+        let center = self.scan_center.unwrap_or([0, 64, 0]);
+        let previous = self.scan_radius;
+        for (pos, block) in scanned {
+            let distance = (0..3).map(|axis| (pos[axis] - center[axis]).abs()).max();
+            if distance.is_some_and(|distance| distance > previous) {
+                self.snapshot.entry(pos).or_insert(block);
+            }
+        }
+        self.scan_radius = radius;
+    }
+
     /// Set the origin point (normalizes all positions relative to this)
     pub fn set_origin(&mut self, pos: [i32; 3]) {
         if self.origin.is_none() {
@@ -83,6 +137,19 @@ impl RecorderState {
         }
     }
 
+    /// Explicitly set the origin point
+    /// Returns false if a different origin is already in use, since changing it
+    /// would re-normalize positions that have already been recorded
+    pub fn set_explicit_origin(&mut self, pos: [i32; 3]) -> bool {
+        match self.origin {
+            Some(existing) => existing == pos,
+            None => {
+                self.origin = Some(pos);
+                true
+            }
+        }
+    }
+
     /// Convert world position to local position (relative to origin)
     #[must_use]
     pub fn to_local(&self, world_pos: [i32; 3]) -> [i32; 3] {
@@ -97,6 +164,20 @@ impl RecorderState {
         }
     }
 
+    /// Convert local position (relative to origin) back to world position
+    #[must_use]
+    pub fn to_world(&self, local_pos: [i32; 3]) -> [i32; 3] {
+        if let Some(origin) = self.origin {
+            [
+                local_pos[0] + origin[0],
+                local_pos[1] + origin[1],
+                local_pos[2] + origin[2],
+            ]
+        } else {
+            local_pos
+        }
+    }
+
     /// Get or create the timeline step for the current tick
     fn get_or_create_current_step(&mut self) -> &mut TimelineStep {
         if self.timeline.is_empty() || self.timeline.last().unwrap().tick != self.current_tick {
@@ -109,14 +190,38 @@ impl RecorderState {
     }
 
     /// Remove any existing Place/Remove actions for this position in the current tick
+    /// Removed actions are kept so that undoing the replacement brings them back
     fn deduplicate_actions(&mut self, pos: [i32; 3]) {
+        let tick = self.current_tick;
         let step = self.get_or_create_current_step();
-        step.actions.retain(|a| match a {
-            RecordedAction::Place { pos: p, .. } => *p != pos,
-            RecordedAction::Remove { pos: p } => *p != pos,
-            // Keep asserts/others
-            _ => true,
+        let mut removed = Vec::new();
+        step.actions.retain(|a| {
+            let keep = match a {
+                RecordedAction::Place { pos: p, .. } => *p != pos,
+                RecordedAction::Remove { pos: p } => *p != pos,
+                // Keep asserts/others
+                _ => true,
+            };
+            if !keep {
+                removed.push(a.clone());
+            }
+            keep
         });
+        if !removed.is_empty() {
+            self.displaced
+                .entry((tick, pos))
+                .or_default()
+                .extend(removed);
+        }
+    }
+
+    /// Update the snapshot for a world position, remembering the previous value for undo
+    fn update_snapshot(&mut self, world_pos: [i32; 3], block: &str) {
+        let previous = self.snapshot.insert(world_pos, block.to_string());
+        self.snapshot_history
+            .entry(world_pos)
+            .or_default()
+            .push(previous);
     }
 
     /// Record a block placement
@@ -129,6 +234,7 @@ impl RecorderState {
 
         // Deduplicate before adding
         self.deduplicate_actions(local_pos);
+        self.block_items.remove(&(self.current_tick, local_pos));
 
         let step = self.get_or_create_current_step();
         step.actions.push(RecordedAction::Place {
@@ -137,7 +243,7 @@ impl RecorderState {
         });
 
         // Update snapshot
-        self.snapshot.insert(world_pos, block.to_string());
+        self.update_snapshot(world_pos, block);
     }
 
     /// Record a block removal
@@ -152,12 +258,13 @@ impl RecorderState {
 
         // Deduplicate before adding
         self.deduplicate_actions(local_pos);
+        self.block_items.remove(&(self.current_tick, local_pos));
 
         let step = self.get_or_create_current_step();
         step.actions.push(RecordedAction::Remove { pos: local_pos });
 
         // Update snapshot - store air to track the removal
-        self.snapshot.insert(world_pos, "minecraft:air".to_string());
+        self.update_snapshot(world_pos, "minecraft:air");
     }
 
     /// Add an assertion for a block
@@ -168,6 +275,7 @@ impl RecorderState {
 
         let local_pos = self.to_local(world_pos);
         self.bounds.expand(local_pos);
+        self.block_items.remove(&(self.current_tick, local_pos));
 
         let step = self.get_or_create_current_step();
         step.actions.push(RecordedAction::Assert {
@@ -176,6 +284,55 @@ impl RecorderState {
         });
     }
 
+    /// Add one assertion covering every block between two world corners, inclusive
+    /// `blocks` holds the non-air blocks by world position; anything missing is air.
+    pub fn add_region_assertion(
+        &mut self,
+        corner_a: [i32; 3],
+        corner_b: [i32; 3],
+        blocks: &HashMap<[i32; 3], String>,
+    ) {
+        let min = [0, 1, 2].map(|axis| corner_a[axis].min(corner_b[axis]));
+        let max = [0, 1, 2].map(|axis| corner_a[axis].max(corner_b[axis]));
+        if self.origin.is_none() {
+            self.set_origin(min);
+        }
+
+        let mut region_blocks = Vec::new();
+        for y in min[1]..=max[1] {
+            for z in min[2]..=max[2] {
+                for x in min[0]..=max[0] {
+                    let block = blocks
+                        .get(&[x, y, z])
+                        .map_or("minecraft:air", String::as_str);
+                    region_blocks.push(block.to_string());
+                }
+            }
+        }
+
+        let region = [self.to_local(min), self.to_local(max)];
+        self.bounds.expand(region[0]);
+        self.bounds.expand(region[1]);
+        let step = self.get_or_create_current_step();
+        step.actions.push(RecordedAction::AssertRegion {
+            region,
+            blocks: region_blocks,
+        });
+    }
+
+    /// Keep a container's contents (its `Items` SNBT) with the block just recorded at a world
+    /// position: a place fills the container, an assert checks it slot by slot
+    pub fn record_items(&mut self, world_pos: [i32; 3], items: &str) {
+        let local_pos = self.to_local(world_pos);
+        self.block_items
+            .insert((self.current_tick, local_pos), items.to_string());
+    }
+
+    /// Contents recorded with the action at a local position on a tick, if it was a container
+    fn items_at(&self, tick: u32, pos: [i32; 3]) -> Option<&str> {
+        self.block_items.get(&(tick, pos)).map(String::as_str)
+    }
+
     /// Convert all Place/Remove actions in the current tick to Assertions
     pub fn convert_actions_to_asserts(&mut self) -> usize {
         let mut converted_count = 0;
@@ -201,7 +358,8 @@ impl RecorderState {
                         converted_count += 1;
                     }
                     // Keep existing asserts unchanged
-                    assert_action @ RecordedAction::Assert { .. } => {
+                    assert_action @ (RecordedAction::Assert { .. }
+                    | RecordedAction::AssertRegion { .. }) => {
                         new_actions.push(assert_action);
                     }
                 }
@@ -213,12 +371,139 @@ impl RecorderState {
         converted_count
     }
 
+    /// Undo the most recently recorded action
+    /// Pops from the current tick's step, or the latest earlier step if the current one is empty.
+    /// Rolls back the snapshot and bounds, and restores any action the undone one had replaced.
+    /// Returns the tick and the removed action, or None if there is nothing to undo.
+    pub fn undo_last_action(&mut self) -> Option<(u32, RecordedAction)> {
+        let step_idx = self
+            .timeline
+            .iter()
+            .rposition(|step| !step.actions.is_empty())?;
+        let tick = self.timeline[step_idx].tick;
+        let action = self.timeline[step_idx].actions.pop()?;
+
+        if let RecordedAction::Place { pos, .. } | RecordedAction::Remove { pos } = &action {
+            // Bring back the action this one replaced during deduplication
+            if let Some(restored) = self
+                .displaced
+                .get_mut(&(tick, *pos))
+                .and_then(|stack| stack.pop())
+            {
+                self.timeline[step_idx].actions.push(restored);
+            }
+
+            // Roll back the snapshot so change detection matches the pre-action world
+            let world_pos = self.to_world(*pos);
+            if let Some(previous) = self
+                .snapshot_history
+                .get_mut(&world_pos)
+                .and_then(|stack| stack.pop())
+            {
+                match previous {
+                    Some(block) => {
+                        self.snapshot.insert(world_pos, block);
+                    }
+                    None => {
+                        self.snapshot.remove(&world_pos);
+                    }
+                }
+            }
+        }
+
+        // Contents belong to whatever action is left at the position, if any
+        let pos = action.pos();
+        if !self.timeline[step_idx]
+            .actions
+            .iter()
+            .any(|remaining| remaining.pos() == pos)
+        {
+            self.block_items.remove(&(tick, pos));
+        }
+
+        if self.timeline[step_idx].actions.is_empty() {
+            self.timeline.remove(step_idx);
+        }
+
+        // Recompute bounds from the remaining actions
+        self.bounds = BoundingBox::new();
+        for step in &self.timeline {
+            for remaining in &step.actions {
+                let [min, max] = remaining.corners();
+                self.bounds.expand(min);
+                self.bounds.expand(max);
+            }
+        }
+
+        Some((tick, action))
+    }
+
+    /// Pending changes grouped by tick: a "Tick N:" header followed by one line per action
+    pub fn diff_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for step in self.timeline.iter().filter(|step| !step.actions.is_empty()) {
+            lines.push(format!("Tick {}:", step.tick));
+            lines.extend(
+                step.actions
+                    .iter()
+                    .map(|action| format!("  {}", action.describe())),
+            );
+        }
+        lines
+    }
+
     /// Advance to the next tick
     pub fn next_tick(&mut self) {
         self.current_tick += 1;
     }
 
+    /// Entries for one recorded step: its placements, then its block checks
+    /// Filled containers are left out of the placements; `to_document` places them with `nbt`.
+    fn step_entries(&self, step: &TimelineStep) -> (Option<TimelineEntry>, Option<TimelineEntry>) {
+        let mut placements: Vec<BlockPlacement> = Vec::new();
+        let mut checks: Vec<BlockCheck> = Vec::new();
+
+        for action in &step.actions {
+            match action {
+                RecordedAction::Place { pos, block } => {
+                    if self.items_at(step.tick, *pos).is_none() {
+                        placements.push(BlockPlacement {
+                            pos: *pos,
+                            block: make_block(block),
+                        });
+                    }
+                }
+                RecordedAction::Remove { pos } => {
+                    placements.push(BlockPlacement {
+                        pos: *pos,
+                        block: make_block("minecraft:air"),
+                    });
+                }
+                RecordedAction::Assert { pos, block } => {
+                    checks.push(BlockCheck {
+                        pos: *pos,
+                        is: make_block(block),
+                    });
+                }
+                // Saved as `assert_fill` or `assert_region` by `to_document`
+                RecordedAction::AssertRegion { .. } => {}
+            }
+        }
+
+        // Emit place_each if there are placements, and assert if there are checks
+        let place_each = (!placements.is_empty()).then(|| TimelineEntry {
+            at: TickSpec::Single(step.tick),
+            action_type: ActionType::PlaceEach { blocks: placements },
+        });
+        let assert = (!checks.is_empty()).then(|| TimelineEntry {
+            at: TickSpec::Single(step.tick),
+            action_type: ActionType::Assert { checks },
+        });
+        (place_each, assert)
+    }
+
     /// Generate a TestSpec from the recorded data
+    /// Container contents and region asserts don't fit a TestSpec; `to_document` adds them.
     #[must_use]
     pub fn generate_test_spec(&self) -> TestSpec {
         let cleanup_region = if self.bounds.is_valid() {
@@ -229,50 +514,10 @@ impl RecorderState {
 
         // Build timeline entries using flint-core types
         let mut timeline_entries: Vec<TimelineEntry> = Vec::new();
-
         for step in &self.timeline {
-            // Group actions by type for this tick
-            let mut placements: Vec<BlockPlacement> = Vec::new();
-            let mut checks: Vec<BlockCheck> = Vec::new();
-
-            for action in &step.actions {
-                match action {
-                    RecordedAction::Place { pos, block } => {
-                        placements.push(BlockPlacement {
-                            pos: *pos,
-                            block: make_block(block),
-                        });
-                    }
-                    RecordedAction::Remove { pos } => {
-                        placements.push(BlockPlacement {
-                            pos: *pos,
-                            block: make_block("minecraft:air"),
-                        });
-                    }
-                    RecordedAction::Assert { pos, block } => {
-                        checks.push(BlockCheck {
-                            pos: *pos,
-                            is: make_block(block),
-                        });
-                    }
-                }
-            }
-
-            // Emit place_each if there are placements
-            if !placements.is_empty() {
-                timeline_entries.push(TimelineEntry {
-                    at: TickSpec::Single(step.tick),
-                    action_type: ActionType::PlaceEach { blocks: placements },
-                });
-            }
-
-            // Emit assert if there are checks
-            if !checks.is_empty() {
-                timeline_entries.push(TimelineEntry {
-                    at: TickSpec::Single(step.tick),
-                    action_type: ActionType::Assert { checks },
-                });
-            }
+            let (place_each, assert) = self.step_entries(step);
+            timeline_entries.extend(place_each);
+            timeline_entries.extend(assert);
         }
 
         TestSpec {
@@ -291,19 +536,149 @@ impl RecorderState {
         }
     }
 
-    /// Save the test to a file
-    pub fn save(&self) -> Result<PathBuf> {
-        let test_spec = self.generate_test_spec();
+    /// The test as saved: the generated TestSpec plus recorded container contents and regions
+    /// A filled container is placed with its `Items` as `nbt`, and each stack in an asserted
+    /// container becomes an `assert_container` check on its slot. A region assert becomes an
+    /// `assert_fill` when every block is the same, and an `assert_region` otherwise.
+    pub fn to_document(&self) -> Result<Value> {
+        let mut document = serde_json::to_value(self.generate_test_spec())?;
+        let mut timeline = Vec::new();
+        for step in &self.timeline {
+            let (place_each, assert) = self.step_entries(step);
+            if let Some(entry) = place_each {
+                timeline.push(serde_json::to_value(entry)?);
+            }
+            for action in &step.actions {
+                if let RecordedAction::Place { pos, block } = action
+                    && let Some(items) = self.items_at(step.tick, *pos)
+                {
+                    let mut entry = serde_json::to_value(TimelineEntry {
+                        at: TickSpec::Single(step.tick),
+                        action_type: ActionType::Place {
+                            pos: *pos,
+                            block: make_block(block),
+                        },
+                    })?;
+                    entry["nbt"] = Value::String(format!("{{Items:{}}}", items));
+                    timeline.push(entry);
+                }
+            }
+            if let Some(entry) = assert {
+                timeline.push(serde_json::to_value(entry)?);
+            }
+            for action in &step.actions {
+                if let RecordedAction::Assert { pos, .. } = action
+                    && let Some(items) = self.items_at(step.tick, *pos)
+                {
+                    for stack in snbt::item_stacks(&snbt::parse(items)?) {
+                        timeline.push(json!({
+                            "at": step.tick,
+                            "do": "assert_container",
+                            "pos": pos,
+                            "slot": stack.slot,
+                            "item": stack.id,
+                            "count": stack.count,
+                        }));
+                    }
+                }
+            }
+            for action in &step.actions {
+                if let RecordedAction::AssertRegion { region, blocks } = action {
+                    timeline.push(region_entry(step.tick, *region, blocks)?);
+                }
+            }
+        }
+        document["timeline"] = Value::Array(timeline);
+        Ok(document)
+    }
+
+    /// Save the test to a file in the given format
+    /// The extension of `test_path` is replaced to match the format
+    pub fn save_as(&self, format: RecordFormat) -> Result<PathBuf> {
+        let document = self.to_document()?;
+        let path = self.test_path.with_extension(format.extension());
 
         // Create parent directories if needed
-        if let Some(parent) = self.test_path.parent() {
+        if let Some(parent) = path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
-        // Write the JSON file with pretty formatting using serde
-        let json_str = serde_json::to_string_pretty(&test_spec)?;
-        std::fs::write(&self.test_path, json_str)?;
+        // Serialize with serde in the requested format
+        let contents = match format {
+            RecordFormat::Json => serde_json::to_string_pretty(&document)?,
+            RecordFormat::Yaml => serde_yaml::to_string(&document)?,
+        };
+        std::fs::write(&path, contents)?;
+
+        Ok(path)
+    }
+}
+
+/// Saved entry for a region assert: `assert_fill` when the box holds one block, otherwise an
+/// `assert_region` with a palette, or plain checks when there are too many kinds for the palette
+fn region_entry(tick: u32, region: [[i32; 3]; 2], blocks: &[String]) -> Result<Value> {
+    if blocks.iter().all(|block| *block == blocks[0]) {
+        return Ok(json!({
+            "at": tick,
+            "do": "assert_fill",
+            "region": region,
+            "block": blocks[0],
+        }));
+    }
+
+    let width = (region[1][0] - region[0][0] + 1) as usize;
+    let depth = (region[1][2] - region[0][2] + 1) as usize;
+    let mut keys: HashMap<&str, char> = HashMap::new();
+    let mut unused = PALETTE_KEYS.chars();
+    for block in blocks {
+        if keys.contains_key(block.as_str()) {
+            continue;
+        }
+        let key = if block == "minecraft:air" {
+            Some('.')
+        } else {
+            unused.next()
+        };
+        let Some(key) = key else {
+            // Too many kinds of block for one-character keys
+            let checks = blocks
+                .iter()
+                .enumerate()
+                .map(|(idx, block)| BlockCheck {
+                    pos: [
+                        region[0][0] + (idx % width) as i32,
+                        region[0][1] + (idx / (width * depth)) as i32,
+                        region[0][2] + (idx / width % depth) as i32,
+                    ],
+                    is: make_block(block),
+                })
+                .collect();
+            return Ok(serde_json::to_value(TimelineEntry {
+                at: TickSpec::Single(tick),
+                action_type: ActionType::Assert { checks },
+            })?);
+        };
+        keys.insert(block, key);
+    }
 
-        Ok(self.test_path.clone())
+    let layers: Vec<Vec<String>> = blocks
+        .chunks(width * depth)
+        .map(|layer| {
+            layer
+                .chunks(width)
+                .map(|row| row.iter().map(|block| keys[block.as_str()]).collect())
+                .collect()
+        })
+        .collect();
+    let mut palette = serde_json::Map::new();
+    for (block, key) in &keys {
+        palette.insert(key.to_string(), serde_json::to_value(make_block(block))?);
     }
+    Ok(json!({
+        "at": tick,
+        "do": "assert_region",
+        "region": region,
+        "palette": palette,
+        "layers": layers,
+    }))
 }
@@ -1,7 +1,11 @@
 //! Tests for the recorder module
 
+use super::actions::RecordedAction;
 use super::bounding_box::BoundingBox;
-use super::state::RecorderState;
+use super::state::{RecordFormat, RecorderState};
+use crate::executor::block::extract_block_id;
+use crate::spec::{ContainerCheck, ExtraCheck};
+use flint_core::test_spec::{ActionType, TestSpec};
 
 #[test]
 fn test_bounding_box() {
@@ -26,3 +30,250 @@ fn test_local_position() {
     assert_eq!(recorder.to_local([100, 64, 200]), [0, 0, 0]);
     assert_eq!(recorder.to_local([105, 65, 198]), [5, 1, -2]);
 }
+
+#[test]
+fn test_undo_place_restores_snapshot_and_bounds() {
+    let mut recorder = RecorderState::new("test", std::path::Path::new("/tmp"));
+    recorder.record_place([100, 64, 200], "minecraft:stone");
+    recorder.record_place([103, 64, 200], "minecraft:dirt");
+    assert_eq!(recorder.bounds.max, [3, 0, 0]);
+
+    let (tick, action) = recorder.undo_last_action().unwrap();
+    assert_eq!(tick, 0);
+    assert_eq!(
+        action,
+        RecordedAction::Place {
+            pos: [3, 0, 0],
+            block: "minecraft:dirt".to_string()
+        }
+    );
+    assert!(!recorder.snapshot.contains_key(&[103, 64, 200]));
+    assert_eq!(recorder.bounds.max, [0, 0, 0]);
+    assert_eq!(recorder.timeline[0].actions.len(), 1);
+}
+
+#[test]
+fn test_undo_restores_deduplicated_action() {
+    let mut recorder = RecorderState::new("test", std::path::Path::new("/tmp"));
+    recorder.record_place([0, 64, 0], "minecraft:stone");
+    recorder.record_place([0, 64, 0], "minecraft:dirt");
+    assert_eq!(recorder.timeline[0].actions.len(), 1);
+
+    recorder.undo_last_action().unwrap();
+    assert_eq!(
+        recorder.timeline[0].actions,
+        vec![RecordedAction::Place {
+            pos: [0, 0, 0],
+            block: "minecraft:stone".to_string()
+        }]
+    );
+    assert_eq!(
+        recorder.snapshot.get(&[0, 64, 0]).map(String::as_str),
+        Some("minecraft:stone")
+    );
+}
+
+#[test]
+fn test_undo_falls_back_to_previous_tick() {
+    let mut recorder = RecorderState::new("test", std::path::Path::new("/tmp"));
+    recorder.record_place([0, 64, 0], "minecraft:stone");
+    recorder.next_tick();
+
+    let (tick, _) = recorder.undo_last_action().unwrap();
+    assert_eq!(tick, 0);
+    assert!(recorder.timeline.is_empty());
+    assert!(recorder.undo_last_action().is_none());
+}
+
+#[test]
+fn test_save_as_yaml_round_trips() {
+    let dir = std::env::temp_dir().join("flintmc_recorder_yaml_test");
+    let mut recorder = RecorderState::new("roundtrip", &dir);
+    recorder.record_place([0, 64, 0], "minecraft:stone");
+
+    let path = recorder.save_as(RecordFormat::Yaml).unwrap();
+    assert_eq!(path.extension().and_then(|e| e.to_str()), Some("yaml"));
+
+    let (loaded, _) = crate::spec::load_test(&path).unwrap();
+    assert_eq!(loaded.name, "roundtrip");
+    assert_eq!(loaded.timeline.len(), 1);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_save_as_json_is_plain_json() {
+    let dir = std::env::temp_dir().join("flintmc_recorder_json_test");
+    let mut recorder = RecorderState::new("plain", &dir);
+    recorder.record_place([0, 64, 0], "minecraft:stone");
+
+    let path = recorder.save_as(RecordFormat::Json).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    // No comments or comment keys, so any JSON tool can read a recorded test
+    let value: serde_json::Value = serde_json::from_str(&contents).unwrap();
+    assert!(!contents.contains("_comment") && !contents.contains("$schema"));
+    assert_eq!(value["name"], "plain");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_diff_lines_group_by_tick() {
+    let mut recorder = RecorderState::new("test", std::path::Path::new("/tmp"));
+    recorder.record_place([10, 64, 10], "minecraft:lever");
+    recorder.next_tick();
+    recorder.record_remove([11, 64, 10]);
+    recorder.add_assertion([10, 65, 10], "minecraft:air");
+    assert_eq!(
+        recorder.diff_lines(),
+        vec![
+            "Tick 0:",
+            "  place [0, 0, 0] minecraft:lever",
+            "Tick 1:",
+            "  remove [1, 0, 0]",
+            "  assert [0, 1, 0] is minecraft:air",
+        ]
+    );
+}
+
+#[test]
+fn test_explicit_origin() {
+    let mut recorder = RecorderState::new("test", std::path::Path::new("/tmp"));
+    assert!(recorder.set_explicit_origin([10, 64, 10]));
+    recorder.record_place([12, 64, 10], "minecraft:stone");
+    assert_eq!(recorder.to_local([12, 64, 10]), [2, 0, 0]);
+
+    // Same origin is accepted, a different one is rejected
+    assert!(recorder.set_explicit_origin([10, 64, 10]));
+    assert!(!recorder.set_explicit_origin([0, 64, 0]));
+    assert_eq!(recorder.origin, Some([10, 64, 10]));
+}
+
+#[test]
+fn test_widening_scan_radius_keeps_pending_changes() {
+    let mut recorder = RecorderState::new("test", std::path::Path::new("/tmp"));
+    recorder.set_scan_center([0, 64, 0]);
+    recorder
+        .snapshot
+        .insert([1, 64, 0], "minecraft:stone".to_string());
+
+    // [1, 64, 0] changed since the snapshot, [20, 64, 0] is newly in range
+    let scanned = std::collections::HashMap::from([
+        ([1, 64, 0], "minecraft:dirt".to_string()),
+        ([2, 64, 0], "minecraft:lever".to_string()),
+        ([20, 64, 0], "minecraft:glass".to_string()),
+    ]);
+    recorder.set_scan_radius(20, scanned);
+
+    assert_eq!(recorder.scan_radius, 20);
+    assert_eq!(recorder.snapshot[&[1, 64, 0]], "minecraft:stone");
+    assert!(!recorder.snapshot.contains_key(&[2, 64, 0]));
+    assert_eq!(recorder.snapshot[&[20, 64, 0]], "minecraft:glass");
+}
+
+#[test]
+fn test_recorded_block_state_round_trips_into_assert() {
+    let mut recorder = RecorderState::new("test", std::path::Path::new("/tmp"));
+    let block = extract_block_id(
+        "BlockState(id: 5000, OakFence { east: false, north: true, south: false, waterlogged: false, west: false })",
+    );
+    assert!(block.starts_with("minecraft:oak_fence["));
+    recorder.record_place([10, 64, 10], &block);
+    assert_eq!(recorder.convert_actions_to_asserts(), 1);
+
+    // Through the saved JSON, as a replay would load it
+    let json = serde_json::to_string(&recorder.generate_test_spec()).unwrap();
+    let spec: TestSpec = serde_json::from_str(&json).unwrap();
+    let checks = spec
+        .timeline
+        .iter()
+        .find_map(|entry| match &entry.action_type {
+            ActionType::Assert { checks } => Some(checks),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(checks[0].is.id, "minecraft:oak_fence");
+    assert_eq!(
+        checks[0].is.properties.get("north"),
+        Some(&serde_json::Value::String("true".to_string()))
+    );
+    assert_eq!(checks[0].is.properties.len(), 5);
+}
+
+#[test]
+fn test_container_contents_round_trip_into_place_and_checks() {
+    let dir = std::env::temp_dir().join("flintmc_recorder_items_test");
+    let mut recorder = RecorderState::new("sorter", &dir);
+    recorder.record_place([0, 64, 0], "minecraft:stone");
+    recorder.record_place([0, 65, 0], "minecraft:chest");
+    recorder.record_items(
+        [0, 65, 0],
+        "[{Slot: 0b, id: \"minecraft:diamond\", count: 5}]",
+    );
+    recorder.next_tick();
+    recorder.add_assertion([0, 64, 1], "minecraft:hopper");
+    recorder.record_items(
+        [0, 64, 1],
+        "[{Slot: 2b, id: \"minecraft:diamond\", count: 1}]",
+    );
+
+    let path = recorder.save_as(RecordFormat::Json).unwrap();
+    let (loaded, options) = crate::spec::load_test(&path).unwrap();
+    // place_each with the stone, the chest placed on its own with its items, the hopper assert
+    // and one container check for its single stack
+    assert_eq!(loaded.timeline.len(), 4);
+    assert_eq!(
+        options.block_nbt.get(&1).map(String::as_str),
+        Some("{Items:[{Slot: 0b, id: \"minecraft:diamond\", count: 5}]}")
+    );
+    assert_eq!(
+        options.extra_checks.get(&3),
+        Some(&ExtraCheck::AssertContainer(ContainerCheck {
+            pos: [0, 0, 1],
+            slot: 2,
+            item: "minecraft:diamond".to_string(),
+            count: 1,
+        }))
+    );
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_region_assert_saves_compactly() {
+    let dir = std::env::temp_dir().join("flintmc_recorder_region_test");
+    let mut recorder = RecorderState::new("region", &dir);
+    recorder.record_place([0, 64, 0], "minecraft:stone");
+    // A 3x1x2 box with one stone block, and a uniform 2x2x1 box of glass
+    let mixed = std::collections::HashMap::from([([0, 64, 0], "minecraft:stone".to_string())]);
+    recorder.add_region_assertion([2, 64, 1], [0, 64, 0], &mixed);
+    let glass = std::collections::HashMap::from([
+        ([5, 64, 0], "minecraft:glass".to_string()),
+        ([6, 64, 0], "minecraft:glass".to_string()),
+        ([5, 65, 0], "minecraft:glass".to_string()),
+        ([6, 65, 0], "minecraft:glass".to_string()),
+    ]);
+    recorder.add_region_assertion([5, 64, 0], [6, 65, 0], &glass);
+    assert_eq!(recorder.bounds.max, [6, 1, 1]);
+
+    let document = recorder.to_document().unwrap();
+    let timeline = document["timeline"].as_array().unwrap();
+    assert_eq!(timeline[1]["do"], "assert_region");
+    assert_eq!(timeline[1]["layers"], serde_json::json!([["a..", "..."]]));
+    assert_eq!(timeline[2]["do"], "assert_fill");
+
+    let path = recorder.save_as(RecordFormat::Json).unwrap();
+    let (loaded, options) = crate::spec::load_test(&path).unwrap();
+    let ActionType::Assert { checks } = &loaded.timeline[1].action_type else {
+        panic!("assert_region should load as an assert");
+    };
+    assert_eq!(checks.len(), 6);
+    assert_eq!(checks[0].is.id, "minecraft:stone");
+    assert_eq!(checks[5].pos, [2, 0, 1]);
+    assert!(matches!(
+        options.extra_checks[&2],
+        ExtraCheck::AssertFill(_)
+    ));
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,285 @@
+//! SNBT parsing - reads the text NBT printed by `/data get`, e.g. a container's `Items`
+
+use anyhow::{Result, bail};
+
+/// A parsed SNBT value
+/// Numbers keep their value without the type suffix (`5b` -> 5.0).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Snbt {
+    Compound(Vec<(String, Snbt)>),
+    List(Vec<Snbt>),
+    String(String),
+    Number(f64),
+}
+
+impl Snbt {
+    /// Value of a compound key (case-sensitive, like NBT)
+    pub fn get(&self, key: &str) -> Option<&Snbt> {
+        match self {
+            Snbt::Compound(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Snbt::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Snbt::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[Snbt]> {
+        match self {
+            Snbt::List(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// Parse one SNBT value, allowing surrounding whitespace
+pub fn parse(input: &str) -> Result<Snbt> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.chars.len() {
+        bail!("Unexpected trailing SNBT at position {}", parser.pos);
+    }
+    Ok(value)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(c) => bail!("Expected '{}' but found '{}' in SNBT", expected, c),
+            None => bail!("Expected '{}' but SNBT ended", expected),
+        }
+    }
+
+    fn value(&mut self) -> Result<Snbt> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.compound(),
+            Some('[') => self.list(),
+            Some('"' | '\'') => Ok(Snbt::String(self.quoted()?)),
+            Some(_) => {
+                let word = self.bare()?;
+                Ok(number(&word).map_or(Snbt::String(word), Snbt::Number))
+            }
+            None => bail!("SNBT ended where a value was expected"),
+        }
+    }
+
+    fn compound(&mut self) -> Result<Snbt> {
+        self.expect('{')?;
+        let mut entries = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(Snbt::Compound(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = match self.peek() {
+                Some('"' | '\'') => self.quoted()?,
+                _ => self.bare()?,
+            };
+            self.expect(':')?;
+            entries.push((key, self.value()?));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(Snbt::Compound(entries));
+                }
+                _ => bail!("Expected ',' or '}}' in SNBT compound"),
+            }
+        }
+    }
+
+    fn list(&mut self) -> Result<Snbt> {
+        self.expect('[')?;
+        // Typed arrays: [B; 1b, 2b], [I; 1, 2], [L; 1L]
+        if matches!(self.chars.get(self.pos + 1), Some(';'))
+            && matches!(self.peek(), Some('B' | 'I' | 'L'))
+        {
+            self.pos += 2;
+        }
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(Snbt::List(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(Snbt::List(items));
+                }
+                _ => bail!("Expected ',' or ']' in SNBT list"),
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String> {
+        let quote = self.peek().unwrap_or('"');
+        self.pos += 1;
+        let mut out = String::new();
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '\\' => {
+                    let escaped = self
+                        .peek()
+                        .ok_or_else(|| anyhow::anyhow!("SNBT string ends in an escape"))?;
+                    self.pos += 1;
+                    out.push(escaped);
+                }
+                c if c == quote => return Ok(out),
+                c => out.push(c),
+            }
+        }
+        bail!("Unterminated SNBT string")
+    }
+
+    /// Unquoted word: keys, numbers, booleans and plain strings
+    fn bare(&mut self) -> Result<String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || "_-.+:".contains(c))
+        {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            bail!("Unexpected character in SNBT at position {}", self.pos);
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+}
+
+/// A bare word as a number, stripping a type suffix (b, s, l, f, d); booleans are 1/0
+fn number(word: &str) -> Option<f64> {
+    match word {
+        "true" => return Some(1.0),
+        "false" => return Some(0.0),
+        _ => {}
+    }
+    let digits = word
+        .strip_suffix(['b', 'B', 's', 'S', 'l', 'L', 'f', 'F', 'd', 'D'])
+        .unwrap_or(word);
+    if !digits.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+' || c == '.') {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+/// One stack in a container's `Items` list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ItemStack {
+    pub slot: u8,
+    pub id: String,
+    pub count: u32,
+}
+
+/// Stacks of an `Items` list; `count` is read from `count` (1.20.5+) or `Count` (older)
+pub fn item_stacks(items: &Snbt) -> Vec<ItemStack> {
+    items
+        .as_list()
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|item| {
+            Some(ItemStack {
+                slot: item.get("Slot")?.as_number()? as u8,
+                id: item.get("id")?.as_str()?.to_string(),
+                count: item
+                    .get("count")
+                    .or_else(|| item.get("Count"))
+                    .and_then(Snbt::as_number)
+                    .unwrap_or(1.0) as u32,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_items() {
+        let items = parse(
+            r#"[{Slot: 0b, id: "minecraft:diamond", count: 5}, {Slot: 2b, id: "minecraft:stone", count: 64, components: {"minecraft:custom_name": '{"text":"a, b}"}'}}]"#,
+        )
+        .unwrap();
+        assert_eq!(
+            item_stacks(&items),
+            vec![
+                ItemStack {
+                    slot: 0,
+                    id: "minecraft:diamond".to_string(),
+                    count: 5,
+                },
+                ItemStack {
+                    slot: 2,
+                    id: "minecraft:stone".to_string(),
+                    count: 64,
+                },
+            ]
+        );
+
+        let legacy = parse(r#"[{Count: 3b, Slot: 1b, id: "minecraft:hopper"}]"#).unwrap();
+        assert_eq!(item_stacks(&legacy)[0].count, 3);
+        assert!(item_stacks(&parse("[]").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_parse_values() {
+        assert_eq!(
+            parse("[I; 1, -2]").unwrap(),
+            Snbt::List(vec![Snbt::Number(1.0), Snbt::Number(-2.0)])
+        );
+        assert_eq!(parse("1.5f").unwrap(), Snbt::Number(1.5));
+        assert_eq!(parse("true").unwrap(), Snbt::Number(1.0));
+        assert_eq!(
+            parse("minecraft:stone").unwrap(),
+            Snbt::String("minecraft:stone".to_string())
+        );
+        assert!(parse("{Slot: 0b").is_err());
+        assert!(parse("[1, 2] 3").is_err());
+    }
+}
@@ -1,8 +1,9 @@
 //! Tick management - gametime queries, stepping, and sprinting
 
-use crate::bot::TestBot;
+use crate::bot::{ChatKind, ChatMessage, TestBot};
 use anyhow::Result;
 use colored::Colorize;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 // Constants for tick timing
 pub const CHAT_DRAIN_TIMEOUT_MS: u64 = 10;
@@ -14,10 +15,14 @@ pub const TICK_STEP_POLL_MS: u64 = 50;
 pub const SPRINT_TIMEOUT_SECS: u64 = 30;
 pub const MIN_RETRY_DELAY_MS: u64 = 200;
 
-/// Drain old chat messages from the bot's queue
-pub async fn drain_chat_messages(bot: &mut TestBot) {
+/// Drain old chat messages of one kind from the bot's queue
+/// Other kinds are left alone, so e.g. a gametime query can't eat a pending step command
+pub async fn drain_chat_messages(bot: &mut TestBot, kind: ChatKind) {
     while bot
-        .recv_chat_timeout(std::time::Duration::from_millis(CHAT_DRAIN_TIMEOUT_MS))
+        .recv_chat_timeout(
+            kind,
+            std::time::Duration::from_millis(CHAT_DRAIN_TIMEOUT_MS),
+        )
         .await
         .is_some()
     {
@@ -25,8 +30,92 @@ pub async fn drain_chat_messages(bot: &mut TestBot) {
     }
 }
 
-/// Returns true to continue, false to step to next tick only
-pub async fn wait_for_step(bot: &mut TestBot, reason: &str) -> Result<bool> {
+/// Player response at a breakpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepCommand {
+    /// Resume until the next breakpoint
+    Continue,
+    /// Advance a single tick and pause again
+    Step,
+    /// Replay up to the previous tick and pause there
+    StepBack,
+    /// Stop the run, clean up, and report the remaining tests as aborted
+    Abort,
+}
+
+/// Short keys accepted at a breakpoint, in addition to the words "step" and "continue"
+/// `a`/`abort` is always accepted, unless `a` was picked as one of these keys
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepKeys {
+    pub step: String,
+    pub continue_key: String,
+}
+
+impl Default for StepKeys {
+    fn default() -> Self {
+        Self {
+            step: "s".to_string(),
+            continue_key: "c".to_string(),
+        }
+    }
+}
+
+/// Parse a chat message into a step command - match exact commands only
+fn parse_step_command(
+    message: &str,
+    keys: &StepKeys,
+    allow_step_back: bool,
+) -> Option<StepCommand> {
+    let msg_lower = message.to_lowercase();
+    let trimmed = msg_lower.trim();
+
+    // Match the message ending with just the command (player commands are prefixed by "<name> ")
+    let is = |command: &str| {
+        let command = command.to_lowercase();
+        trimmed == command || trimmed.ends_with(&format!(" {}", command))
+    };
+
+    if allow_step_back && (is("!step-back") || is("step-back")) {
+        Some(StepCommand::StepBack)
+    } else if is(&keys.step) || is("step") {
+        Some(StepCommand::Step)
+    } else if is(&keys.continue_key) || is("continue") {
+        Some(StepCommand::Continue)
+    } else if is("a") || is("abort") {
+        Some(StepCommand::Abort)
+    } else {
+        None
+    }
+}
+
+/// The bot's own `say` line at a breakpoint, skipped when reading step commands
+const STEP_PROMPT: &str = "Waiting for step/continue";
+
+/// Next line that may hold a step command, waiting up to CHAT_POLL_TIMEOUT_MS
+/// Player chat comes first; system messages cover the console, `/say` and `/tellraw`.
+async fn recv_step_input(bot: &mut TestBot) -> Option<String> {
+    let half = std::time::Duration::from_millis(CHAT_POLL_TIMEOUT_MS / 2);
+    if let Some(ChatMessage::Player { message, .. }) =
+        bot.recv_chat_timeout(ChatKind::Player, half).await
+    {
+        return Some(message);
+    }
+    match bot.recv_chat_timeout(ChatKind::System, half).await {
+        Some(ChatMessage::System(message)) if !message.contains(STEP_PROMPT) => Some(message),
+        _ => None,
+    }
+}
+
+/// Pause at a breakpoint until the player sends a step command
+/// `!step-back` is only offered when `allow_step_back` is set.
+/// Returns `Abort` as soon as `interrupted` is set (Ctrl-C).
+pub async fn wait_for_step_command(
+    bot: &mut TestBot,
+    keys: &StepKeys,
+    reason: &str,
+    allow_step_back: bool,
+    interrupted: &AtomicBool,
+) -> Result<StepCommand> {
     println!(
         "\n{} {} {}",
         "⏸".yellow().bold(),
@@ -34,49 +123,50 @@ pub async fn wait_for_step(bot: &mut TestBot, reason: &str) -> Result<bool> {
         reason
     );
 
-    println!(
-        "  Waiting for in-game chat command: {} = step, {} = continue",
-        "s".cyan().bold(),
-        "c".cyan().bold()
-    );
+    if allow_step_back {
+        println!(
+            "  Waiting for in-game chat command: {} = step, {} = continue, {} = replay to previous tick, {} = abort",
+            keys.step.cyan().bold(),
+            keys.continue_key.cyan().bold(),
+            "!step-back".cyan().bold(),
+            "a".cyan().bold()
+        );
+    } else {
+        println!(
+            "  Waiting for in-game chat command: {} = step, {} = continue, {} = abort",
+            keys.step.cyan().bold(),
+            keys.continue_key.cyan().bold(),
+            "a".cyan().bold()
+        );
+    }
 
-    // Send chat message to inform player
-    bot.send_command("say Waiting for step/continue (s = step, c = continue)")
-        .await?;
+    // First, drain any old commands from the chat queue
+    drain_chat_messages(bot, ChatKind::Player).await;
+    drain_chat_messages(bot, ChatKind::System).await;
 
-    // First, drain any old messages from the chat queue
-    drain_chat_messages(bot).await;
+    // Send chat message to inform player
+    bot.send_command(&format!(
+        "say {} ({} = step, {} = continue, a = abort)",
+        STEP_PROMPT, keys.step, keys.continue_key
+    ))
+    .await?;
 
     // Now wait for a fresh chat command
     loop {
-        if let Some((_, message)) = bot
-            .recv_chat_timeout(std::time::Duration::from_millis(CHAT_POLL_TIMEOUT_MS))
-            .await
-        {
-            // Skip messages from the bot itself (contains "Waiting for step/continue")
-            if message.contains("Waiting for step/continue") {
-                continue;
-            }
-
-            // Look for commands in the message - match exact commands only
-            let msg_lower = message.to_lowercase();
-            let trimmed = msg_lower.trim();
-
-            // Match the message ending with just "s" or "c" (player commands)
-            if trimmed.ends_with(" s")
-                || trimmed == "s"
-                || trimmed.ends_with(" step")
-                || trimmed == "step"
-            {
-                println!("  {} Received 's' from chat", "→".blue());
-                return Ok(false); // Step mode
-            } else if trimmed.ends_with(" c")
-                || trimmed == "c"
-                || trimmed.ends_with(" continue")
-                || trimmed == "continue"
-            {
-                println!("  {} Received 'c' from chat", "→".blue());
-                return Ok(true); // Continue mode
+        if interrupted.load(Ordering::SeqCst) {
+            return Ok(StepCommand::Abort);
+        }
+        bot.ensure_connected()?;
+        if let Some(message) = recv_step_input(bot).await {
+            if let Some(command) = parse_step_command(&message, keys, allow_step_back) {
+                let label = match command {
+                    StepCommand::Step => keys.step.as_str(),
+                    StepCommand::Continue => keys.continue_key.as_str(),
+                    StepCommand::StepBack => "!step-back",
+                    StepCommand::Abort => "abort",
+                };
+                println!("  {} Received '{}' from chat", "→".blue(), label);
+                return Ok(command);
             }
         }
     }
@@ -85,8 +175,10 @@ pub async fn wait_for_step(bot: &mut TestBot, reason: &str) -> Result<bool> {
 /// Query the current game time from the server
 /// Returns the game time in ticks
 pub async fn query_gametime(bot: &mut TestBot) -> Result<u32> {
-    // Clear any pending chat messages
-    drain_chat_messages(bot).await;
+    bot.ensure_connected()?;
+
+    // Clear any stale gametime replies
+    drain_chat_messages(bot, ChatKind::GameTime).await;
 
     // Send the time query command
     bot.send_command("time query gametime").await?;
@@ -96,24 +188,16 @@ pub async fn query_gametime(bot: &mut TestBot) -> Result<u32> {
     let start = std::time::Instant::now();
 
     while start.elapsed() < timeout {
-        if let Some((_, message)) = bot
-            .recv_chat_timeout(std::time::Duration::from_millis(CHAT_POLL_TIMEOUT_MS))
+        bot.ensure_connected()?;
+        if let Some(ChatMessage::GameTime(time)) = bot
+            .recv_chat_timeout(
+                ChatKind::GameTime,
+                std::time::Duration::from_millis(CHAT_POLL_TIMEOUT_MS),
+            )
             .await
         {
-            // Look for "The time is" message
-            if message.contains("The time is") {
-                // Extract the time value
-                if let Some(time_str) = message.split("The time is ").nth(1) {
-                    // Parse the number (might have formatting)
-                    let time_clean = time_str
-                        .chars()
-                        .filter(|c| c.is_ascii_digit())
-                        .collect::<String>();
-                    if let Ok(time) = time_clean.parse::<u32>() {
-                        return Ok(time);
-                    }
-                }
-            }
+            bot.set_known_gametime(time);
+            return Ok(time);
         }
     }
 
@@ -121,14 +205,22 @@ pub async fn query_gametime(bot: &mut TestBot) -> Result<u32> {
 }
 
 /// Step a single tick using /tick step and verify completion
-/// Returns the time taken in ms
-pub async fn step_tick(bot: &mut TestBot, verbose: bool) -> Result<u64> {
-    let before = query_gametime(bot).await?;
+/// The game time read by the previous step's confirmation is reused as this step's starting
+/// point, so consecutive steps cost one gametime query each instead of two; the query before
+/// the step is only sent after something else (a sprint, unfreeze) may have moved time.
+/// Returns the time taken in ms. Fails immediately if the bot is no longer in game.
+pub async fn step_tick(bot: &mut TestBot) -> Result<u64> {
+    bot.ensure_connected()?;
+    let before = match bot.known_gametime() {
+        Some(time) => time,
+        None => query_gametime(bot).await?,
+    };
 
     let start = std::time::Instant::now();
     bot.send_command("tick step").await?;
 
-    // Wait for the tick to actually complete by polling gametime
+    // A frozen server runs the stepped tick within one tick interval, so the first
+    // confirming query almost always sees it; later polls only cover a lagging server
     let timeout = std::time::Duration::from_secs(TICK_STEP_TIMEOUT_SECS);
     let poll_start = std::time::Instant::now();
 
@@ -138,15 +230,7 @@ pub async fn step_tick(bot: &mut TestBot, verbose: bool) -> Result<u64> {
 
         if after > before {
             let elapsed = start.elapsed().as_millis() as u64;
-            if verbose {
-                println!(
-                    "    {} Stepped 1 tick (verified: {} -> {}) in {} ms",
-                    "→".dimmed(),
-                    before,
-                    after,
-                    elapsed
-                );
-            }
+            tracing::debug!(before, after, elapsed_ms = elapsed, "Stepped 1 tick");
             return Ok(elapsed);
         }
 
@@ -156,20 +240,47 @@ pub async fn step_tick(bot: &mut TestBot, verbose: bool) -> Result<u64> {
     }
 }
 
-/// Sprint ticks and capture the time taken from server output
-/// Returns the ms per tick from the server's sprint completion message
+/// Build the sprint command that advances exactly `ticks` ticks
+/// Returns None for fewer than 2 ticks, which must not be sprinted
 /// NOTE: Accounts for Minecraft's off-by-one bug where "tick sprint N" executes N+1 ticks
-pub async fn sprint_ticks(bot: &mut TestBot, ticks: u32, verbose: bool) -> Result<u64> {
-    // Clear any pending chat messages
-    drain_chat_messages(bot).await;
-
-    // Account for Minecraft's off-by-one bug: "tick sprint N" executes N+1 ticks
+fn sprint_command(ticks: u32) -> Option<String> {
+    if ticks < 2 {
+        return None;
+    }
     // So to execute `ticks` ticks, we request ticks-1
-    let ticks_to_request = ticks - 1;
+    Some(format!("tick sprint {}", ticks - 1))
+}
+
+/// Time taken by a sprint
+pub struct SprintTiming {
+    /// Total time to allow for the sprinted ticks, in ms
+    pub total_ms: u64,
+    /// Server-reported ms per tick, if the completion message could be parsed
+    pub mspt: Option<f64>,
+}
+
+/// Sprint ticks and capture the time taken from server output
+/// Uses the ms per tick from the server's sprint completion message
+/// A single tick is delegated to `step_tick`, since "tick sprint 0" is invalid
+pub async fn sprint_ticks(bot: &mut TestBot, ticks: u32) -> Result<SprintTiming> {
+    let Some(command) = sprint_command(ticks) else {
+        if ticks == 0 {
+            anyhow::bail!("Cannot sprint 0 ticks");
+        }
+        return Ok(SprintTiming {
+            total_ms: step_tick(bot).await?,
+            mspt: None,
+        });
+    };
+
+    // Watchdog: don't wait out the sprint timeout on a dead connection
+    bot.ensure_connected()?;
+
+    // Clear any stale completion messages
+    drain_chat_messages(bot, ChatKind::Sprint).await;
 
     // Send the sprint command
-    bot.send_command(&format!("tick sprint {}", ticks_to_request))
-        .await?;
+    bot.send_command(&command).await?;
 
     // Wait for the "Sprint completed" message
     // Server message format: "Sprint completed with X ticks per second, or Y ms per tick"
@@ -177,50 +288,101 @@ pub async fn sprint_ticks(bot: &mut TestBot, ticks: u32, verbose: bool) -> Resul
     let start = std::time::Instant::now();
 
     while start.elapsed() < timeout {
-        if let Some((_, message)) = bot
-            .recv_chat_timeout(std::time::Duration::from_millis(CHAT_POLL_TIMEOUT_MS))
+        bot.ensure_connected()?;
+        if let Some(ChatMessage::Sprint(mspt)) = bot
+            .recv_chat_timeout(
+                ChatKind::Sprint,
+                std::time::Duration::from_millis(CHAT_POLL_TIMEOUT_MS),
+            )
             .await
         {
-            // Look for "Sprint completed" message
-            if message.contains("Sprint completed") {
-                // Try to extract ms per tick
-                // Format: "... or X ms per tick"
-                if let Some(ms_part) = message.split("or ").nth(1)
-                    && let Some(ms_str) = ms_part.split(" ms per tick").next()
-                    && let Ok(ms) = ms_str.trim().parse::<f64>()
-                {
-                    let ms_rounded = ms.ceil() as u64;
-                    if verbose {
-                        println!(
-                            "    {} Sprint {} ticks completed in {} ms per tick",
-                            "⚡".dimmed(),
-                            ticks,
-                            ms_rounded
-                        );
-                    }
-                    // Return total time: ms per tick * number of ticks
-                    return Ok(ms_rounded * ticks as u64);
-                }
-                // If we found the message but couldn't parse, use default
-                if verbose {
-                    println!(
-                        "    {} Sprint {} ticks completed (timing not parsed)",
-                        "⚡".dimmed(),
-                        ticks
-                    );
-                }
-                return Ok(MIN_RETRY_DELAY_MS);
+            if let Some(ms) = mspt {
+                let ms_rounded = ms.ceil() as u64;
+                tracing::debug!(ticks, mspt = ms, "Sprint completed");
+                // Return total time: ms per tick * number of ticks
+                return Ok(SprintTiming {
+                    total_ms: ms_rounded * ticks as u64,
+                    mspt: Some(ms),
+                });
             }
+            // If we found the message but couldn't parse, use default
+            tracing::debug!(ticks, "Sprint completed (timing not parsed)");
+            return Ok(SprintTiming {
+                total_ms: MIN_RETRY_DELAY_MS,
+                mspt: None,
+            });
         }
     }
 
     // Timeout - return default
-    if verbose {
-        println!(
-            "    {} Sprint {} ticks (no completion message received)",
-            "⚡".dimmed(),
-            ticks
+    tracing::debug!(ticks, "Sprint sent, no completion message received");
+    Ok(SprintTiming {
+        total_ms: MIN_RETRY_DELAY_MS,
+        mspt: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_step_command() {
+        let keys = StepKeys::default();
+        assert_eq!(
+            parse_step_command("<Steve> s", &keys, true),
+            Some(StepCommand::Step)
+        );
+        assert_eq!(
+            parse_step_command("<Steve> continue", &keys, false),
+            Some(StepCommand::Continue)
+        );
+        assert_eq!(
+            parse_step_command("<Steve> !step-back", &keys, true),
+            Some(StepCommand::StepBack)
+        );
+        assert_eq!(parse_step_command("<Steve> !step-back", &keys, false), None);
+        assert_eq!(parse_step_command("<Steve> steps", &keys, true), None);
+        assert_eq!(
+            parse_step_command("<Steve> abort", &keys, false),
+            Some(StepCommand::Abort)
+        );
+    }
+
+    #[test]
+    fn test_parse_step_command_custom_keys() {
+        let keys = StepKeys {
+            step: "n".to_string(),
+            continue_key: "g".to_string(),
+        };
+        assert_eq!(
+            parse_step_command("<Steve> n", &keys, false),
+            Some(StepCommand::Step)
+        );
+        assert_eq!(
+            parse_step_command("<Steve> G", &keys, false),
+            Some(StepCommand::Continue)
         );
+        assert_eq!(parse_step_command("<Steve> s", &keys, false), None);
+        assert_eq!(
+            parse_step_command("<Steve> step", &keys, false),
+            Some(StepCommand::Step)
+        );
+    }
+
+    #[test]
+    fn test_sprint_command_never_zero() {
+        assert_eq!(sprint_command(0), None);
+        assert_eq!(sprint_command(1), None);
+        for ticks in 2..1000 {
+            let cmd = sprint_command(ticks).unwrap();
+            assert_ne!(cmd, "tick sprint 0");
+        }
+    }
+
+    #[test]
+    fn test_sprint_command_off_by_one() {
+        assert_eq!(sprint_command(2).as_deref(), Some("tick sprint 1"));
+        assert_eq!(sprint_command(20).as_deref(), Some("tick sprint 19"));
     }
-    Ok(MIN_RETRY_DELAY_MS)
 }
@@ -0,0 +1,202 @@
+//! Tick trace (`--trace-log`) - every command, assertion outcome and tick advance of the merged
+//! timeline as JSON lines, so two runs can be diffed to find where they diverged
+
+use anyhow::{Context, Result};
+use flint_core::results::AssertFailure;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+use super::TestExecutor;
+use super::actions::Outcome;
+
+/// What happened, at the tick the timeline was on
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TraceRecord {
+    /// A batch of tests starts: cleanup, hooks and tick freeze follow
+    Start { tests: Vec<String> },
+    /// A command the bot sent
+    Command { command: String },
+    /// An assertion's outcome; `retry` means it failed but its `within` window is still open
+    Assert {
+        test: String,
+        outcome: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pos: Option<[i32; 3]>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        expected: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        actual: Option<String>,
+    },
+    /// An action that errored, e.g. a rejected command
+    Error { test: String, message: String },
+    /// The timeline moved on: `step` for a single tick, `sprint` for several
+    Advance {
+        mode: &'static str,
+        ticks: u32,
+        ms: u64,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        mspt: Option<f64>,
+    },
+}
+
+impl TraceRecord {
+    /// Records for an action's outcome, one per failed check; none for plain actions, whose
+    /// commands say it all
+    pub fn for_outcome(test: &str, outcome: &Result<Outcome>, retry: bool) -> Vec<Self> {
+        let assert = |outcome: &'static str, detail: Option<&AssertFailure>| TraceRecord::Assert {
+            test: test.to_string(),
+            outcome,
+            pos: detail.map(|detail| detail.position),
+            expected: detail.map(|detail| String::from(&detail.expected)),
+            actual: detail.map(|detail| String::from(&detail.actual)),
+        };
+        match outcome {
+            Ok(Outcome::Action) => Vec::new(),
+            Ok(Outcome::AssertPassed) => vec![assert("passed", None)],
+            Ok(Outcome::AssertFailed(details)) => details
+                .iter()
+                .map(|detail| assert(if retry { "retry" } else { "failed" }, Some(detail)))
+                .collect(),
+            Err(e) => vec![TraceRecord::Error {
+                test: test.to_string(),
+                message: format!("{:#}", e),
+            }],
+        }
+    }
+}
+
+/// One line of the trace
+#[derive(Serialize)]
+struct TraceLine<'a> {
+    tick: u32,
+    #[serde(flatten)]
+    record: &'a TraceRecord,
+    /// Milliseconds since the trace was opened; drop this field before diffing two traces
+    elapsed_ms: u64,
+}
+
+/// Open trace file; a write error stops tracing with one warning instead of failing the run
+pub struct TraceLog {
+    out: Option<BufWriter<File>>,
+    start: Instant,
+}
+
+impl TraceLog {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create trace log {}", path.display()))?;
+        Ok(Self {
+            out: Some(BufWriter::new(file)),
+            start: Instant::now(),
+        })
+    }
+
+    /// Append a record stamped with the time it happened
+    pub fn write(&mut self, tick: u32, record: &TraceRecord, at: Instant) {
+        let Some(out) = self.out.as_mut() else {
+            return;
+        };
+        let line = TraceLine {
+            tick,
+            record,
+            elapsed_ms: at.saturating_duration_since(self.start).as_millis() as u64,
+        };
+        let written = serde_json::to_writer(&mut *out, &line)
+            .map_err(std::io::Error::from)
+            .and_then(|()| out.write_all(b"\n"));
+        if let Err(e) = written {
+            tracing::warn!("Trace log write failed, no longer tracing: {}", e);
+            self.out = None;
+        }
+    }
+
+    /// Flush buffered lines, so the trace is complete up to here even if the run dies
+    pub fn flush(&mut self) {
+        if let Some(out) = self.out.as_mut()
+            && let Err(e) = out.flush()
+        {
+            tracing::warn!("Trace log write failed, no longer tracing: {}", e);
+            self.out = None;
+        }
+    }
+}
+
+impl TestExecutor {
+    /// Trace the commands sent since the last call, at `tick`
+    pub(super) fn trace_commands(&mut self, tick: u32) {
+        let Some(trace) = self.trace.as_mut() else {
+            return;
+        };
+        for (at, command) in self.bot.take_sent_commands() {
+            trace.write(tick, &TraceRecord::Command { command }, at);
+        }
+    }
+
+    /// Trace the commands that led up to `record`, then the record itself
+    pub(super) fn trace(&mut self, tick: u32, record: TraceRecord) {
+        self.trace_commands(tick);
+        if let Some(trace) = self.trace.as_mut() {
+            trace.write(tick, &record, Instant::now());
+        }
+    }
+
+    /// Trace pending commands and flush, e.g. at the end of a tick
+    pub(super) fn flush_trace(&mut self, tick: u32) {
+        self.trace_commands(tick);
+        if let Some(trace) = self.trace.as_mut() {
+            trace.flush();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_lines() {
+        let path = std::env::temp_dir().join(format!("flint-trace-{}.ndjson", std::process::id()));
+        let mut trace = TraceLog::create(&path).unwrap();
+        let at = trace.start;
+        trace.write(
+            3,
+            &TraceRecord::Command {
+                command: "setblock 0 64 0 minecraft:stone".to_string(),
+            },
+            at,
+        );
+        trace.write(
+            3,
+            &TraceRecord::Advance {
+                mode: "sprint",
+                ticks: 5,
+                ms: 12,
+                mspt: None,
+            },
+            at,
+        );
+        trace.flush();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let lines: Vec<serde_json::Value> = text
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            lines[0],
+            serde_json::json!({
+                "tick": 3,
+                "kind": "command",
+                "command": "setblock 0 64 0 minecraft:stone",
+                "elapsed_ms": 0
+            })
+        );
+        assert_eq!(lines[1]["kind"], "advance");
+        assert!(lines[1].get("mspt").is_none());
+    }
+}
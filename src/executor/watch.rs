@@ -0,0 +1,160 @@
+//! Watch mode - re-runs tests whenever their files change
+
+use anyhow::Result;
+use colored::Colorize;
+use flint_core::format::print_concise_summary;
+use flint_core::loader::TestLoader;
+use flint_core::spatial::calculate_test_offset_default;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use super::TestExecutor;
+use crate::bot::ConnectError;
+
+const WATCH_POLL_MS: u64 = 500;
+/// Saves must settle this long before a re-run, so editors writing in several steps trigger one run
+const WATCH_DEBOUNCE_MS: u64 = 300;
+
+/// Test file -> modification time
+type Snapshot = HashMap<PathBuf, SystemTime>;
+
+fn snapshot(files: &[PathBuf]) -> Snapshot {
+    files
+        .iter()
+        .filter_map(|file| {
+            let modified = std::fs::metadata(file).ok()?.modified().ok()?;
+            Some((file.clone(), modified))
+        })
+        .collect()
+}
+
+/// Files that are new or modified in `after`, sorted for a stable run order
+fn changed_files(before: &Snapshot, after: &Snapshot) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = after
+        .iter()
+        .filter(|(file, modified)| before.get(*file) != Some(*modified))
+        .map(|(file, _)| file.clone())
+        .collect();
+    changed.sort();
+    changed
+}
+
+impl TestExecutor {
+    /// Watch mode: stay connected and re-run tests whose files change, until Ctrl-C
+    pub async fn watch_mode(
+        &mut self,
+        test_loader: &mut TestLoader,
+        tags: &[String],
+    ) -> Result<()> {
+        let collect = |loader: &TestLoader| {
+            if tags.is_empty() {
+                loader.collect_all_test_files()
+            } else {
+                loader.collect_by_tags(tags)
+            }
+        };
+
+        let files = collect(test_loader)?;
+        let mut known = snapshot(&files);
+        println!(
+            "{} Watching {} test files for changes (Ctrl-C to stop)",
+            "→".blue().bold(),
+            files.len()
+        );
+
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_millis(WATCH_POLL_MS)).await;
+            if self.is_interrupted() {
+                return Ok(());
+            }
+            self.bot.ensure_connected()?;
+
+            // Pick up new files and tag edits
+            test_loader.verify_and_rebuild_index()?;
+            let mut current = snapshot(&collect(test_loader)?);
+            if changed_files(&known, &current).is_empty() {
+                known = current;
+                continue;
+            }
+
+            // Debounce: wait until the files stop changing
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_millis(WATCH_DEBOUNCE_MS)).await;
+                let settled = snapshot(&collect(test_loader)?);
+                if settled == current {
+                    break;
+                }
+                current = settled;
+            }
+            let changed = crate::order::load_ordered(changed_files(&known, &current), self.seed);
+            known = current;
+
+            let count = changed.len();
+            let mut tests_with_offsets = Vec::new();
+            let mut test_options = Vec::new();
+            for (idx, loaded) in changed.into_iter().enumerate() {
+                match loaded.loaded {
+                    Ok((test, options)) => {
+                        let offset = calculate_test_offset_default(idx, count);
+                        tests_with_offsets.push((test, offset));
+                        test_options.push(options);
+                    }
+                    Err(e) => eprintln!("{} {:#}", "Error:".red().bold(), e),
+                }
+            }
+            if tests_with_offsets.is_empty() {
+                continue;
+            }
+
+            let names: Vec<&str> = tests_with_offsets
+                .iter()
+                .map(|(test, _)| test.name.as_str())
+                .collect();
+            println!("\n{} {}", "re-running:".cyan().bold(), names.join(", "));
+
+            let start = std::time::Instant::now();
+            match self
+                .run_tests_parallel(&tests_with_offsets, &test_options, false)
+                .await
+            {
+                Ok(output) => {
+                    print_concise_summary(&output.results, start.elapsed());
+                    for (name, reason) in &output.skipped {
+                        println!("{} {} ({})", "SKIP".yellow().bold(), name, reason.dimmed());
+                    }
+                }
+                // Keep watching through test errors, but not through a lost server
+                Err(e) if e.downcast_ref::<ConnectError>().is_some() => return Err(e),
+                Err(e) => eprintln!("{} {:#}", "Error:".red().bold(), e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_changed_files() {
+        let t0 = SystemTime::UNIX_EPOCH;
+        let t1 = t0 + Duration::from_secs(1);
+        let before: Snapshot = [(PathBuf::from("a.json"), t0), (PathBuf::from("b.json"), t0)]
+            .into_iter()
+            .collect();
+        let after: Snapshot = [
+            (PathBuf::from("a.json"), t0),
+            (PathBuf::from("b.json"), t1),
+            (PathBuf::from("c.json"), t0),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            changed_files(&before, &after),
+            vec![PathBuf::from("b.json"), PathBuf::from("c.json")]
+        );
+        assert!(changed_files(&after, &after).is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+//! Process exit codes - lets CI tell test failures apart from infrastructure errors
+
+use crate::bot::ConnectError;
+
+/// Exit code for errors that don't fall into a more specific category
+pub const EXIT_OTHER: u8 = 6;
+
+/// A run outcome that maps to a distinct exit code
+#[derive(Debug, thiserror::Error)]
+pub enum RunError {
+    /// One or more tests failed (exit code 1)
+    #[error("{0} test(s) failed")]
+    TestsFailed(usize),
+    /// Invalid combination of command-line arguments (exit code 2, same as clap)
+    #[error("{0}")]
+    Usage(String),
+    /// A test file could not be found, read or parsed (exit code 3)
+    #[error("{0:#}")]
+    Load(anyhow::Error),
+    /// A timeout, e.g. while connecting (exit code 4)
+    #[error("{0:#}")]
+    Timeout(anyhow::Error),
+    /// The server could not be reached or dropped the bot (exit code 5)
+    #[error("{0:#}")]
+    Connection(anyhow::Error),
+    /// Tests passed but the server ran slower than `--max-mspt` allows (exit code 7)
+    #[error("Server {stat} ms/tick {measured:.2} exceeds --max-mspt {threshold:.2}")]
+    TooSlow {
+        stat: &'static str,
+        measured: f64,
+        threshold: f64,
+    },
+    /// The run was aborted from a breakpoint before every test finished, with no failures so far
+    /// (exit code 8)
+    #[error("Run aborted before every test finished")]
+    Aborted,
+    /// The run was interrupted with Ctrl-C (exit code 130, as for SIGINT)
+    #[error("Interrupted")]
+    Interrupted,
+}
+
+impl RunError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            RunError::TestsFailed(_) => 1,
+            RunError::Usage(_) => 2,
+            RunError::Load(_) => 3,
+            RunError::Timeout(_) => 4,
+            RunError::Connection(_) => 5,
+            RunError::TooSlow { .. } => 7,
+            RunError::Aborted => 8,
+            RunError::Interrupted => 130,
+        }
+    }
+
+    /// Classify an error from connecting to the server
+    pub fn from_connect(error: anyhow::Error) -> Self {
+        match error.downcast_ref::<ConnectError>() {
+            Some(ConnectError::Timeout { .. } | ConnectError::NotInGame { .. }) => {
+                RunError::Timeout(error)
+            }
+            _ => RunError::Connection(error),
+        }
+    }
+}
+
+/// Exit code for an error returned from the run
+pub fn exit_code_for(error: &anyhow::Error) -> u8 {
+    if let Some(run_error) = error.downcast_ref::<RunError>() {
+        return run_error.exit_code();
+    }
+    // Losing the bot mid-run surfaces from deep in the executor - same code as RunError::Connection
+    match error.downcast_ref::<ConnectError>() {
+        Some(
+            ConnectError::Disconnected
+            | ConnectError::Kicked { .. }
+            | ConnectError::SpamKicked { .. },
+        ) => 5,
+        _ => EXIT_OTHER,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        assert_eq!(exit_code_for(&RunError::TestsFailed(2).into()), 1);
+        assert_eq!(
+            exit_code_for(&RunError::Load(anyhow::anyhow!("bad json")).into()),
+            3
+        );
+        assert_eq!(exit_code_for(&anyhow::anyhow!("unexpected")), EXIT_OTHER);
+        assert_eq!(exit_code_for(&RunError::Interrupted.into()), 130);
+        assert_eq!(exit_code_for(&RunError::Aborted.into()), 8);
+    }
+
+    #[test]
+    fn test_connect_errors_are_classified() {
+        let timeout = ConnectError::Timeout {
+            server: "localhost:25565".to_string(),
+            secs: 15,
+        };
+        assert_eq!(RunError::from_connect(timeout.into()).exit_code(), 4);
+
+        let refused = ConnectError::Refused {
+            server: "localhost:25566".to_string(),
+            reason: "Connection refused".to_string(),
+        };
+        assert_eq!(RunError::from_connect(refused.into()).exit_code(), 5);
+
+        let lost = anyhow::Error::from(ConnectError::Disconnected).context("Tick step failed");
+        assert_eq!(exit_code_for(&lost), 5);
+    }
+}
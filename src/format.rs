@@ -0,0 +1,914 @@
+//! Machine-readable result formatters
+//!
+//! Every formatter writes to a caller-provided target so results can go to
+//! stdout or to a file without mixing with human-readable logs.
+
+use crate::executor::{PerfGate, PerfSummary, RunEvent, RunProfile};
+use flint_core::results::{AssertFailure, TestResult};
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::time::{Duration, SystemTime};
+
+/// Every recorded failure detail for a test, first failure first
+fn failures_of<'a>(
+    failures: &'a [(String, AssertFailure)],
+    test_name: &str,
+) -> Vec<&'a AssertFailure> {
+    failures
+        .iter()
+        .filter(|(name, _)| name == test_name)
+        .map(|(_, failure)| failure)
+        .collect()
+}
+
+/// Outcome of a finished test, telling `expect_failure` tests apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Passed,
+    Failed,
+    /// Expected to fail and did
+    XFail,
+    /// Expected to fail but passed, which fails the run
+    XPass,
+}
+
+impl Status {
+    /// With `expect_failure`, a pass is an XFAIL and a failure an XPASS
+    fn of(result: &TestResult, expect_failure: bool) -> Self {
+        match (result.success, expect_failure) {
+            (true, false) => Status::Passed,
+            (false, false) => Status::Failed,
+            (true, true) => Status::XFail,
+            (false, true) => Status::XPass,
+        }
+    }
+
+    /// Lowercase name used in JSON
+    fn name(self) -> &'static str {
+        match self {
+            Status::Passed => "passed",
+            Status::Failed => "failed",
+            Status::XFail => "xfail",
+            Status::XPass => "xpass",
+        }
+    }
+}
+
+/// Status of a result in a run whose `expect_failure` tests are `expected_failures`
+fn status_in(result: &TestResult, expected_failures: &HashSet<String>) -> Status {
+    Status::of(result, expected_failures.contains(&result.test_name))
+}
+
+/// Chat and console label of a result: PASS, FAIL, XFAIL or XPASS
+pub fn status_label(result: &TestResult, expect_failure: bool) -> &'static str {
+    match Status::of(result, expect_failure) {
+        Status::Passed => "PASS",
+        Status::Failed => "FAIL",
+        Status::XFail => "XFAIL",
+        Status::XPass => "XPASS",
+    }
+}
+
+/// (XFAIL, XPASS) counts of a run
+fn expected_failure_counts(
+    results: &[TestResult],
+    expected_failures: &HashSet<String>,
+) -> (usize, usize) {
+    results.iter().fold((0, 0), |(xfail, xpass), r| {
+        match status_in(r, expected_failures) {
+            Status::XFail => (xfail + 1, xpass),
+            Status::XPass => (xfail, xpass + 1),
+            Status::Passed | Status::Failed => (xfail, xpass),
+        }
+    })
+}
+
+/// ", 2 xfail, 1 xpass" for summaries, leaving out zero counts
+fn expected_failure_suffix(results: &[TestResult], expected_failures: &HashSet<String>) -> String {
+    let (xfail, xpass) = expected_failure_counts(results, expected_failures);
+    let mut suffix = String::new();
+    if xfail > 0 {
+        suffix.push_str(&format!(", {} xfail", xfail));
+    }
+    if xpass > 0 {
+        suffix.push_str(&format!(", {} xpass", xpass));
+    }
+    suffix
+}
+
+/// Short "expected X, got Y" description of a failure
+fn failure_message(failure: &AssertFailure) -> String {
+    format!(
+        "expected {}, got {}",
+        String::from(&failure.expected),
+        String::from(&failure.actual)
+    )
+}
+
+/// Failure message with where and when it happened
+fn failure_line(failure: &AssertFailure) -> String {
+    format!(
+        "{} at ({},{},{}) tick {}",
+        failure_message(failure),
+        failure.position[0],
+        failure.position[1],
+        failure.position[2],
+        failure.tick
+    )
+}
+
+/// Optional timing sections of the JSON document, each left out when None
+#[derive(Default)]
+pub struct Timing<'a> {
+    pub performance: Option<&'a PerfSummary>,
+    pub gate: Option<&'a PerfGate>,
+    pub profile: Option<&'a RunProfile>,
+}
+
+/// Print results as a JSON document with summary, per-test and failure sections
+pub fn print_json(
+    out: &mut dyn Write,
+    results: &[TestResult],
+    failures: &[(String, AssertFailure)],
+    skipped: &[(String, String)],
+    expected_failures: &HashSet<String>,
+    timing: &Timing,
+    elapsed: Duration,
+) -> io::Result<()> {
+    let passed = results.iter().filter(|r| r.success).count();
+    let (xfail, xpass) = expected_failure_counts(results, expected_failures);
+    let mut tests: Vec<_> = results
+        .iter()
+        .map(|r| {
+            serde_json::json!({
+                "name": r.test_name,
+                "status": status_in(r, expected_failures).name(),
+                "success": r.success,
+                "total_ticks": r.total_ticks,
+                "execution_time_ms": r.execution_time_ms,
+            })
+        })
+        .collect();
+    tests.extend(skipped.iter().map(|(name, reason)| {
+        serde_json::json!({
+            "name": name,
+            "status": "skipped",
+            "reason": reason,
+        })
+    }));
+    let failure_entries: Vec<_> = failures
+        .iter()
+        .map(|(name, failure)| {
+            serde_json::json!({
+                "test": name,
+                "tick": failure.tick,
+                "expected": String::from(&failure.expected),
+                "actual": String::from(&failure.actual),
+                "position": failure.position,
+            })
+        })
+        .collect();
+
+    let mut document = serde_json::json!({
+        "summary": {
+            "total": results.len() + skipped.len(),
+            "passed": passed,
+            "failed": results.len() - passed,
+            "skipped": skipped.len(),
+            "xfail": xfail,
+            "xpass": xpass,
+            "duration_secs": elapsed.as_secs_f64(),
+        },
+        "tests": tests,
+        "failures": failure_entries,
+    });
+    if let Some(perf) = timing.performance {
+        document["performance"] = serde_json::json!({
+            "samples": perf.samples,
+            "sprinted_ticks": perf.ticks,
+            "min_mspt": perf.min_mspt,
+            "avg_mspt": perf.avg_mspt,
+            "max_mspt": perf.max_mspt,
+            "total_server_ms": perf.total_server_ms,
+        });
+        if let Some(gate) = timing.gate {
+            document["performance"]["gate"] = serde_json::json!({
+                "stat": gate.stat.name(),
+                "threshold_mspt": gate.threshold,
+                "measured_mspt": gate.measured,
+                "passed": gate.passed(),
+            });
+        }
+    }
+
+    if let Some(profile) = timing.profile {
+        let mut phases = serde_json::Map::new();
+        for (phase, ms) in &profile.phases {
+            phases.insert(format!("{}_ms", phase.name()), (*ms).into());
+        }
+        phases.insert("other_ms".to_string(), profile.other_ms.into());
+        phases.insert("total_ms".to_string(), profile.total_ms.into());
+        document["profile"] = serde_json::Value::Object(phases);
+    }
+
+    serde_json::to_writer_pretty(&mut *out, &document)?;
+    writeln!(out)
+}
+
+/// Print results in Test Anything Protocol version 13
+pub fn print_tap(
+    out: &mut dyn Write,
+    results: &[TestResult],
+    failures: &[(String, AssertFailure)],
+    skipped: &[(String, String)],
+    expected_failures: &HashSet<String>,
+) -> io::Result<()> {
+    writeln!(out, "TAP version 13")?;
+    writeln!(out, "1..{}", results.len() + skipped.len())?;
+    for (idx, result) in results.iter().enumerate() {
+        // TAP's TODO directive marks a failure that doesn't count against the run
+        match status_in(result, expected_failures) {
+            Status::Passed => {
+                writeln!(out, "ok {} - {}", idx + 1, result.test_name)?;
+                continue;
+            }
+            Status::XFail => {
+                writeln!(
+                    out,
+                    "not ok {} - {} # TODO expected failure",
+                    idx + 1,
+                    result.test_name
+                )?;
+                continue;
+            }
+            Status::Failed | Status::XPass => {}
+        }
+        writeln!(out, "not ok {} - {}", idx + 1, result.test_name)?;
+        let test_failures = failures_of(failures, &result.test_name);
+        let Some(first) = test_failures.first() else {
+            continue;
+        };
+        // The first failure stays at the top level; the full list follows when there are more
+        writeln!(out, "  ---")?;
+        writeln!(out, "  message: {:?}", failure_message(first))?;
+        writeln!(
+            out,
+            "  at: [{}, {}, {}]",
+            first.position[0], first.position[1], first.position[2]
+        )?;
+        writeln!(out, "  tick: {}", first.tick)?;
+        if test_failures.len() > 1 {
+            writeln!(out, "  failures:")?;
+            for failure in &test_failures {
+                writeln!(out, "    - message: {:?}", failure_message(failure))?;
+                writeln!(
+                    out,
+                    "      at: [{}, {}, {}]",
+                    failure.position[0], failure.position[1], failure.position[2]
+                )?;
+                writeln!(out, "      tick: {}", failure.tick)?;
+            }
+        }
+        writeln!(out, "  ...")?;
+    }
+    for (idx, (name, reason)) in skipped.iter().enumerate() {
+        writeln!(
+            out,
+            "ok {} - {} # SKIP {}",
+            results.len() + idx + 1,
+            name,
+            reason
+        )?;
+    }
+    Ok(())
+}
+
+/// Escape a value for use inside an XML attribute
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Where and when a run happened, for the JUnit `<testsuite>` attributes
+pub struct SuiteInfo {
+    pub hostname: String,
+    /// Start of the run, ISO 8601 in UTC
+    pub timestamp: String,
+}
+
+impl SuiteInfo {
+    /// This machine, for a run that started at `started`
+    pub fn new(started: SystemTime) -> Self {
+        Self {
+            hostname: hostname(),
+            timestamp: iso8601_utc(started),
+        }
+    }
+}
+
+/// The machine's host name from the environment or /etc/hostname, or "localhost"
+fn hostname() -> String {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// Format a time as `YYYY-MM-DDTHH:MM:SS` in UTC, as JUnit's `timestamp` expects
+fn iso8601_utc(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since 1970-01-01 (proleptic Gregorian)
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+/// Print results as JUnit XML
+pub fn print_junit(
+    out: &mut dyn Write,
+    results: &[TestResult],
+    failures: &[(String, AssertFailure)],
+    skipped: &[(String, String)],
+    expected_failures: &HashSet<String>,
+    elapsed: Duration,
+    suite: &SuiteInfo,
+) -> io::Result<()> {
+    let failed = results.iter().filter(|r| !r.success).count();
+    let total = results.len() + skipped.len();
+    let total_secs = elapsed.as_secs_f64();
+    // An XFAIL is reported as skipped, the way other test runners do
+    let (xfail, _) = expected_failure_counts(results, expected_failures);
+    let skipped_count = skipped.len() + xfail;
+
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        out,
+        r#"<testsuites tests="{}" failures="{}" skipped="{}" time="{:.3}">"#,
+        total, failed, skipped_count, total_secs
+    )?;
+    writeln!(
+        out,
+        r#"  <testsuite name="flintmc" tests="{}" failures="{}" skipped="{}" time="{:.3}" hostname="{}" timestamp="{}">"#,
+        total,
+        failed,
+        skipped_count,
+        total_secs,
+        escape_xml(&suite.hostname),
+        suite.timestamp
+    )?;
+    for result in results {
+        let time = result.execution_time_ms as f64 / 1000.0;
+        let name = escape_xml(&result.test_name);
+        if result.success {
+            if status_in(result, expected_failures) == Status::XFail {
+                writeln!(
+                    out,
+                    r#"    <testcase classname="" name="{}" time="{:.3}">"#,
+                    name, time
+                )?;
+                writeln!(out, r#"      <skipped message="expected failure"/>"#)?;
+                writeln!(out, "    </testcase>")?;
+            } else {
+                writeln!(
+                    out,
+                    r#"    <testcase classname="" name="{}" time="{:.3}" />"#,
+                    name, time
+                )?;
+            }
+            continue;
+        }
+        writeln!(
+            out,
+            r#"    <testcase classname="" name="{}" time="{:.3}">"#,
+            name, time
+        )?;
+        // JUnit allows one <failure> per testcase: the first failure is its message and the
+        // element's text lists all of them
+        let test_failures = failures_of(failures, &result.test_name);
+        match test_failures.as_slice() {
+            [] => writeln!(out, r#"      <failure message="test failed"/>"#)?,
+            [failure] => writeln!(
+                out,
+                r#"      <failure message="{}"/>"#,
+                escape_xml(&failure_line(failure))
+            )?,
+            [first, rest @ ..] => {
+                let lines: Vec<String> = test_failures
+                    .iter()
+                    .map(|failure| failure_line(failure))
+                    .collect();
+                writeln!(
+                    out,
+                    r#"      <failure message="{} (and {} more)">{}</failure>"#,
+                    escape_xml(&failure_line(first)),
+                    rest.len(),
+                    escape_xml(&lines.join("\n"))
+                )?;
+            }
+        }
+        writeln!(out, "    </testcase>")?;
+    }
+    for (name, reason) in skipped {
+        writeln!(
+            out,
+            r#"    <testcase classname="" name="{}" time="0.000">"#,
+            escape_xml(name)
+        )?;
+        writeln!(out, r#"      <skipped message="{}"/>"#, escape_xml(reason))?;
+        writeln!(out, "    </testcase>")?;
+    }
+    writeln!(out, "  </testsuite>")?;
+    writeln!(out, "</testsuites>")
+}
+
+/// Escape a value for use in a GitHub Actions workflow command message
+fn escape_github_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a value for use in a GitHub Actions workflow command property (e.g. title)
+fn escape_github_property(s: &str) -> String {
+    escape_github_data(s)
+        .replace(':', "%3A")
+        .replace(',', "%2C")
+}
+
+/// Print results as GitHub Actions annotations
+/// Each failure becomes an `::error` line, followed by a `::notice` summary
+pub fn print_github(
+    out: &mut dyn Write,
+    results: &[TestResult],
+    failures: &[(String, AssertFailure)],
+    skipped: &[(String, String)],
+    expected_failures: &HashSet<String>,
+    elapsed: Duration,
+) -> io::Result<()> {
+    for result in results.iter().filter(|r| !r.success) {
+        let mut messages: Vec<String> = failures_of(failures, &result.test_name)
+            .into_iter()
+            .map(failure_line)
+            .collect();
+        if messages.is_empty() {
+            messages.push("test failed".to_string());
+        }
+        for message in messages {
+            writeln!(
+                out,
+                "::error title={}::{}",
+                escape_github_property(&result.test_name),
+                escape_github_data(&message)
+            )?;
+        }
+    }
+
+    for (name, reason) in skipped {
+        writeln!(
+            out,
+            "::warning title={}::{}",
+            escape_github_property(name),
+            escape_github_data(&format!("skipped: {}", reason))
+        )?;
+    }
+
+    let passed = results.iter().filter(|r| r.success).count();
+    let failed = results.len() - passed;
+    writeln!(
+        out,
+        "::notice::{} passed, {} failed, {} skipped{} ({:.3}s)",
+        passed,
+        failed,
+        skipped.len(),
+        expected_failure_suffix(results, expected_failures),
+        elapsed.as_secs_f64()
+    )
+}
+
+/// Escape a value for use inside a Markdown table cell
+fn escape_markdown_cell(s: &str) -> String {
+    s.replace('|', "\\|")
+}
+
+/// Escape text placed inside an HTML element of the Markdown report, like `<summary>`
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Print results as a Markdown report, suitable for pasting into PRs and chat
+pub fn print_markdown(
+    out: &mut dyn Write,
+    results: &[TestResult],
+    failures: &[(String, AssertFailure)],
+    skipped: &[(String, String)],
+    expected_failures: &HashSet<String>,
+    elapsed: Duration,
+) -> io::Result<()> {
+    let passed = results.iter().filter(|r| r.success).count();
+    let failed = results.len() - passed;
+
+    writeln!(
+        out,
+        "**FlintMC:** {} tests, {} passed, {} failed, {} skipped{} ({:.3}s)",
+        results.len() + skipped.len(),
+        passed,
+        failed,
+        skipped.len(),
+        expected_failure_suffix(results, expected_failures),
+        elapsed.as_secs_f64()
+    )?;
+    writeln!(out)?;
+    writeln!(out, "| Test | Status | Ticks | Duration |")?;
+    writeln!(out, "|------|--------|------:|---------:|")?;
+    for result in results {
+        let status = match status_in(result, expected_failures) {
+            Status::Passed => "✅",
+            Status::Failed => "❌",
+            Status::XFail => "✅ XFAIL",
+            Status::XPass => "❌ XPASS",
+        };
+        writeln!(
+            out,
+            "| {} | {} | {} | {}ms |",
+            escape_markdown_cell(&result.test_name),
+            status,
+            result.total_ticks,
+            result.execution_time_ms
+        )?;
+    }
+    for (name, reason) in skipped {
+        writeln!(
+            out,
+            "| {} | ⏭️ {} | - | - |",
+            escape_markdown_cell(name),
+            escape_markdown_cell(reason)
+        )?;
+    }
+
+    // One collapsible block per failed test, with each of its failures
+    for result in results.iter().filter(|r| !r.success) {
+        let test_failures = failures_of(failures, &result.test_name);
+        if test_failures.is_empty() {
+            continue;
+        }
+        writeln!(out)?;
+        writeln!(out, "<details>")?;
+        writeln!(
+            out,
+            "<summary>❌ {} ({} failed)</summary>",
+            escape_html(&result.test_name),
+            test_failures.len()
+        )?;
+        for failure in test_failures {
+            writeln!(out)?;
+            writeln!(out, "- **Expected:** `{}`", String::from(&failure.expected))?;
+            writeln!(out, "- **Actual:** `{}`", String::from(&failure.actual))?;
+            writeln!(
+                out,
+                "- **Position:** ({}, {}, {})",
+                failure.position[0], failure.position[1], failure.position[2]
+            )?;
+            writeln!(out, "- **Tick:** {}", failure.tick)?;
+        }
+        writeln!(out)?;
+        writeln!(out, "</details>")?;
+    }
+    Ok(())
+}
+
+/// Failing tests named in the one-line summary before it stops listing them
+const ONELINE_EXAMPLES: usize = 3;
+
+/// One plain line for shell prompts and chat bots, e.g.
+/// `FlintMC: 38 passed, 2 failed (examples: foo@tick12, bar@tick40) in 12.3s`
+pub fn oneline_summary(
+    results: &[TestResult],
+    failures: &[(String, AssertFailure)],
+    skipped: &[(String, String)],
+    expected_failures: &HashSet<String>,
+    elapsed: Duration,
+) -> String {
+    let passed = results.iter().filter(|r| r.success).count();
+    let mut line = format!(
+        "FlintMC: {} passed, {} failed",
+        passed,
+        results.len() - passed
+    );
+    if !skipped.is_empty() {
+        line.push_str(&format!(", {} skipped", skipped.len()));
+    }
+    line.push_str(&expected_failure_suffix(results, expected_failures));
+    let examples: Vec<String> = results
+        .iter()
+        .filter(|r| !r.success)
+        .take(ONELINE_EXAMPLES)
+        .map(|r| match failures_of(failures, &r.test_name).first() {
+            Some(failure) => format!("{}@tick{}", r.test_name, failure.tick),
+            None => r.test_name.clone(),
+        })
+        .collect();
+    if !examples.is_empty() {
+        line.push_str(&format!(" (examples: {})", examples.join(", ")));
+    }
+    line.push_str(&format!(" in {:.1}s", elapsed.as_secs_f64()));
+    line
+}
+
+/// Write a single run event as one line of JSON and flush immediately
+pub fn write_ndjson_event(out: &mut dyn Write, event: &RunEvent) -> io::Result<()> {
+    serde_json::to_writer(&mut *out, event)?;
+    writeln!(out)?;
+    out.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_github_data() {
+        assert_eq!(escape_github_data("100%"), "100%25");
+        assert_eq!(escape_github_data("a\nb\r"), "a%0Ab%0D");
+        assert_eq!(
+            escape_github_data("oak_fence[east=true]"),
+            "oak_fence[east=true]"
+        );
+    }
+
+    #[test]
+    fn test_escape_github_property() {
+        assert_eq!(
+            escape_github_property("minecraft:stone,dirt"),
+            "minecraft%3Astone%2Cdirt"
+        );
+    }
+
+    #[test]
+    fn test_tap_reports_skipped_tests() {
+        let skipped = vec![(
+            "smelting".to_string(),
+            "dependency 'furnace_setup' did not pass".to_string(),
+        )];
+        let mut out = Vec::new();
+        print_tap(&mut out, &[], &[], &skipped, &HashSet::new()).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("1..1"));
+        assert!(text.contains("ok 1 - smelting # SKIP dependency 'furnace_setup' did not pass"));
+    }
+
+    #[test]
+    fn test_json_total_counts_skipped_tests() {
+        let results = vec![TestResult::new("furnace_setup".to_string())];
+        let skipped = vec![(
+            "smelting".to_string(),
+            "dependency 'furnace_setup' did not pass".to_string(),
+        )];
+        let mut out = Vec::new();
+        print_json(
+            &mut out,
+            &results,
+            &[],
+            &skipped,
+            &HashSet::new(),
+            &Timing::default(),
+            Duration::from_secs(1),
+        )
+        .unwrap();
+        let document: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(document["summary"]["total"], 2);
+        assert_eq!(document["summary"]["passed"], 1);
+        assert_eq!(document["summary"]["failed"], 0);
+        assert_eq!(document["summary"]["skipped"], 1);
+    }
+
+    #[test]
+    fn test_oneline_summary() {
+        let failed = |name: &str| {
+            TestResult::new(name.to_string()).with_failure_reason("1 assertions failed".to_string())
+        };
+        let results = vec![
+            TestResult::new("ok".to_string()),
+            failed("foo"),
+            failed("bar"),
+        ];
+        let failures = vec![(
+            "foo".to_string(),
+            AssertFailure {
+                tick: 12,
+                expected: flint_core::results::InfoType::String("stone".to_string()),
+                actual: flint_core::results::InfoType::String("air".to_string()),
+                position: [0, 64, 0],
+                error_message: String::new(),
+                execution_time_ms: None,
+            },
+        )];
+        assert_eq!(
+            oneline_summary(
+                &results,
+                &failures,
+                &[],
+                &HashSet::new(),
+                Duration::from_millis(12_340)
+            ),
+            "FlintMC: 1 passed, 2 failed (examples: foo@tick12, bar) in 12.3s"
+        );
+        assert_eq!(
+            oneline_summary(
+                &results[..1],
+                &[],
+                &[],
+                &HashSet::new(),
+                Duration::from_secs(2)
+            ),
+            "FlintMC: 1 passed, 0 failed in 2.0s"
+        );
+    }
+
+    #[test]
+    fn test_iso8601_utc() {
+        let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        assert_eq!(iso8601_utc(at(0)), "1970-01-01T00:00:00");
+        assert_eq!(iso8601_utc(at(951_782_400)), "2000-02-29T00:00:00");
+        assert_eq!(iso8601_utc(at(1_767_225_599)), "2025-12-31T23:59:59");
+    }
+
+    #[test]
+    fn test_junit_suite_attributes() {
+        let suite = SuiteInfo {
+            hostname: "ci-runner-1".to_string(),
+            timestamp: "2026-01-02T03:04:05".to_string(),
+        };
+        let mut out = Vec::new();
+        print_junit(
+            &mut out,
+            &[],
+            &[],
+            &[],
+            &HashSet::new(),
+            Duration::ZERO,
+            &suite,
+        )
+        .unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert!(xml.contains(r#"hostname="ci-runner-1" timestamp="2026-01-02T03:04:05">"#));
+    }
+
+    fn failure_at(tick: u32, y: i32) -> AssertFailure {
+        AssertFailure {
+            tick,
+            expected: flint_core::results::InfoType::String("stone".to_string()),
+            actual: flint_core::results::InfoType::String("air".to_string()),
+            position: [0, y, 0],
+            error_message: String::new(),
+            execution_time_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_every_failure_is_reported() {
+        let results = vec![
+            TestResult::new("wall".to_string())
+                .with_failure_reason("2 assertions failed".to_string()),
+        ];
+        let failures = vec![
+            ("wall".to_string(), failure_at(5, 64)),
+            ("wall".to_string(), failure_at(5, 65)),
+        ];
+
+        let mut out = Vec::new();
+        print_tap(&mut out, &results, &failures, &[], &HashSet::new()).unwrap();
+        let tap = String::from_utf8(out).unwrap();
+        assert!(tap.contains("  at: [0, 64, 0]\n  tick: 5\n  failures:\n"));
+        assert!(tap.contains("      at: [0, 65, 0]"));
+
+        let mut out = Vec::new();
+        print_junit(
+            &mut out,
+            &results,
+            &failures,
+            &[],
+            &HashSet::new(),
+            Duration::ZERO,
+            &SuiteInfo {
+                hostname: "localhost".to_string(),
+                timestamp: "2026-01-02T03:04:05".to_string(),
+            },
+        )
+        .unwrap();
+        let xml = String::from_utf8(out).unwrap();
+        assert_eq!(xml.matches("<failure ").count(), 1);
+        assert!(xml.contains(
+            r#"<failure message="expected stone, got air at (0,64,0) tick 5 (and 1 more)">"#
+        ));
+        assert!(xml.contains("expected stone, got air at (0,65,0) tick 5</failure>"));
+
+        let mut out = Vec::new();
+        print_github(
+            &mut out,
+            &results,
+            &failures,
+            &[],
+            &HashSet::new(),
+            Duration::ZERO,
+        )
+        .unwrap();
+        let github = String::from_utf8(out).unwrap();
+        assert_eq!(github.matches("::error ").count(), 2);
+    }
+
+    #[test]
+    fn test_expected_failures_are_labelled() {
+        let expected_failures = HashSet::from(["format_known_bug".to_string()]);
+        let xfail = TestResult::new("format_known_bug".to_string());
+        let passed = TestResult::new("format_plain".to_string());
+        assert_eq!(status_label(&xfail, true), "XFAIL");
+        assert_eq!(status_label(&passed, false), "PASS");
+
+        let mut out = Vec::new();
+        print_tap(&mut out, &[passed, xfail], &[], &[], &expected_failures).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.contains("ok 1 - format_plain"));
+        assert!(text.contains("not ok 2 - format_known_bug # TODO expected failure"));
+
+        let xpass = TestResult::new("format_known_bug".to_string())
+            .with_failure_reason("expected to fail, but every assertion passed".to_string());
+        assert_eq!(status_label(&xpass, true), "XPASS");
+        let xfail = TestResult::new("format_known_bug".to_string());
+        assert_eq!(
+            oneline_summary(
+                &[xfail, xpass],
+                &[],
+                &[],
+                &expected_failures,
+                Duration::from_secs(1)
+            ),
+            "FlintMC: 1 passed, 1 failed, 1 xfail, 1 xpass (examples: format_known_bug) in 1.0s"
+        );
+    }
+
+    #[test]
+    fn test_markdown_summary_escapes_test_names() {
+        let results = vec![
+            TestResult::new("hopper<chest> & co".to_string())
+                .with_failure_reason("1 assertions failed".to_string()),
+        ];
+        let failures = vec![(
+            "hopper<chest> & co".to_string(),
+            AssertFailure {
+                tick: 3,
+                expected: flint_core::results::InfoType::String("chest".to_string()),
+                actual: flint_core::results::InfoType::String("air".to_string()),
+                position: [0, 64, 0],
+                error_message: String::new(),
+                execution_time_ms: None,
+            },
+        )];
+        let mut out = Vec::new();
+        print_markdown(
+            &mut out,
+            &results,
+            &failures,
+            &[],
+            &HashSet::new(),
+            Duration::from_secs(1),
+        )
+        .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        assert!(
+            text.contains("<summary>❌ hopper&lt;chest&gt; &amp; co (1 failed)</summary>"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn test_escape_xml() {
+        assert_eq!(escape_xml(r#"<a & "b">"#), "&lt;a &amp; &quot;b&quot;&gt;");
+    }
+}
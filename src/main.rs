@@ -1,19 +1,34 @@
 mod bot;
+mod cache;
+mod changed;
 mod executor;
+mod exit;
+mod format;
+mod manifest;
+mod order;
+mod schema;
+mod snapshot;
+mod spec;
+mod validate;
 
 use anyhow::{Context, Result};
 use clap::{CommandFactory, Parser, ValueEnum};
 use clap_complete::Shell;
 use colored::Colorize;
-use flint_core::format;
+use exit::RunError;
 use flint_core::format::{format_number, print_concise_summary, print_test_summary};
 use flint_core::loader::TestLoader;
-use flint_core::results::AssertFailure;
+use flint_core::results::{AssertFailure, TestResult};
 use flint_core::spatial::calculate_test_offset_default;
-use flint_core::test_spec::{ActionType, TestSpec};
+use flint_core::test_spec::ActionType;
+use spec::LoadedTest;
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Write};
 use std::path::Path;
 use std::path::PathBuf;
-use std::time::Instant;
+use std::process::ExitCode;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
 use tracing_subscriber::EnvFilter;
 
 /// Output format for test results
@@ -28,6 +43,44 @@ enum OutputFormat {
     Tap,
     /// JUnit XML
     Junit,
+    /// GitHub Actions workflow annotations
+    Github,
+    /// Markdown report
+    Markdown,
+    /// Newline-delimited JSON events, streamed as tests complete
+    Ndjson,
+}
+
+/// How the pretty output summarizes a finished run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum SummaryFormat {
+    /// Summary block with skipped tests and tick timing (default)
+    #[default]
+    Full,
+    /// A single line with counts, a few failing tests and the duration
+    Oneline,
+}
+
+/// When to color output (`--color`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum ColorChoice {
+    /// Color when stdout is a terminal and NO_COLOR/CLICOLOR allow it (default)
+    #[default]
+    Auto,
+    /// Always color, e.g. for CI logs that render ANSI escapes
+    Always,
+    /// Never color, and draw the progress bar and separators in ASCII
+    Never,
+}
+
+/// Format of diagnostic logs written to stderr
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+enum LogFormat {
+    /// Human-readable lines (default)
+    #[default]
+    Text,
+    /// One JSON object per event, for log aggregation
+    Json,
 }
 
 // Constants
@@ -35,16 +88,51 @@ const CHUNK_SIZE: usize = 100;
 const GRID_SIZE: usize = 10; // Tests are arranged in a 10x10 grid
 const SEPARATOR_WIDTH: usize = 60;
 
+/// Apply `--color` to every `colored` string, returning whether output is colored
+fn apply_color_choice(choice: ColorChoice) -> bool {
+    match choice {
+        ColorChoice::Always => colored::control::set_override(true),
+        ColorChoice::Never => colored::control::set_override(false),
+        // colored already honors NO_COLOR and CLICOLOR; pipes and files get plain text too
+        ColorChoice::Auto => {
+            if !std::io::stdout().is_terminal() {
+                colored::control::set_override(false);
+            }
+        }
+    }
+    colored::control::SHOULD_COLORIZE.should_colorize()
+}
+
+/// A separator line: box drawing when colored, plain ASCII under --color never or a pipe
+fn separator_line() -> String {
+    let glyph = if colored::control::SHOULD_COLORIZE.should_colorize() {
+        "═"
+    } else {
+        "="
+    };
+    glyph.repeat(SEPARATOR_WIDTH)
+}
+
 /// Print a separator line
 fn print_separator() {
-    println!("{}", "═".repeat(SEPARATOR_WIDTH).dimmed());
+    println!("{}", separator_line().dimmed());
+}
+
+/// A test's offset within its chunk: its grid cell, or the first cell for every test with
+/// --sequential, where the tests of a chunk run one at a time
+fn test_offset(args: &Args, test_index: usize, chunk_len: usize) -> [i32; 3] {
+    if args.sequential {
+        calculate_test_offset_default(0, 1)
+    } else {
+        calculate_test_offset_default(test_index, chunk_len)
+    }
 }
 
 /// Print chunk header
 fn print_chunk_header(chunk_idx: usize, total_chunks: usize, chunk_len: usize) {
     println!(
         "{} {} Chunk {}/{} ({} tests in {}x{} grid)",
-        "═".repeat(SEPARATOR_WIDTH).dimmed(),
+        separator_line().dimmed(),
         "→".blue().bold(),
         chunk_idx + 1,
         total_chunks,
@@ -56,6 +144,183 @@ fn print_chunk_header(chunk_idx: usize, total_chunks: usize, chunk_len: usize) {
     println!();
 }
 
+/// Print server tick timing gathered from sprints
+fn print_perf_summary(perf: &executor::PerfSummary) {
+    println!(
+        "{} {:.2} ms/tick avg (min {:.2}, max {:.2}) over {} sprinted ticks, {:.1}s server time",
+        "Server:".bold(),
+        perf.avg_mspt,
+        perf.min_mspt,
+        perf.max_mspt,
+        format_number(perf.ticks as usize),
+        perf.total_server_ms / 1000.0
+    );
+}
+
+/// Print where the run's time went, slowest phase first
+fn print_profile(out: &mut dyn Write, profile: &executor::RunProfile) -> std::io::Result<()> {
+    let total = profile.total_ms.max(1) as f64;
+    writeln!(
+        out,
+        "{} {:.1}s in the timeline",
+        "Profile:".bold(),
+        profile.total_ms as f64 / 1000.0
+    )?;
+    let mut phases: Vec<(&str, u64)> = profile
+        .phases
+        .iter()
+        .map(|(phase, ms)| (phase.name(), *ms))
+        .chain([("other", profile.other_ms)])
+        .collect();
+    phases.sort_by_key(|&(_, ms)| std::cmp::Reverse(ms));
+    for (name, ms) in phases {
+        writeln!(
+            out,
+            "  {:<10} {:>8.1}s {:>5.1}%",
+            name,
+            ms as f64 / 1000.0,
+            ms as f64 * 100.0 / total
+        )?;
+    }
+    Ok(())
+}
+
+/// Print the tests longer than `--warn-long-test`, longest first
+/// The merged timeline runs as long as its longest test, so these set the pace of the suite.
+fn print_long_tests(
+    out: &mut dyn Write,
+    results: &[TestResult],
+    threshold: u32,
+) -> std::io::Result<()> {
+    let mut long: Vec<&TestResult> = results
+        .iter()
+        .filter(|result| result.total_ticks > threshold)
+        .collect();
+    if long.is_empty() {
+        return Ok(());
+    }
+    long.sort_by_key(|result| std::cmp::Reverse(result.total_ticks));
+    writeln!(
+        out,
+        "{} {} test(s) run longer than {} ticks:",
+        "Long tests:".yellow().bold(),
+        long.len(),
+        format_number(threshold as usize)
+    )?;
+    for result in long {
+        writeln!(
+            out,
+            "  {:>8} ticks  {}",
+            format_number(result.total_ticks as usize),
+            result.test_name
+        )?;
+    }
+    Ok(())
+}
+
+/// Print the --max-mspt check
+fn print_perf_gate(gate: &executor::PerfGate) {
+    let (mark, verdict) = if gate.passed() {
+        ("✓".green().bold(), "within")
+    } else {
+        ("✗".red().bold(), "exceeds")
+    };
+    println!(
+        "{} Server {} ms/tick {:.2} {} --max-mspt {:.2}",
+        mark,
+        gate.stat.name(),
+        gate.measured,
+        verdict,
+        gate.threshold
+    );
+}
+
+/// Open the target for machine-readable output: the given file, or stdout
+fn open_output(path: Option<&Path>) -> Result<Box<dyn Write>> {
+    match path {
+        Some(path) => {
+            let file = std::fs::File::create(path)
+                .with_context(|| format!("Failed to create output file: {}", path.display()))?;
+            Ok(Box::new(std::io::BufWriter::new(file)))
+        }
+        None => Ok(Box::new(std::io::stdout())),
+    }
+}
+
+/// Write the JUnit report to a file for CI, next to whatever the terminal shows
+fn write_junit_file(
+    path: &Path,
+    results: &[TestResult],
+    failures: &[(String, AssertFailure)],
+    skipped: &[(String, String)],
+    expected_failures: &HashSet<String>,
+    elapsed: Duration,
+    started_at: std::time::SystemTime,
+) -> Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    let mut out = open_output(Some(path))?;
+    format::print_junit(
+        &mut *out,
+        results,
+        failures,
+        skipped,
+        expected_failures,
+        elapsed,
+        &format::SuiteInfo::new(started_at),
+    )
+    .and_then(|()| out.flush())
+    .with_context(|| format!("Failed to write JUnit report {}", path.display()))
+}
+
+/// On Ctrl-C, set the executor's interrupt flag so the run unfreezes and cleans up before exiting
+/// A second Ctrl-C exits immediately, skipping cleanup
+fn install_interrupt_handler(executor: &executor::TestExecutor) {
+    let interrupted = executor.interrupt_flag();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_err() {
+            return;
+        }
+        eprintln!(
+            "\n{} Interrupted, cleaning up (press Ctrl-C again to exit immediately)",
+            "■".yellow().bold()
+        );
+        interrupted.store(true, Ordering::SeqCst);
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(RunError::Interrupted.exit_code().into());
+        }
+    });
+}
+
+/// Prepare the server for a run: gamerules, then `--before-all` commands
+/// If a step fails, whatever was already changed is undone before returning the error.
+async fn start_suite(executor: &mut executor::TestExecutor, args: &Args) -> Result<()> {
+    let started = match executor.apply_gamerules(&args.gamerules).await {
+        Ok(()) => {
+            executor
+                .run_before_all(&args.before_all, &args.after_all)
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    if let Err(e) = started {
+        finish_suite(executor).await?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Undo `start_suite` in reverse: `--after-all` commands, then the original gamerules
+async fn finish_suite(executor: &mut executor::TestExecutor) -> Result<()> {
+    executor.run_after_all().await;
+    executor.restore_gamerules().await
+}
+
 // ─────────────────────────────────────────────────────────────
 
 #[derive(Parser, Debug)]
@@ -78,6 +343,42 @@ struct Args {
     #[arg(long)]
     break_after_setup: bool,
 
+    /// Pause with the world frozen as soon as any assertion fails
+    #[arg(long)]
+    break_on_failure: bool,
+
+    /// Pause after the timeline completes, before cleanup, with time frozen and test areas intact
+    #[arg(long)]
+    break_before_cleanup: bool,
+
+    /// Chat key that steps one tick at a breakpoint ("step" always works too)
+    #[arg(long, value_name = "KEY", default_value = "s")]
+    step_key: String,
+
+    /// Chat key that continues from a breakpoint ("continue" always works too)
+    #[arg(long, value_name = "KEY", default_value = "c")]
+    continue_key: String,
+
+    /// Seconds to wait for the connection and for the bot to enter the game
+    #[arg(long, value_name = "SECS", default_value_t = bot::DEFAULT_CONNECT_TIMEOUT_SECS)]
+    connect_timeout: u64,
+
+    /// Stream run events as JSON lines to tcp://host:port or unix:///path
+    #[arg(long, value_name = "ADDRESS")]
+    event_sink: Option<String>,
+
+    /// Set a gamerule for the run, restored afterwards (e.g. randomTickSpeed=0). Repeatable
+    #[arg(long = "gamerule", value_name = "RULE=VALUE", value_parser = executor::parse_gamerule)]
+    gamerules: Vec<(String, String)>,
+
+    /// Command to send once before the first test, e.g. "save-off". Repeatable
+    #[arg(long = "before-all", value_name = "COMMAND")]
+    before_all: Vec<String>,
+
+    /// Command to send once after the run, even when it fails or is aborted. Repeatable
+    #[arg(long = "after-all", value_name = "COMMAND")]
+    after_all: Vec<String>,
+
     /// Filter tests by tags (can be specified multiple times)
     #[arg(short = 't', long = "tag")]
     tags: Vec<String>,
@@ -86,11 +387,33 @@ struct Args {
     #[arg(short = 'i', long)]
     interactive: bool,
 
-    /// Delay in milliseconds between each action (default: 100)
-    #[arg(short = 'd', long = "action-delay", default_value = "100")]
+    /// Stay connected and re-run tests whenever their files change
+    #[arg(long, conflicts_with_all = ["interactive", "list", "dry_run", "only_changed"])]
+    watch: bool,
+
+    /// Extra pause in milliseconds after each place, fill and remove, on top of the
+    /// --max-commands-per-sec throttle (default: 0, none)
+    #[arg(short = 'd', long = "action-delay", default_value = "0")]
     action_delay: u64,
 
-    /// Verbose output: show all per-action details during test execution
+    /// Most commands to send the server per second, so dense ticks don't trip spam kicks (0: no cap)
+    #[arg(long, default_value_t = bot::DEFAULT_MAX_COMMANDS_PER_SEC)]
+    max_commands_per_sec: u32,
+
+    /// Reconnect and run the interrupted chunk again, up to this many times, after a spam kick
+    #[arg(long, default_value_t = 0)]
+    retries: u32,
+
+    /// How long an assertion keeps re-reading a block before it fails, in milliseconds
+    /// (default: 10 reads 50 ms apart)
+    #[arg(long, value_name = "MS")]
+    assert_timeout: Option<u64>,
+
+    /// Read back every placed, filled and removed block and resend a command that didn't land
+    #[arg(long)]
+    verify_writes: bool,
+
+    /// Verbose output: log per-action details during test execution (sets the log level to debug)
     #[arg(short, long)]
     verbose: bool,
 
@@ -98,10 +421,62 @@ struct Args {
     #[arg(short, long)]
     quiet: bool,
 
+    /// What the progress bar tracks: tick position or completed tests
+    #[arg(long, value_enum, default_value_t = executor::ProgressMode::Ticks)]
+    progress: executor::ProgressMode,
+
+    /// What happens to each test's cleanup region: filled with air, left alone, or saved and restored
+    #[arg(long, value_enum, default_value_t = executor::CleanupMode::Air)]
+    cleanup_mode: executor::CleanupMode,
+
+    /// How test chunks are kept loaded: forceload or teleporting the bot, whichever works (auto)
+    #[arg(long, value_enum, value_name = "MODE", default_value_t = executor::ChunkLoading::Auto)]
+    keep_chunks_loaded: executor::ChunkLoading,
+
+    /// Merge nearby cleanup regions into fewer fill commands (with --cleanup-mode air)
+    #[arg(long)]
+    fill_cleanup_batch: bool,
+
+    /// Write the blocks of each failing test's area to DIR at its first failure, before cleanup
+    #[arg(long, value_name = "DIR")]
+    dump_on_failure: Option<PathBuf>,
+
+    /// Write every command, assertion outcome and tick advance to PATH as JSON lines, per tick
+    #[arg(long, value_name = "PATH")]
+    trace_log: Option<PathBuf>,
+
+    /// Place at most N tests on the grid at once, running larger sets in sequential batches
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    max_parallel: Option<u64>,
+
+    /// Fail the run if the server's ms per tick during sprints exceeds this, even if all tests pass
+    #[arg(long, value_name = "MS")]
+    max_mspt: Option<f64>,
+
+    /// Which ms-per-tick figure --max-mspt checks
+    #[arg(long, value_enum, default_value_t = executor::MsptStat::Avg, requires = "max_mspt")]
+    mspt_stat: executor::MsptStat,
+
+    /// Print how long setup, actions, assertions, tick advances, cleanup and pauses took
+    #[arg(long)]
+    profile: bool,
+
     /// Stop after the first test failure
     #[arg(long)]
     fail_fast: bool,
 
+    /// Stop a failing test's remaining actions but let other tests run to completion
+    #[arg(long, conflicts_with = "fail_fast")]
+    fail_fast_per_test: bool,
+
+    /// Stop scheduling tests once N of them have failed; the rest are reported as not run
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..), conflicts_with = "fail_fast")]
+    bail_after: Option<u64>,
+
+    /// Run tests one at a time at the same offset instead of merging them on the grid
+    #[arg(long, conflicts_with = "max_parallel")]
+    sequential: bool,
+
     /// List discovered tests and exit
     #[arg(long)]
     list: bool,
@@ -110,26 +485,241 @@ struct Args {
     #[arg(long)]
     dry_run: bool,
 
+    /// Flag tests whose timeline runs longer than this many ticks (dry run, validate and the report)
+    #[arg(long, value_name = "TICKS")]
+    warn_long_test: Option<u32>,
+
+    /// Lint test files without connecting to the server; exits non-zero on any error
+    #[arg(long, conflicts_with_all = ["interactive", "watch", "list", "dry_run"])]
+    validate: bool,
+
+    /// Shuffle test placement with this seed instead of sorting by name (reproducible)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Run only the M-th of N equal shards of the selected tests (e.g. 2/5)
+    #[arg(long, value_name = "M/N", value_parser = order::parse_shard, conflicts_with_all = ["interactive", "watch"])]
+    shard: Option<order::Shard>,
+
+    /// Only run tests whose files differ from this git ref, plus the tests depending on them
+    #[arg(long, value_name = "GIT_REF", conflicts_with_all = ["interactive", "watch"])]
+    changed_since: Option<String>,
+
+    /// Treat blocks a test touches outside its cleanup region as errors, not warnings
+    #[arg(long)]
+    strict: bool,
+
+    /// Write the merged timeline as a .mcfunction (with scheduled per-tick functions) and exit
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["interactive", "watch", "list", "dry_run"])]
+    export_mcfunction: Option<PathBuf>,
+
+    /// World offset added to every test's grid position in the export
+    #[arg(
+        long,
+        value_name = "X,Y,Z",
+        value_delimiter = ',',
+        num_args = 3,
+        allow_negative_numbers = true,
+        requires = "export_mcfunction"
+    )]
+    export_offset: Vec<i32>,
+
+    /// Only run tests whose files changed since the last run, reusing cached results for the rest
+    #[arg(long)]
+    only_changed: bool,
+
+    /// Ignore and don't update the .flint-cache result cache
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Record snapshots for tests with a snapshot_region that don't have one yet
+    #[arg(long, conflicts_with = "update_snapshots")]
+    snapshot: bool,
+
+    /// Re-record every snapshot from this run instead of comparing
+    #[arg(long)]
+    update_snapshots: bool,
+
     /// Output format for test results
     #[arg(long, value_enum, default_value_t = OutputFormat::Pretty)]
     format: OutputFormat,
 
+    /// How the pretty output summarizes the run
+    #[arg(long, value_enum, default_value_t = SummaryFormat::Full)]
+    summary_format: SummaryFormat,
+
+    /// Write machine-readable results to this file instead of stdout
+    #[arg(long, value_name = "PATH")]
+    output_file: Option<PathBuf>,
+
+    /// Also write a JUnit XML report to PATH (creating parent directories), whatever --format is
+    #[arg(long, value_name = "PATH")]
+    junit: Option<PathBuf>,
+
+    /// When to color output: auto (terminals only), always or never
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Format of diagnostic logs on stderr
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// File format for tests saved by the interactive recorder
+    #[arg(long, value_enum, default_value_t = executor::RecordFormat::Json)]
+    record_format: executor::RecordFormat,
+
+    /// Replay each recorded test once right after `!save` and report the result in chat
+    #[arg(long)]
+    verify_recording: bool,
+
+    /// Test directory used when no PATH is given, and where the recorder saves tests
+    #[arg(long, value_name = "PATH", default_value = executor::DEFAULT_TESTS_DIR)]
+    tests_dir: PathBuf,
+
     /// Generate shell completions and exit
     #[arg(long, value_enum)]
     completions: Option<Shell>,
+
+    /// Print a JSON Schema for test files and exit
+    #[arg(long)]
+    emit_schema: bool,
+
+    /// Write a JSON manifest of the discovered tests and their metadata to PATH and exit
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["interactive", "watch", "list", "dry_run"])]
+    emit_manifest: Option<PathBuf>,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    // Setup logging
-    tracing_subscriber::fmt()
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            // Failed tests were already reported by the summary
+            if !matches!(
+                e.downcast_ref(),
+                Some(RunError::TestsFailed(_) | RunError::Interrupted)
+            ) {
+                eprintln!("{} {:#}", "Error:".red().bold(), e);
+            }
+            ExitCode::from(exit::exit_code_for(&e))
+        }
+    }
+}
+
+/// Set up the tracing subscriber; `RUST_LOG` overrides the default filter
+/// `--verbose` turns on flintmc's debug events (per-action and lifecycle logs).
+/// JSON logs include the lifecycle events by default, for log pipelines.
+fn init_logging(format: LogFormat, verbose: bool, ansi: bool) {
+    let default_filter = if verbose || matches!(format, LogFormat::Json) {
+        "info,flintmc=debug"
+    } else {
+        "info"
+    };
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(default_filter));
+    let subscriber = tracing_subscriber::fmt()
         .with_writer(std::io::stderr)
-        .with_env_filter(
-            EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info")),
-        )
-        .init();
+        .with_ansi(ansi)
+        .with_env_filter(filter);
+    match format {
+        LogFormat::Text => subscriber.init(),
+        LogFormat::Json => subscriber.json().init(),
+    }
+}
+
+/// `--validate`: load and lint every file, print a report, fail if any file has errors
+fn validate_files(tests: &[LoadedTest], strict: bool, warn_long_test: Option<u32>) -> Result<()> {
+    let mut errors = 0;
+    let mut warnings = 0;
+    let mut failed_files = 0;
+    // Test name -> first file declaring it
+    let mut names: HashMap<String, &Path> = HashMap::new();
+
+    for loaded in tests {
+        let test_file = loaded.path.as_path();
+        let issues = match &loaded.loaded {
+            Ok((test, options)) => {
+                let mut issues = validate::lint(test, options, strict);
+                let span = executor::tick_span(test, options);
+                if let Some(threshold) = warn_long_test.filter(|threshold| span > *threshold) {
+                    issues.push(validate::Issue {
+                        severity: validate::Severity::Warning,
+                        message: format!(
+                            "timeline runs {} ticks, over --warn-long-test {}",
+                            format_number(span as usize),
+                            format_number(threshold as usize)
+                        ),
+                    });
+                }
+                if let Some(first) = names.get(&test.name) {
+                    issues.push(validate::Issue {
+                        severity: validate::Severity::Error,
+                        message: format!(
+                            "test name '{}' is already used by {}",
+                            test.name,
+                            first.display()
+                        ),
+                    });
+                } else {
+                    names.insert(test.name.clone(), test_file);
+                }
+                issues
+            }
+            Err(e) => vec![validate::Issue {
+                severity: validate::Severity::Error,
+                message: format!("{:#}", e),
+            }],
+        };
+
+        let file_errors = issues
+            .iter()
+            .filter(|issue| issue.severity == validate::Severity::Error)
+            .count();
+        errors += file_errors;
+        warnings += issues.len() - file_errors;
+        if file_errors > 0 {
+            failed_files += 1;
+            println!("{} {}", "✗".red().bold(), test_file.display());
+        } else {
+            println!("{} {}", "✓".green(), test_file.display());
+        }
+        for issue in &issues {
+            match issue.severity {
+                validate::Severity::Error => {
+                    println!("    {} {}", "error:".red().bold(), issue.message)
+                }
+                validate::Severity::Warning => {
+                    println!("    {} {}", "warning:".yellow().bold(), issue.message)
+                }
+            }
+        }
+    }
+
+    println!(
+        "\n{} files checked: {} error(s), {} warning(s)",
+        format_number(tests.len()),
+        errors,
+        warnings
+    );
+    if failed_files > 0 {
+        return Err(RunError::Load(anyhow::anyhow!(
+            "{} test file(s) failed validation",
+            failed_files
+        ))
+        .into());
+    }
+    Ok(())
+}
 
+async fn run() -> Result<()> {
     let args = Args::parse();
+    let colored_output = apply_color_choice(args.color);
+    // Interactive mode always shows per-action logs
+    init_logging(
+        args.log_format,
+        args.verbose || args.interactive,
+        colored_output,
+    );
 
     if let Some(shell) = args.completions {
         clap_complete::generate(
@@ -141,6 +731,11 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    if args.emit_schema {
+        println!("{}", serde_json::to_string_pretty(&schema::test_schema())?);
+        return Ok(());
+    }
+
     let verbose = args.verbose;
 
     if verbose {
@@ -149,66 +744,126 @@ async fn main() -> Result<()> {
     }
 
     let mut test_loader = if let Some(ref path) = args.path {
-        if verbose {
-            println!("{} Loading tests from {}...", "→".blue(), path.display());
-        }
-        TestLoader::new(path, args.recursive).with_context(|| {
-            format!(
-                "Failed to initialize test loader for path: {}",
-                path.display()
-            )
-        })?
+        tracing::debug!(path = %path.display(), "Loading tests");
+        // The manifest lists every test below the path, subdirectories included
+        TestLoader::new(path, args.recursive || args.emit_manifest.is_some())
+            .with_context(|| {
+                format!(
+                    "Failed to initialize test loader for path: {}",
+                    path.display()
+                )
+            })
+            .map_err(RunError::Load)?
     } else {
-        let default_path = Path::new("FlintBenchmark/tests");
-        TestLoader::new(default_path, true).with_context(|| {
-            format!(
-                "Failed to initialize test loader for default path: {}",
-                default_path.display()
-            )
-        })?
+        let default_path = args.tests_dir.as_path();
+        // A fresh project can start interactive mode and record its first test
+        if args.interactive && !default_path.exists() {
+            std::fs::create_dir_all(default_path).with_context(|| {
+                format!(
+                    "Failed to create tests directory {}",
+                    default_path.display()
+                )
+            })?;
+        }
+        TestLoader::new(default_path, true)
+            .with_context(|| {
+                format!(
+                    "Failed to initialize test loader for default path: {}",
+                    default_path.display()
+                )
+            })
+            .map_err(RunError::Load)?
     };
 
     // Collect test files - use tags if provided, otherwise collect all
     let test_files = if !args.tags.is_empty() {
-        if verbose {
-            println!("{} Filtering by tags: {:?}", "→".blue(), args.tags);
-        }
+        tracing::debug!(tags = ?args.tags, "Filtering by tags");
         test_loader
             .collect_by_tags(&args.tags)
-            .with_context(|| format!("Failed to collect tests by tags: {:?}", args.tags))?
+            .with_context(|| format!("Failed to collect tests by tags: {:?}", args.tags))
+            .map_err(RunError::Load)?
     } else {
         test_loader
             .collect_all_test_files()
-            .context("Failed to collect test files")?
+            .context("Failed to collect test files")
+            .map_err(RunError::Load)?
     };
 
-    // In interactive mode, we don't require tests to be found initially
-    if test_files.is_empty() && !args.interactive {
+    // Load every test once; selection, ordering and each mode below reuse the loaded specs
+    let tests: Vec<LoadedTest> = test_files.into_iter().map(LoadedTest::load).collect();
+
+    let tests = match args.changed_since.as_deref() {
+        Some(git_ref) => {
+            match changed::changed_since(git_ref).map_err(RunError::Load)? {
+                Some(changed) => {
+                    let total = tests.len();
+                    let selected = changed::select_changed(tests, &changed);
+                    eprintln!(
+                        "Changed since {}: {} of {} tests",
+                        git_ref,
+                        format_number(selected.len()),
+                        format_number(total)
+                    );
+                    // Nothing changed: nothing to run is not a failure
+                    if selected.is_empty() && total > 0 {
+                        return Ok(());
+                    }
+                    selected
+                }
+                None => {
+                    tracing::warn!(
+                        "Not in a git repository, running every test instead of changes since {}",
+                        git_ref
+                    );
+                    tests
+                }
+            }
+        }
+        None => tests,
+    };
+
+    // Grid positions follow test names, so file discovery order can't move tests around
+    let mut tests = order::order_tests(tests, args.seed);
+    if let Some(shard) = args.shard {
+        let total = tests.len();
+        tests = shard.select(tests);
+        eprintln!(
+            "Shard {}/{}: {} of {} tests",
+            shard.index,
+            shard.count,
+            format_number(tests.len()),
+            format_number(total)
+        );
+        // More shards than tests: nothing to do here is not a failure
+        if tests.is_empty() && total > 0 {
+            return Ok(());
+        }
+    }
+
+    // In interactive and watch mode, we don't require tests to be found initially
+    if tests.is_empty() && !args.interactive && !args.watch {
         let location = if !args.tags.is_empty() {
             format!("with tags: {:?}", args.tags)
         } else if let Some(ref path) = args.path {
             format!("at: {}", path.display())
         } else {
-            "at default path: FlintBenchmark/tests".to_string()
+            format!("at default path: {}", args.tests_dir.display())
         };
-        eprintln!("{} No test files found {}", "Error:".red().bold(), location);
-        std::process::exit(1);
+        return Err(RunError::Load(anyhow::anyhow!("No test files found {}", location)).into());
     }
 
-    if verbose && !args.interactive {
-        println!("Found {} test file(s)\n", test_files.len());
-    }
+    tracing::debug!(count = tests.len(), "Found test files");
 
     // --list: print test names and exit
     if args.list {
-        for test_file in &test_files {
-            match TestSpec::from_file(test_file) {
-                Ok(test) => println!("{}", test.name),
+        for loaded in &tests {
+            match &loaded.loaded {
+                Ok((test, _)) => println!("{}", test.name),
                 Err(e) => {
                     eprintln!(
                         "{} Failed to load test {}: {}",
                         "Error:".red().bold(),
-                        test_file.display(),
+                        loaded.path.display(),
                         e
                     );
                 }
@@ -217,21 +872,45 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    if let Some(path) = &args.emit_manifest {
+        let errors = manifest::write_manifest(path, &tests).map_err(RunError::Load)?;
+        eprintln!(
+            "Wrote manifest of {} tests to {}",
+            format_number(tests.len()),
+            path.display()
+        );
+        if errors > 0 {
+            tracing::warn!(
+                "{} test files failed to load, see their error entries",
+                errors
+            );
+        }
+        return Ok(());
+    }
+
     // --dry-run: show execution plan and exit
     if args.dry_run {
-        let chunks: Vec<_> = test_files.chunks(CHUNK_SIZE).collect();
+        let chunks: Vec<_> = tests.chunks(CHUNK_SIZE).collect();
         let n = chunks.len();
-        println!(
-            "{} tests, {} {} (up to {} tests per batch)",
-            format_number(test_files.len()),
-            n,
-            if n == 1 { "batch" } else { "batches" },
-            CHUNK_SIZE
-        );
+        if args.sequential {
+            println!(
+                "{} tests, run one at a time at the same offset",
+                format_number(tests.len())
+            );
+        } else {
+            println!(
+                "{} tests, {} {} (up to {} tests per batch)",
+                format_number(tests.len()),
+                n,
+                if n == 1 { "batch" } else { "batches" },
+                CHUNK_SIZE
+            );
+        }
         println!();
 
+        let mut long_tests = 0;
         for (chunk_idx, chunk) in chunks.iter().enumerate() {
-            if chunks.len() > 1 {
+            if chunks.len() > 1 && !args.sequential {
                 println!(
                     "Batch {}/{} ({} tests)",
                     chunk_idx + 1,
@@ -239,11 +918,11 @@ async fn main() -> Result<()> {
                     chunk.len()
                 );
             }
-            for (test_index, test_file) in chunk.iter().enumerate() {
-                match TestSpec::from_file(test_file) {
-                    Ok(test) => {
-                        let offset = calculate_test_offset_default(test_index, chunk.len());
-                        let max_tick = test.max_tick();
+            for (test_index, loaded) in chunk.iter().enumerate() {
+                match &loaded.loaded {
+                    Ok((test, options)) => {
+                        let offset = test_offset(&args, test_index, chunk.len());
+                        let max_tick = executor::tick_span(test, options);
                         let assertions = test
                             .timeline
                             .iter()
@@ -254,56 +933,179 @@ async fn main() -> Result<()> {
                         } else {
                             format!(" [{}]", test.tags.join(", "))
                         };
+                        let long = if args
+                            .warn_long_test
+                            .is_some_and(|threshold| max_tick > threshold)
+                        {
+                            long_tests += 1;
+                            format!(" {}", "long".yellow().bold())
+                        } else {
+                            String::new()
+                        };
                         println!(
-                            "  {} ({}t, {}a, offset [{},{},{}]){}",
+                            "  {} ({}t, {}a, offset [{},{},{}]){}{}",
                             test.name,
                             max_tick,
                             assertions,
                             offset[0],
                             offset[1],
                             offset[2],
-                            tags.dimmed()
+                            tags.dimmed(),
+                            long
                         );
                     }
                     Err(e) => {
                         eprintln!(
                             "{} Failed to load test {}: {}",
                             "Error:".red().bold(),
-                            test_file.display(),
+                            loaded.path.display(),
                             e
                         );
                     }
                 }
             }
         }
+        if let Some(threshold) = args.warn_long_test
+            && long_tests > 0
+        {
+            println!(
+                "\n{} {} test(s) run longer than {} ticks",
+                "Warning:".yellow().bold(),
+                long_tests,
+                format_number(threshold as usize)
+            );
+        }
         return Ok(());
     }
 
-    // Require --server for execution modes
-    let server = args.server.as_deref().unwrap_or_else(|| {
-        eprintln!(
-            "{} --server is required when running tests",
-            "Error:".red().bold()
+    if args.validate {
+        return validate_files(&tests, args.strict, args.warn_long_test);
+    }
+
+    if let Some(path) = &args.export_mcfunction {
+        if tests.len() > CHUNK_SIZE {
+            return Err(RunError::Usage(format!(
+                "--export-mcfunction exports one grid of up to {} tests, {} selected (narrow with a path or --tag)",
+                CHUNK_SIZE,
+                tests.len()
+            ))
+            .into());
+        }
+        let count = tests.len();
+        let mut tests_with_offsets = Vec::new();
+        let mut test_options = Vec::new();
+        for (test_index, loaded) in tests.into_iter().enumerate() {
+            let (test, options) = loaded.loaded.map_err(RunError::Load)?;
+            let offset = calculate_test_offset_default(test_index, count);
+            tests_with_offsets.push((test, offset));
+            test_options.push(options);
+        }
+        let extra_offset = match args.export_offset.as_slice() {
+            [x, y, z] => [*x, *y, *z],
+            _ => [0, 0, 0],
+        };
+        let written =
+            executor::export_mcfunction(&tests_with_offsets, &test_options, extra_offset, path)?;
+        println!(
+            "{} Exported {} tests to {} ({} files)",
+            "✓".green(),
+            tests_with_offsets.len(),
+            path.display(),
+            written.len()
         );
-        std::process::exit(1);
-    });
+        return Ok(());
+    }
+
+    // Require --server for execution modes
+    let Some(server) = args.server.as_deref() else {
+        return Err(RunError::Usage("--server is required when running tests".to_string()).into());
+    };
 
     // Connect to server
     let mut executor = executor::TestExecutor::new();
 
     // Set action delay
     executor.set_action_delay(args.action_delay);
+    executor.set_max_commands_per_sec(args.max_commands_per_sec);
+    executor.set_verify_writes(args.verify_writes);
+    executor.set_assert_timeout(args.assert_timeout.map(Duration::from_millis));
     executor.set_verbose(args.verbose);
-    executor.set_quiet(args.quiet || !matches!(args.format, OutputFormat::Pretty));
+    executor.set_quiet(
+        args.quiet
+            || !matches!(args.format, OutputFormat::Pretty)
+            || args.summary_format == SummaryFormat::Oneline,
+    );
     executor.set_fail_fast(args.fail_fast);
-
-    if verbose && args.action_delay != 100 {
-        println!(
-            "{} Action delay set to {} ms",
-            "→".yellow(),
-            args.action_delay
+    executor.set_fail_fast_per_test(args.fail_fast_per_test);
+    executor.set_break_on_failure(args.break_on_failure);
+    executor.set_break_before_cleanup(args.break_before_cleanup);
+    // --sequential runs batches of one, keeping dependency order and skipping
+    executor.set_max_parallel(
+        args.max_parallel
+            .map(|n| n as usize)
+            .or(args.sequential.then_some(1)),
+    );
+    executor.set_progress_mode(args.progress);
+    executor.set_ascii_progress(!colored_output);
+    executor.set_strict(args.strict);
+    executor.set_seed(args.seed);
+    executor.set_cleanup_mode(args.cleanup_mode);
+    executor.set_chunk_loading(args.keep_chunks_loaded);
+    executor.set_fill_cleanup_batch(args.fill_cleanup_batch);
+    executor.set_dump_dir(args.dump_on_failure.clone());
+    if let Some(path) = &args.trace_log {
+        executor.set_trace_log(path)?;
+    }
+    executor.set_snapshot_mode(if args.update_snapshots {
+        snapshot::SnapshotMode::Update
+    } else if args.snapshot {
+        snapshot::SnapshotMode::Record
+    } else {
+        snapshot::SnapshotMode::Compare
+    });
+    if args.step_key.eq_ignore_ascii_case(&args.continue_key) {
+        return Err(
+            RunError::Usage("--step-key and --continue-key must differ".to_string()).into(),
+        );
+    }
+    executor.set_step_keys(executor::StepKeys {
+        step: args.step_key.clone(),
+        continue_key: args.continue_key.clone(),
+    });
+    executor.set_record_format(args.record_format);
+    executor.set_verify_recordings(args.verify_recording);
+    executor.set_tests_dir(args.tests_dir.clone());
+    if matches!(args.format, OutputFormat::Ndjson) {
+        let mut out = open_output(args.output_file.as_deref())?;
+        executor.add_event_handler(Box::new(move |event: &executor::RunEvent| {
+            // Tick events are for live tools on --event-sink, not the result stream
+            if matches!(event, executor::RunEvent::Tick { .. }) {
+                return;
+            }
+            if let Err(e) = format::write_ndjson_event(&mut *out, event) {
+                tracing::warn!("Failed to write event: {}", e);
+            }
+        }));
+    } else if matches!(args.format, OutputFormat::Pretty) && args.output_file.is_some() {
+        eprintln!(
+            "{} --output-file is ignored for pretty output",
+            "Warning:".yellow().bold()
         );
     }
+    if let Some(address) = args.event_sink.as_deref() {
+        match executor::sink_handler(address) {
+            Ok(handler) => executor.add_event_handler(handler),
+            Err(e) => eprintln!(
+                "{} {:#}, continuing without event sink",
+                "Warning:".yellow().bold(),
+                e
+            ),
+        }
+    }
+
+    if args.action_delay != 0 {
+        tracing::debug!(ms = args.action_delay, "Action delay set");
+    }
 
     // Interactive mode: enter command loop
     if args.interactive {
@@ -312,30 +1114,100 @@ async fn main() -> Result<()> {
             "→".yellow().bold()
         );
         println!("  Commands: !search, !run, !run-all, !run-tags, !list, !reload, !help, !stop");
-        println!("  During tests: type 's' to step, 'c' to continue\n");
+        println!(
+            "  During tests: type '{}' to step, '{}' to continue\n",
+            args.step_key, args.continue_key
+        );
 
         println!("{} Connecting to {}...", "→".blue(), server);
-        executor.connect(server).await?;
+        executor
+            .connect(server, Duration::from_secs(args.connect_timeout))
+            .await
+            .map_err(RunError::from_connect)?;
         println!("{} Connected successfully\n", "✓".green());
+        install_interrupt_handler(&executor);
+        start_suite(&mut executor, &args).await?;
 
-        executor.interactive_mode(&mut test_loader).await?;
-        return Ok(());
+        let result = executor.interactive_mode(&mut test_loader).await;
+        finish_suite(&mut executor).await?;
+        if result.is_ok() && executor.is_interrupted() {
+            return Err(RunError::Interrupted.into());
+        }
+        return result;
     }
 
-    if verbose {
+    // Watch mode: re-run tests as their files are saved
+    if args.watch {
         println!("{} Connecting to {}...", "→".blue(), server);
-    }
-    executor.connect(server).await?;
-    if verbose {
+        executor
+            .connect(server, Duration::from_secs(args.connect_timeout))
+            .await
+            .map_err(RunError::from_connect)?;
         println!("{} Connected successfully\n", "✓".green());
+        install_interrupt_handler(&executor);
+        start_suite(&mut executor, &args).await?;
+
+        let result = executor.watch_mode(&mut test_loader, &args.tags).await;
+        finish_suite(&mut executor).await?;
+        return result;
     }
 
-    // Load all tests and run in chunks
-    let total_tests = test_files.len();
-    let chunks: Vec<_> = test_files.chunks(CHUNK_SIZE).collect();
+    // --only-changed: reuse cached results for tests whose files haven't changed
+    let cache_path = cache::cache_path(args.path.as_deref().unwrap_or(&args.tests_dir));
+    let mut manifest = (!args.no_cache).then(|| cache::Manifest::load(&cache_path));
+    let (tests, cached_results) = match manifest.as_ref() {
+        Some(manifest) if args.only_changed => manifest.split_changed(tests),
+        _ => (tests, Vec::new()),
+    };
+    if !cached_results.is_empty() {
+        eprintln!(
+            "Reusing {} cached results for unchanged tests",
+            format_number(cached_results.len())
+        );
+    }
+
+    // A test that doesn't load fails the run before anything is placed
+    let mut runnable = Vec::with_capacity(tests.len());
+    for loaded in tests {
+        match loaded.loaded {
+            Ok((test, options)) => runnable.push((loaded.path, test, options)),
+            Err(e) => {
+                let e = e.context(format!("Failed to load test {}", loaded.path.display()));
+                return Err(RunError::Load(e).into());
+            }
+        }
+    }
+
+    // Tests are already ordered with prerequisites first; a cycle stops the run before it starts
+    {
+        let names_and_deps: Vec<(&str, &[String])> = runnable
+            .iter()
+            .map(|(_, test, _)| (test.name.as_str(), test.dependencies.as_slice()))
+            .collect();
+        executor::dependency_order(&names_and_deps).map_err(RunError::Load)?;
+    }
+
+    if !runnable.is_empty() {
+        executor
+            .connect(server, Duration::from_secs(args.connect_timeout))
+            .await
+            .map_err(RunError::from_connect)?;
+        install_interrupt_handler(&executor);
+        start_suite(&mut executor, &args).await?;
+    }
+
+    // Run the loaded tests in chunks
+    let total_tests = runnable.len();
+    let chunks: Vec<_> = runnable.chunks(CHUNK_SIZE).collect();
     let total_chunks = chunks.len();
 
-    if verbose {
+    if verbose && args.sequential {
+        println!(
+            "{} Running {} tests one at a time, each cleaned up before the next\n",
+            "→".blue().bold(),
+            total_tests
+        );
+    } else if verbose {
         println!(
             "{} Running {} tests in {} chunk(s) of up to {}",
             "→".blue().bold(),
@@ -352,61 +1224,115 @@ async fn main() -> Result<()> {
     }
 
     let start_time = Instant::now();
+    let started_at = std::time::SystemTime::now();
+    executor.emit(executor::RunEvent::RunStart { total: total_tests });
     let mut all_results = Vec::new();
     let mut all_failures: Vec<(String, AssertFailure)> = Vec::new();
+    let mut all_skipped: Vec<(String, String)> = Vec::new();
+    let mut expected_failures: HashSet<String> = HashSet::new();
+    // Test name -> file, to record results in the cache
+    let mut test_paths: HashMap<String, PathBuf> = HashMap::new();
+    let mut retries_left = args.retries;
+    // Aborted from a breakpoint: the run is incomplete, so it can't pass
+    let mut run_aborted = false;
 
     for (chunk_idx, chunk) in chunks.iter().enumerate() {
-        if verbose {
+        if verbose && !args.sequential {
             print_chunk_header(chunk_idx, total_chunks, chunk.len());
         }
+        // Failures in earlier chunks use up part of the --bail-after budget
+        if let Some(limit) = args.bail_after {
+            let failed = all_results.iter().filter(|r| !r.success).count();
+            executor.set_bail_after(Some((limit as usize).saturating_sub(failed)));
+        }
+        // Dependents of tests that did not pass in earlier chunks are skipped
+        executor.set_blocked_tests(
+            all_results
+                .iter()
+                .filter(|r| !r.success)
+                .map(|r| r.test_name.clone())
+                .chain(all_skipped.iter().map(|(name, _)| name.clone()))
+                .collect(),
+        );
 
         let mut tests_with_offsets = Vec::new();
-        for (test_index, test_file) in chunk.iter().enumerate() {
-            match TestSpec::from_file(test_file) {
-                Ok(test) => {
-                    // Calculate offset within this chunk (10x10 grid)
-                    let offset = calculate_test_offset_default(test_index, chunk.len());
-                    if verbose {
-                        println!(
-                            "  {} Grid position: {} (offset: [{}, {}, {}])",
-                            "→".blue(),
-                            format!("[{}/{}]", test_index + 1, chunk.len()).dimmed(),
-                            offset[0],
-                            offset[1],
-                            offset[2]
-                        );
-                    }
-                    tests_with_offsets.push((test, offset));
-                }
-                Err(e) => {
+        let mut test_options = Vec::new();
+        for (test_index, (test_file, test, options)) in chunk.iter().enumerate() {
+            // Calculate offset within this chunk (10x10 grid)
+            let offset = test_offset(&args, test_index, chunk.len());
+            tracing::debug!(
+                test = %test.name,
+                grid_index = test_index + 1,
+                offset = ?offset,
+                "Placed test on grid"
+            );
+            test_paths.insert(test.name.clone(), test_file.clone());
+            tests_with_offsets.push((test.clone(), offset));
+            test_options.push(options.clone());
+        }
+
+        // Run this chunk of tests in parallel using merged timeline
+        let output = loop {
+            match executor
+                .run_tests_parallel(&tests_with_offsets, &test_options, args.break_after_setup)
+                .await
+            {
+                Ok(output) => break output,
+                // Kicked for spam: the chunk cleans up its own areas, so it can start over
+                Err(e) if retries_left > 0 && bot::is_spam_kick_error(&e) => {
+                    retries_left -= 1;
                     eprintln!(
-                        "{} Failed to load test {}: {}",
-                        "Error:".red().bold(),
-                        test_file.display(),
-                        e
+                        "{} {:#}\n  Reconnecting in {}s to run chunk {}/{} again ({} retries left)",
+                        "Warning:".yellow().bold(),
+                        e,
+                        bot::RECONNECT_DELAY_SECS,
+                        chunk_idx + 1,
+                        total_chunks,
+                        retries_left
                     );
-                    std::process::exit(1);
+                    tokio::time::sleep(Duration::from_secs(bot::RECONNECT_DELAY_SECS)).await;
+                    executor
+                        .connect(server, Duration::from_secs(args.connect_timeout))
+                        .await
+                        .map_err(RunError::from_connect)?;
+                }
+                Err(e) => {
+                    finish_suite(&mut executor).await?;
+                    return Err(e);
                 }
             }
-        }
-
-        if verbose {
-            println!();
-        }
-
-        // Run this chunk of tests in parallel using merged timeline
-        let output = executor
-            .run_tests_parallel(&tests_with_offsets, args.break_after_setup)
-            .await?;
+        };
 
         all_results.extend(output.results);
         all_failures.extend(output.failures);
+        all_skipped.extend(output.skipped);
+        expected_failures.extend(output.expected_failures);
+
+        // Aborted from a breakpoint or out of --bail-after budget: later chunks never run
+        let bailed = args
+            .bail_after
+            .is_some_and(|limit| all_results.iter().filter(|r| !r.success).count() as u64 >= limit);
+        run_aborted |= output.aborted;
+        if output.aborted || bailed {
+            let reason = if output.aborted {
+                "run aborted before this test started"
+            } else {
+                "not run: --bail-after limit reached"
+            };
+            for (_, test, _) in chunks[chunk_idx + 1..]
+                .iter()
+                .flat_map(|chunk| chunk.iter())
+            {
+                all_skipped.push((test.name.clone(), reason.to_string()));
+            }
+            break;
+        }
 
         if args.fail_fast && !all_failures.is_empty() {
             break;
         }
 
-        if verbose && chunk_idx + 1 < total_chunks {
+        if verbose && !args.sequential && chunk_idx + 1 < total_chunks {
             println!(
                 "\n{} Chunk {}/{} complete ({} tests). Moving to next chunk...\n",
                 "✓".green().bold(),
@@ -418,22 +1344,202 @@ async fn main() -> Result<()> {
     }
 
     let elapsed = start_time.elapsed();
+    finish_suite(&mut executor).await?;
+
+    if let Some(manifest) = manifest.as_mut() {
+        for result in &all_results {
+            if let Some(path) = test_paths.get(&result.test_name) {
+                manifest.record(path, result);
+            }
+        }
+        if let Err(e) = manifest.save(&cache_path) {
+            tracing::warn!("{:#}", e);
+        }
+    }
+    all_results.extend(cached_results);
+
+    let perf = executor.perf_summary();
+    let gate = match (perf.as_ref(), args.max_mspt) {
+        (Some(perf), Some(threshold)) => {
+            Some(executor::PerfGate::check(perf, args.mspt_stat, threshold))
+        }
+        (None, Some(_)) => {
+            eprintln!(
+                "{} --max-mspt not checked: no sprint reported the server's tick time",
+                "Warning:".yellow().bold()
+            );
+            None
+        }
+        _ => None,
+    };
+    let profile = args.profile.then(|| executor.profile_summary());
+    let passed = all_results.iter().filter(|r| r.success).count();
+    executor.emit(executor::RunEvent::RunEnd {
+        total: all_results.len() + all_skipped.len(),
+        passed,
+        failed: all_results.len() - passed,
+        skipped: all_skipped.len(),
+        duration_secs: elapsed.as_secs_f64(),
+    });
 
     match args.format {
+        OutputFormat::Pretty if args.summary_format == SummaryFormat::Oneline => {
+            println!(
+                "{}",
+                format::oneline_summary(
+                    &all_results,
+                    &all_failures,
+                    &all_skipped,
+                    &expected_failures,
+                    elapsed
+                )
+            );
+        }
         OutputFormat::Pretty => {
             if verbose {
                 print_test_summary(&all_results, SEPARATOR_WIDTH);
             } else {
                 print_concise_summary(&all_results, elapsed);
             }
+            for (name, reason) in &all_skipped {
+                println!("{} {} ({})", "SKIP".yellow().bold(), name, reason.dimmed());
+            }
+            for result in &all_results {
+                match format::status_label(result, expected_failures.contains(&result.test_name)) {
+                    "XFAIL" => println!("{} {}", "XFAIL".yellow().bold(), result.test_name),
+                    "XPASS" => println!(
+                        "{} {} ({})",
+                        "XPASS".red().bold(),
+                        result.test_name,
+                        "expected to fail, but every assertion passed".dimmed()
+                    ),
+                    _ => {}
+                }
+            }
+            if let Some(perf) = &perf {
+                print_perf_summary(perf);
+            }
+            if let Some(gate) = &gate {
+                print_perf_gate(gate);
+            }
+            if let Some(profile) = &profile {
+                print_profile(&mut std::io::stdout(), profile)?;
+            }
+            if let Some(threshold) = args.warn_long_test {
+                print_long_tests(&mut std::io::stdout(), &all_results, threshold)?;
+            }
+        }
+        OutputFormat::Json
+        | OutputFormat::Tap
+        | OutputFormat::Junit
+        | OutputFormat::Github
+        | OutputFormat::Markdown => {
+            let mut out = open_output(args.output_file.as_deref())?;
+            let target = &mut *out;
+            match args.format {
+                OutputFormat::Json => format::print_json(
+                    target,
+                    &all_results,
+                    &all_failures,
+                    &all_skipped,
+                    &expected_failures,
+                    &format::Timing {
+                        performance: perf.as_ref(),
+                        gate: gate.as_ref(),
+                        profile: profile.as_ref(),
+                    },
+                    elapsed,
+                ),
+                OutputFormat::Tap => format::print_tap(
+                    target,
+                    &all_results,
+                    &all_failures,
+                    &all_skipped,
+                    &expected_failures,
+                ),
+                OutputFormat::Junit => format::print_junit(
+                    target,
+                    &all_results,
+                    &all_failures,
+                    &all_skipped,
+                    &expected_failures,
+                    elapsed,
+                    &format::SuiteInfo::new(started_at),
+                ),
+                OutputFormat::Github => format::print_github(
+                    target,
+                    &all_results,
+                    &all_failures,
+                    &all_skipped,
+                    &expected_failures,
+                    elapsed,
+                ),
+                _ => format::print_markdown(
+                    target,
+                    &all_results,
+                    &all_failures,
+                    &all_skipped,
+                    &expected_failures,
+                    elapsed,
+                ),
+            }
+            .and_then(|()| target.flush())
+            .context("Failed to write test results")?;
         }
-        OutputFormat::Json => format::print_json(&all_results, elapsed),
-        OutputFormat::Tap => format::print_tap(&all_results),
-        OutputFormat::Junit => format::print_junit(&all_results, elapsed),
+        // Events were streamed during the run, ending with run_end
+        OutputFormat::Ndjson => {}
+    }
+
+    // Formats with no place for the profile get it on stderr, keeping their output parseable
+    let profile_reported = match args.format {
+        OutputFormat::Json => true,
+        OutputFormat::Pretty => args.summary_format != SummaryFormat::Oneline,
+        _ => false,
+    };
+    if let Some(profile) = &profile
+        && !profile_reported
+    {
+        print_profile(&mut std::io::stderr(), profile)?;
+    }
+    if let Some(threshold) = args.warn_long_test
+        && !(matches!(args.format, OutputFormat::Pretty)
+            && args.summary_format != SummaryFormat::Oneline)
+    {
+        print_long_tests(&mut std::io::stderr(), &all_results, threshold)?;
+    }
+
+    if let Some(path) = &args.junit {
+        write_junit_file(
+            path,
+            &all_results,
+            &all_failures,
+            &all_skipped,
+            &expected_failures,
+            elapsed,
+            started_at,
+        )?;
+    }
+
+    if executor.is_interrupted() {
+        return Err(RunError::Interrupted.into());
     }
 
-    if all_results.iter().any(|r| !r.success) {
-        std::process::exit(1);
+    // Skipped tests never fail the run
+    let failed = all_results.iter().filter(|r| !r.success).count();
+    if failed > 0 {
+        return Err(RunError::TestsFailed(failed).into());
+    }
+    if run_aborted {
+        return Err(RunError::Aborted.into());
+    }
+
+    if let Some(gate) = gate.filter(|gate| !gate.passed()) {
+        return Err(RunError::TooSlow {
+            stat: gate.stat.name(),
+            measured: gate.measured,
+            threshold: gate.threshold,
+        }
+        .into());
     }
 
     Ok(())
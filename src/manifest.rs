@@ -0,0 +1,91 @@
+//! Test manifest (`--emit-manifest <path>`) - every discovered test and its metadata as JSON, for
+//! dashboards and test explorers that need to know what exists without running anything
+
+use crate::spec::LoadedTest;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// One discovered test file; a file that fails to load has only `name`, `path` and `error`
+#[derive(Debug, Serialize)]
+pub struct ManifestEntry {
+    pub name: String,
+    pub path: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    /// Last tick of the timeline, like a result's `total_ticks`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tick_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dependencies: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ManifestEntry {
+    /// A loaded test file's entry, recording a load error instead of failing
+    pub fn new(test: &LoadedTest) -> Self {
+        let path = &test.path;
+        match &test.loaded {
+            Ok((test, _)) => Self {
+                tick_count: Some(test.max_tick()),
+                name: test.name.clone(),
+                path: path.clone(),
+                tags: Some(test.tags.clone()),
+                dependencies: Some(test.dependencies.clone()),
+                description: test.description.clone(),
+                error: None,
+            },
+            Err(e) => Self {
+                // The file name stands in for a name the file never got to declare
+                name: path
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                path: path.clone(),
+                tags: None,
+                tick_count: None,
+                dependencies: None,
+                description: None,
+                error: Some(format!("{:#}", e)),
+            },
+        }
+    }
+}
+
+/// Write the manifest of `tests` to `out`, returning the entries that failed to load
+pub fn write_manifest(out: &Path, tests: &[LoadedTest]) -> Result<usize> {
+    let entries: Vec<ManifestEntry> = tests.iter().map(ManifestEntry::new).collect();
+    let errors = entries.iter().filter(|entry| entry.error.is_some()).count();
+    if let Some(parent) = out.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory {}", parent.display()))?;
+    }
+    let json = serde_json::to_string_pretty(&entries)?;
+    std::fs::write(out, json + "\n")
+        .with_context(|| format!("Failed to write manifest {}", out.display()))?;
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_error_becomes_entry() {
+        let dir = std::env::temp_dir().join(format!("flint-manifest-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let broken = dir.join("broken.json");
+        std::fs::write(&broken, "{ \"name\": ").unwrap();
+
+        let entry = ManifestEntry::new(&LoadedTest::load(broken));
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(entry.name, "broken");
+        assert!(entry.error.is_some());
+        let json = serde_json::to_value(&entry).unwrap();
+        assert!(json.get("tags").is_none());
+        assert!(json.get("tick_count").is_none());
+    }
+}
@@ -0,0 +1,197 @@
+//! Test ordering - grid positions follow test names, not file discovery order,
+//! optionally shuffled by a seed for randomized-but-reproducible placement,
+//! and split into shards for distributing a suite across machines
+
+use crate::spec::LoadedTest;
+use std::path::PathBuf;
+
+/// Sort key: the lowercased test name, or the path for files that don't load
+fn sort_key(test: &LoadedTest) -> (String, PathBuf) {
+    (test.name().trim().to_lowercase(), test.path.clone())
+}
+
+/// Deterministic 64-bit generator (SplitMix64), so a seed shuffles the same way everywhere
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Fisher-Yates shuffle driven by `seed`
+fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut rng = SplitMix64(seed);
+    for i in (1..items.len()).rev() {
+        let j = (rng.next() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// Move each test after its dependencies, so chunks of a large run judge prerequisites first
+/// A dependency cycle leaves the order as is, for the run to report.
+fn dependencies_first(tests: Vec<LoadedTest>) -> Vec<LoadedTest> {
+    let order = {
+        let names_and_deps: Vec<(&str, &[String])> = tests
+            .iter()
+            .map(|test| match &test.loaded {
+                Ok((test, _)) => (test.name.as_str(), test.dependencies.as_slice()),
+                Err(_) => ("", &[][..]),
+            })
+            .collect();
+        match crate::executor::dependency_order(&names_and_deps) {
+            Ok(order) => order,
+            Err(_) => return tests,
+        }
+    };
+    let mut slots: Vec<Option<LoadedTest>> = tests.into_iter().map(Some).collect();
+    order
+        .into_iter()
+        .filter_map(|idx| slots[idx].take())
+        .collect()
+}
+
+/// Order loaded tests by test name, shuffle them if a seed is given, then put dependencies first
+pub fn order_tests(mut tests: Vec<LoadedTest>, seed: Option<u64>) -> Vec<LoadedTest> {
+    tests.sort_by_cached_key(sort_key);
+    if let Some(seed) = seed {
+        shuffle(&mut tests, seed);
+    }
+    dependencies_first(tests)
+}
+
+/// Load each test file once and order the results
+pub fn load_ordered(files: Vec<PathBuf>, seed: Option<u64>) -> Vec<LoadedTest> {
+    order_tests(files.into_iter().map(LoadedTest::load).collect(), seed)
+}
+
+/// One of `count` equal parts of the ordered test list (`--shard M/N`, 1-based)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    pub index: usize,
+    pub count: usize,
+}
+
+/// Parse `M/N` with 1 <= M <= N
+pub fn parse_shard(arg: &str) -> Result<Shard, String> {
+    let (index, count) = arg
+        .split_once('/')
+        .ok_or_else(|| format!("expected M/N, got '{}'", arg))?;
+    let index: usize = index
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid shard number '{}'", index))?;
+    let count: usize = count
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid shard count '{}'", count))?;
+    if count == 0 {
+        return Err("shard count must be at least 1".to_string());
+    }
+    if index == 0 || index > count {
+        return Err(format!("shard number must be between 1 and {}", count));
+    }
+    Ok(Shard { index, count })
+}
+
+impl Shard {
+    /// This shard's contiguous slice: sizes differ by at most one, and shards are disjoint
+    pub fn select<T>(self, items: Vec<T>) -> Vec<T> {
+        let len = items.len();
+        let start = (self.index - 1) * len / self.count;
+        let end = self.index * len / self.count;
+        items.into_iter().skip(start).take(end - start).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Load and order `files`, returning the ordered test names
+    fn ordered_names(files: &[PathBuf], seed: Option<u64>) -> Vec<String> {
+        let tests = files.iter().cloned().map(LoadedTest::load).collect();
+        order_tests(tests, seed)
+            .iter()
+            .map(LoadedTest::name)
+            .collect()
+    }
+
+    #[test]
+    fn test_order_ignores_discovery_order() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("example_tests");
+        let files: Vec<PathBuf> = [
+            "water/water_source.json",
+            "redstone/redstone_clock.json",
+            "basic_placement.json",
+            "fences/fence_to_fence.json",
+            "redstone/lever_basic.json",
+            "fences/fence_connects_to_block.json",
+        ]
+        .iter()
+        .map(|file| dir.join(file))
+        .collect();
+        let mut reversed = files.clone();
+        reversed.reverse();
+
+        let sorted = ordered_names(&files, None);
+        assert_eq!(
+            sorted,
+            vec![
+                "basic_block_placement",
+                "fence_connects_to_block",
+                "fence_connects_to_fence",
+                "lever_basic",
+                "repeater_feedback_clock",
+                "water_source_block",
+            ]
+        );
+        assert_eq!(ordered_names(&reversed, None), sorted);
+        let shuffled = ordered_names(&files, Some(7));
+        assert_eq!(shuffled, ordered_names(&reversed, Some(7)));
+        // Every other example depends on it, so a shuffle still runs it first
+        assert_eq!(shuffled[0], "basic_block_placement");
+    }
+
+    #[test]
+    fn test_shards_cover_the_suite_once() {
+        assert_eq!(parse_shard("2/5"), Ok(Shard { index: 2, count: 5 }));
+        assert!(parse_shard("0/5").is_err());
+        assert!(parse_shard("6/5").is_err());
+        assert!(parse_shard("2").is_err());
+
+        for len in [0, 1, 7, 23] {
+            let items: Vec<usize> = (0..len).collect();
+            let union: Vec<usize> = (1..=5)
+                .flat_map(|index| Shard { index, count: 5 }.select(items.clone()))
+                .collect();
+            assert_eq!(union, items);
+        }
+        let sizes: Vec<usize> = (1..=5)
+            .map(|index| {
+                Shard { index, count: 5 }
+                    .select((0..23).collect::<Vec<_>>())
+                    .len()
+            })
+            .collect();
+        assert_eq!(sizes, vec![4, 5, 4, 5, 5]);
+    }
+
+    #[test]
+    fn test_shuffle_is_a_seeded_permutation() {
+        let mut a: Vec<u32> = (0..20).collect();
+        let mut b = a.clone();
+        shuffle(&mut a, 42);
+        shuffle(&mut b, 42);
+        assert_eq!(a, b);
+        assert_ne!(a, (0..20).collect::<Vec<_>>());
+
+        let mut sorted = a.clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,504 @@
+//! JSON Schema for test files (`--emit-schema`), for editor completion and validation via `$schema`
+//!
+//! flint-core's spec types carry no schema annotations, so the schema is written out here. It
+//! describes files as authors write them, including the extensions `spec` expands at load time.
+
+use serde_json::{Value, json};
+
+/// Schema dialect of the emitted document
+const DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// `{"$ref": "#/$defs/<name>"}`
+fn def(name: &str) -> Value {
+    json!({ "$ref": format!("#/$defs/{}", name) })
+}
+
+/// One timeline action: `at`, a constant `do` and its own fields
+fn action(name: &str, description: &str, fields: Value, required: &[&str]) -> Value {
+    let mut properties = json!({
+        "at": def("tick_spec"),
+        "do": { "const": name },
+        "_comment": def("comment"),
+    });
+    if let (Some(properties), Some(fields)) = (properties.as_object_mut(), fields.as_object()) {
+        properties.extend(fields.clone());
+    }
+    let required: Vec<&str> = ["at", "do"]
+        .into_iter()
+        .chain(required.iter().copied())
+        .collect();
+    json!({
+        "description": description,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+/// The test file schema
+pub fn test_schema() -> Value {
+    let actions = vec![
+        action(
+            "place",
+            "Place a single block",
+            json!({
+                "pos": def("position"),
+                "block": def("block"),
+                "nbt": {
+                    "description": "Block entity data as an SNBT compound, e.g. {Items:[...]} for a chest",
+                    "type": "string",
+                },
+            }),
+            &["pos", "block"],
+        ),
+        action(
+            "place_each",
+            "Place several blocks",
+            json!({
+                "blocks": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": { "pos": def("position"), "block": def("block") },
+                        "required": ["pos", "block"],
+                        "additionalProperties": false,
+                    },
+                },
+            }),
+            &["blocks"],
+        ),
+        action(
+            "fill",
+            "Fill a region (corners inclusive)",
+            json!({ "region": def("region"), "with": def("block") }),
+            &["region", "with"],
+        ),
+        action(
+            "remove",
+            "Replace a block with air",
+            json!({ "pos": def("position") }),
+            &["pos"],
+        ),
+        action(
+            "assert",
+            "Check block types and states",
+            json!({
+                "checks": {
+                    "type": "array",
+                    "items": { "anyOf": [def("block_check"), def("value_check")] },
+                },
+                "values": {
+                    "description": "One block per tick in `at`; the i-th tick checks every position against the i-th block, in place of `is`",
+                    "type": "array",
+                    "items": def("block"),
+                },
+                "within": {
+                    "description": "Also pass if the checks hold at any of the next N ticks",
+                    "type": "integer",
+                    "minimum": 0,
+                },
+            }),
+            &["checks"],
+        ),
+        action(
+            "assert_region",
+            "Check every block in a box, drawn with a palette",
+            json!({
+                "region": def("region"),
+                "palette": {
+                    "description": "One-character keys to the blocks they stand for",
+                    "type": "object",
+                    "propertyNames": { "minLength": 1, "maxLength": 1 },
+                    "additionalProperties": def("block"),
+                },
+                "layers": {
+                    "description": "Bottom to top: rows from low z to high z, one key per block from low x to high x",
+                    "type": "array",
+                    "items": { "type": "array", "items": { "type": "string" } },
+                },
+                "within": {
+                    "description": "Also pass if the checks hold at any of the next N ticks",
+                    "type": "integer",
+                    "minimum": 0,
+                },
+            }),
+            &["region", "palette", "layers"],
+        ),
+        action(
+            "assert_entities",
+            "Count the entities in a region",
+            json!({
+                "region": def("region"),
+                "entity": {
+                    "description": "Entity type id; every entity except the bot if omitted",
+                    "type": "string",
+                },
+                "count": { "type": "integer", "minimum": 0 },
+            }),
+            &["region", "count"],
+        ),
+        action(
+            "assert_container",
+            "Check one slot of a container",
+            json!({
+                "pos": def("position"),
+                "slot": { "type": "integer", "minimum": 0, "maximum": 255 },
+                "item": {
+                    "description": "Item id, or minecraft:air for an empty slot",
+                    "type": "string",
+                },
+                "count": { "type": "integer", "minimum": 0 },
+            }),
+            &["pos", "slot", "item", "count"],
+        ),
+        action(
+            "assert_structure",
+            "Compare a region with a structure template",
+            json!({
+                "pos": def("position"),
+                "structure": { "description": "Template id, e.g. mypack:door", "type": "string" },
+                "size": {
+                    "type": "array",
+                    "items": { "type": "integer", "minimum": 1 },
+                    "minItems": 3,
+                    "maxItems": 3,
+                },
+            }),
+            &["pos", "structure", "size"],
+        ),
+        action(
+            "assert_light",
+            "Check the light level at a position (needs 1.20.5+)",
+            json!({
+                "pos": def("position"),
+                "min": { "type": "integer", "minimum": 0, "maximum": 15, "default": 0 },
+                "max": { "type": "integer", "minimum": 0, "maximum": 15, "default": 15 },
+            }),
+            &["pos"],
+        ),
+        action(
+            "assert_biome",
+            "Check the biome at a position",
+            json!({
+                "pos": def("position"),
+                "biome": {
+                    "description": "Biome id, e.g. minecraft:plains, or a tag like #minecraft:is_ocean",
+                    "type": "string",
+                },
+            }),
+            &["pos", "biome"],
+        ),
+        action(
+            "assert_fill",
+            "Check that every block in a region is the same block",
+            json!({
+                "region": def("region"),
+                "block": {
+                    "description": "Block id with optional state, e.g. minecraft:oak_slab[type=top]",
+                    "type": "string",
+                },
+            }),
+            &["region", "block"],
+        ),
+    ];
+
+    let mut timeline_entry = actions;
+    timeline_entry.push(def("repeat"));
+
+    let mut defs = shared_defs();
+    defs.insert(
+        "timeline_entry".to_string(),
+        json!({ "oneOf": timeline_entry }),
+    );
+    json!({
+        "$schema": DIALECT,
+        "title": "FlintMC test",
+        "type": "object",
+        "properties": file_properties(),
+        "required": ["name", "timeline"],
+        "$defs": defs,
+    })
+}
+
+/// Top-level keys of a test file
+fn file_properties() -> Value {
+    json!({
+        "$schema": { "description": "Path or URL of this schema, for editors", "type": "string" },
+        "_comment": def("comment"),
+        "flintVersion": { "description": "Test schema version", "type": "string" },
+        "name": { "type": "string" },
+        "description": { "type": "string" },
+        "tags": { "type": "array", "items": { "type": "string" } },
+        "dependencies": {
+            "description": "Tests that must pass before this one runs",
+            "type": "array",
+            "items": { "type": "string" },
+        },
+        "dimension": {
+            "enum": [
+                "overworld", "the_nether", "the_end", "nether", "end",
+                "minecraft:overworld", "minecraft:the_nether", "minecraft:the_end",
+            ],
+        },
+        "setup": def("setup"),
+        "breakpoints": {
+            "type": "array",
+            "items": {
+                "oneOf": [
+                    { "type": "integer", "minimum": 0 },
+                    {
+                        "type": "object",
+                        "properties": {
+                            "from": { "type": "integer", "minimum": 0 },
+                            "to": { "type": "integer", "minimum": 0 },
+                        },
+                        "required": ["from", "to"],
+                        "additionalProperties": false,
+                    },
+                ],
+            },
+        },
+        "snapshot_region": def("region"),
+        "before": {
+            "description": "Commands run at the test's origin before the timeline",
+            "type": "array",
+            "items": { "type": "string" },
+        },
+        "after": {
+            "description": "Commands run at the test's origin when the test finishes",
+            "type": "array",
+            "items": { "type": "string" },
+        },
+        "settles_fast": {
+            "description": "Fail a block check once a wrong block reads the same twice",
+            "type": "boolean",
+        },
+        "expect_failure": {
+            "description": "Known bug: the test passes when an assertion fails (XFAIL) and fails when all pass (XPASS)",
+            "type": "boolean",
+        },
+        "timeline": { "type": "array", "items": def("timeline_entry") },
+    })
+}
+
+/// Definitions the file properties and actions refer to, one `json!` each
+fn shared_defs() -> serde_json::Map<String, Value> {
+    let mut defs = serde_json::Map::new();
+    defs.insert(
+        "comment".to_string(),
+        json!({ "description": "A note for readers, dropped when the test loads" }),
+    );
+    defs.insert(
+        "coordinates".to_string(),
+        json!({
+            "type": "array",
+            "items": { "type": "integer" },
+            "minItems": 3,
+            "maxItems": 3,
+        }),
+    );
+    defs.insert(
+        "position".to_string(),
+        json!({
+            "oneOf": [
+                def("coordinates"),
+                {
+                    "description": "A position relative to another one",
+                    "type": "object",
+                    "properties": {
+                        "relative_to": def("position"),
+                        "dir": { "enum": ["north", "south", "east", "west", "up", "down"] },
+                        "dist": { "type": "integer", "default": 1 },
+                    },
+                    "required": ["relative_to", "dir"],
+                    "additionalProperties": false,
+                },
+            ],
+        }),
+    );
+    defs.insert(
+        "region".to_string(),
+        json!({
+            "description": "Two corners, inclusive",
+            "type": "array",
+            "items": def("position"),
+            "minItems": 2,
+            "maxItems": 2,
+        }),
+    );
+    defs.insert(
+        "tick_spec".to_string(),
+        json!({
+            "oneOf": [
+                { "type": "integer", "minimum": 0 },
+                { "type": "array", "items": { "type": "integer", "minimum": 0 } },
+                {
+                    "description": "Every `step` ticks from `start` through `end`",
+                    "type": "object",
+                    "properties": {
+                        "start": { "type": "integer", "minimum": 0 },
+                        "step": { "type": "integer", "minimum": 1 },
+                        "end": { "type": "integer", "minimum": 0 },
+                    },
+                    "required": ["start", "step", "end"],
+                    "additionalProperties": false,
+                },
+            ],
+        }),
+    );
+    defs.insert(
+        "block".to_string(),
+        json!({
+            "oneOf": [
+                { "description": "Block id, e.g. minecraft:stone", "type": "string" },
+                {
+                    "type": "object",
+                    "properties": {
+                        "id": { "type": "string" },
+                        "properties": {
+                            "type": "object",
+                            "additionalProperties": {
+                                "oneOf": [
+                                    { "type": ["string", "boolean", "integer"] },
+                                    {
+                                        "description": "A comparison, e.g. { \">=\": 5 }; only asserts support these",
+                                        "type": "object",
+                                        "propertyNames": { "enum": ["==", "!=", "<", "<=", ">", ">="] },
+                                        "additionalProperties": { "type": ["string", "integer", "number"] },
+                                        "minProperties": 1,
+                                        "maxProperties": 1,
+                                    },
+                                ],
+                            },
+                        },
+                    },
+                    "required": ["id"],
+                    "additionalProperties": false,
+                },
+            ],
+        }),
+    );
+    defs.insert(
+        "block_check".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "pos": def("position"),
+                "is": def("block"),
+                "negate": {
+                    "description": "Pass when the block does NOT match",
+                    "type": "boolean",
+                },
+                "strict_properties": {
+                    "description": "Require exactly the listed properties",
+                    "type": "boolean",
+                },
+            },
+            "required": ["pos", "is"],
+            "additionalProperties": false,
+        }),
+    );
+    defs.insert(
+        "value_check".to_string(),
+        json!({
+            "description": "A check whose block comes from the assert's `values`",
+            "type": "object",
+            "properties": {
+                "pos": def("position"),
+                "negate": { "type": "boolean" },
+                "strict_properties": { "type": "boolean" },
+            },
+            "required": ["pos"],
+            "additionalProperties": false,
+        }),
+    );
+    defs.insert(
+        "setup".to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "cleanup": {
+                    "type": "object",
+                    "properties": { "region": def("region") },
+                    "required": ["region"],
+                },
+                "warmup_ticks": { "type": "integer", "minimum": 0 },
+            },
+        }),
+    );
+    defs.insert(
+        "repeat".to_string(),
+        json!({
+            "description": "A sub-timeline expanded `count` times, `period` ticks apart",
+            "type": "object",
+            "properties": {
+                "repeat": {
+                    "type": "object",
+                    "properties": {
+                        "start": { "type": "integer", "minimum": 0, "default": 0 },
+                        "count": { "type": "integer", "minimum": 0 },
+                        "period": { "type": "integer", "minimum": 0 },
+                        "timeline": { "type": "array", "items": def("timeline_entry") },
+                    },
+                    "required": ["count", "period", "timeline"],
+                    "additionalProperties": false,
+                },
+            },
+            "required": ["repeat"],
+            "additionalProperties": false,
+        }),
+    );
+    defs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every `$ref` target below `value`
+    fn refs(value: &Value, out: &mut Vec<String>) {
+        match value {
+            Value::Object(object) => {
+                if let Some(Value::String(target)) = object.get("$ref") {
+                    out.push(target.clone());
+                }
+                object.values().for_each(|v| refs(v, out));
+            }
+            Value::Array(items) => items.iter().for_each(|v| refs(v, out)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_schema_refs_and_actions() {
+        let schema = test_schema();
+        let mut targets = Vec::new();
+        refs(&schema, &mut targets);
+        for target in targets {
+            let name = target.strip_prefix("#/$defs/").unwrap();
+            assert!(
+                schema["$defs"].get(name).is_some(),
+                "missing $defs/{}",
+                name
+            );
+        }
+
+        let actions: Vec<&str> = schema["$defs"]["timeline_entry"]["oneOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|entry| entry["properties"]["do"]["const"].as_str())
+            .collect();
+        for name in [
+            "place",
+            "place_each",
+            "fill",
+            "remove",
+            "assert",
+            "assert_structure",
+        ] {
+            assert!(actions.contains(&name), "no schema for {}", name);
+        }
+    }
+}
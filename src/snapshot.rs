@@ -0,0 +1,205 @@
+//! Snapshot (golden file) checks - the final blocks of a test's `snapshot_region`,
+//! recorded once and compared on later runs
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Golden file directory, relative to the working directory
+pub const SNAPSHOT_DIR: &str = ".flint-snapshots";
+
+/// What to do with snapshots at the end of a test
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SnapshotMode {
+    /// Compare against existing snapshots, warn about missing ones (default)
+    #[default]
+    Compare,
+    /// Record snapshots that don't exist yet, compare the rest (`--snapshot`)
+    Record,
+    /// Overwrite every snapshot with this run's blocks (`--update-snapshots`)
+    Update,
+}
+
+/// One non-air block, in the test's local coordinates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SnapshotBlock {
+    pos: [i32; 3],
+    block: String,
+}
+
+/// Recorded blocks of a snapshot region
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    region: [[i32; 3]; 2],
+    blocks: Vec<SnapshotBlock>,
+}
+
+/// A position whose block differs from the snapshot ("minecraft:air" if empty)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotMismatch {
+    pub pos: [i32; 3],
+    pub expected: String,
+    pub actual: String,
+}
+
+const AIR: &str = "minecraft:air";
+
+/// A test name as a file name: anything but ASCII letters, digits, `-` and `_` becomes `_`
+pub fn file_name(test_name: &str) -> String {
+    test_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Golden file for a test, named after the test
+pub fn path_for(test_name: &str) -> PathBuf {
+    Path::new(SNAPSHOT_DIR).join(format!("{}.json", file_name(test_name)))
+}
+
+impl Snapshot {
+    /// Build a snapshot from scanned world blocks, moving them into local coordinates
+    pub fn from_world(
+        region: [[i32; 3]; 2],
+        blocks: &HashMap<[i32; 3], String>,
+        offset: [i32; 3],
+    ) -> Self {
+        let local: BTreeMap<[i32; 3], &String> = blocks
+            .iter()
+            .map(|(pos, block)| {
+                (
+                    [pos[0] - offset[0], pos[1] - offset[1], pos[2] - offset[2]],
+                    block,
+                )
+            })
+            .collect();
+        Self {
+            region,
+            blocks: local
+                .into_iter()
+                .map(|(pos, block)| SnapshotBlock {
+                    pos,
+                    block: block.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Load a snapshot, or None if it hasn't been recorded
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+        let snapshot = serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid snapshot {}", path.display()))?;
+        Ok(Some(snapshot))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write snapshot {}", path.display()))
+    }
+
+    /// Positions where `actual` differs from this snapshot, sorted by position
+    pub fn diff(&self, actual: &Snapshot) -> Vec<SnapshotMismatch> {
+        let expected: BTreeMap<[i32; 3], &str> = self
+            .blocks
+            .iter()
+            .map(|b| (b.pos, b.block.as_str()))
+            .collect();
+        let actual: BTreeMap<[i32; 3], &str> = actual
+            .blocks
+            .iter()
+            .map(|b| (b.pos, b.block.as_str()))
+            .collect();
+
+        let mut positions: Vec<[i32; 3]> = expected.keys().chain(actual.keys()).copied().collect();
+        positions.sort();
+        positions.dedup();
+        positions
+            .into_iter()
+            .filter_map(|pos| {
+                let expected = expected.get(&pos).copied().unwrap_or(AIR);
+                let actual = actual.get(&pos).copied().unwrap_or(AIR);
+                (expected != actual).then(|| SnapshotMismatch {
+                    pos,
+                    expected: expected.to_string(),
+                    actual: actual.to_string(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_reports_changed_missing_and_extra_blocks() {
+        let region = [[0, 64, 0], [2, 64, 0]];
+        let offset = [100, 0, 0];
+        let golden: HashMap<[i32; 3], String> = [
+            ([100, 64, 0], "minecraft:stone".to_string()),
+            (
+                [101, 64, 0],
+                "minecraft:redstone_lamp[lit=true]".to_string(),
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let run: HashMap<[i32; 3], String> = [
+            ([100, 64, 0], "minecraft:stone".to_string()),
+            (
+                [101, 64, 0],
+                "minecraft:redstone_lamp[lit=false]".to_string(),
+            ),
+            ([102, 64, 0], "minecraft:dirt".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        let golden = Snapshot::from_world(region, &golden, offset);
+        let run = Snapshot::from_world(region, &run, offset);
+        assert!(golden.diff(&golden).is_empty());
+        assert_eq!(
+            golden.diff(&run),
+            vec![
+                SnapshotMismatch {
+                    pos: [1, 64, 0],
+                    expected: "minecraft:redstone_lamp[lit=true]".to_string(),
+                    actual: "minecraft:redstone_lamp[lit=false]".to_string(),
+                },
+                SnapshotMismatch {
+                    pos: [2, 64, 0],
+                    expected: AIR.to_string(),
+                    actual: "minecraft:dirt".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_path_for_sanitizes_name() {
+        assert_eq!(
+            path_for("fences/oak fence"),
+            Path::new(SNAPSHOT_DIR).join("fences_oak_fence.json")
+        );
+    }
+}
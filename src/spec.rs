@@ -0,0 +1,1368 @@
+//! Test spec loading - picks a parser for flint-core's TestSpec by file extension
+//! and checks the declared schema version
+
+use anyhow::{Context, Result, bail};
+use flint_core::test_spec::{ActionType, TestSpec, TimelineEntry};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Check whether a path refers to a YAML test file
+pub fn is_yaml(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("yaml" | "yml")
+    )
+}
+
+/// Blank out `//` and `/* */` comments outside strings, so plain serde_json can parse the text
+/// Line breaks are kept, so parse errors still point at the right line.
+fn strip_json_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut in_string = false;
+    while let Some(c) = chars.next() {
+        if in_string {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                out.push(c);
+            }
+            ('/', Some('/')) => while chars.next_if(|c| *c != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        out.push('\n');
+                    }
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Remove `_comment` keys at any depth: notes for readers, not part of the spec
+fn remove_comment_keys(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            object.remove("_comment");
+            object.values_mut().for_each(remove_comment_keys);
+        }
+        Value::Array(items) => items.iter_mut().for_each(remove_comment_keys),
+        _ => {}
+    }
+}
+
+/// Dimension a test runs in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dimension {
+    #[default]
+    #[serde(alias = "minecraft:overworld")]
+    Overworld,
+    #[serde(alias = "nether", alias = "minecraft:the_nether")]
+    TheNether,
+    #[serde(alias = "end", alias = "minecraft:the_end")]
+    TheEnd,
+}
+
+impl Dimension {
+    /// Namespaced id for `/execute in`
+    pub fn id(self) -> &'static str {
+        match self {
+            Dimension::Overworld => "minecraft:overworld",
+            Dimension::TheNether => "minecraft:the_nether",
+            Dimension::TheEnd => "minecraft:the_end",
+        }
+    }
+
+    /// Lowest and highest buildable y
+    pub fn y_range(self) -> (i32, i32) {
+        match self {
+            Dimension::Overworld => (-64, 319),
+            Dimension::TheNether | Dimension::TheEnd => (0, 255),
+        }
+    }
+}
+
+/// An `assert_entities` check: how many entities (of one type, or any) a region holds
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct EntityCheck {
+    /// [min, max] block corners in local coordinates, inclusive
+    pub region: [[i32; 3]; 2],
+    /// Entity type id, e.g. "minecraft:item"; any entity if omitted
+    #[serde(default)]
+    pub entity: Option<String>,
+    pub count: u32,
+}
+
+/// An `assert_container` check: the item stack in one slot of a chest, hopper, etc.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ContainerCheck {
+    pub pos: [i32; 3],
+    pub slot: u8,
+    /// Item id, e.g. "minecraft:diamond", or "minecraft:air" for an empty slot
+    pub item: String,
+    pub count: u32,
+}
+
+/// An `assert_structure` check: a region must match a saved structure template block for block
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct StructureCheck {
+    /// Lowest corner of the region, where the template's origin goes
+    pub pos: [i32; 3],
+    /// Template id as `/place template` takes it, e.g. "mypack:door"
+    pub structure: String,
+    /// Template size in blocks (x, y, z), as shown by the structure block that saved it
+    pub size: [i32; 3],
+}
+
+/// Brightest light level there is
+pub const MAX_LIGHT: u8 = 15;
+
+fn max_light() -> u8 {
+    MAX_LIGHT
+}
+
+/// An `assert_light` check: the light level at a position, as mob spawning sees it, in a range
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct LightCheck {
+    pub pos: [i32; 3],
+    #[serde(default)]
+    pub min: u8,
+    #[serde(default = "max_light")]
+    pub max: u8,
+}
+
+/// An `assert_biome` check: the biome at a position
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BiomeCheck {
+    pub pos: [i32; 3],
+    /// Biome id, e.g. "minecraft:plains", or a biome tag like "#minecraft:is_ocean"
+    pub biome: String,
+}
+
+/// An `assert_fill` check: every block in a region is the same block
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FillCheck {
+    /// Two corners in local coordinates, inclusive, in any order
+    pub region: [[i32; 3]; 2],
+    /// Block id with optional state, e.g. "minecraft:stone" or "minecraft:oak_slab[type=top]"
+    pub block: String,
+}
+
+/// A check flint-core has no action for, run in place of an empty placeholder assert
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "do", rename_all = "snake_case")]
+pub enum ExtraCheck {
+    AssertEntities(EntityCheck),
+    AssertContainer(ContainerCheck),
+    AssertStructure(StructureCheck),
+    AssertLight(LightCheck),
+    AssertBiome(BiomeCheck),
+    AssertFill(FillCheck),
+}
+
+/// `do` values parsed into ExtraCheck
+const EXTRA_CHECK_ACTIONS: &[&str] = &[
+    "assert_entities",
+    "assert_container",
+    "assert_structure",
+    "assert_light",
+    "assert_biome",
+    "assert_fill",
+];
+
+/// Per-check modifiers of one scheduled assertion, as check positions
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckFlags {
+    /// Checks that pass when the block does NOT match (`negate`)
+    pub negated: Vec<[i32; 3]>,
+    /// Checks whose block must have exactly the listed properties (`strict_properties`)
+    pub strict: Vec<[i32; 3]>,
+    /// Stop polling a wrong block once it reads the same twice (`settles_fast`)
+    pub settles_fast: bool,
+}
+
+/// Per-test options that flint-core's TestSpec has no field for
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TestOptions {
+    #[serde(default)]
+    pub dimension: Dimension,
+    /// Ticks to run after cleanup and freeze, before the timeline's tick 0
+    #[serde(default)]
+    pub warmup_ticks: u32,
+    /// Region compared against a golden file when the test ends: [min, max] in local coordinates
+    #[serde(default)]
+    pub snapshot_region: Option<[[i32; 3]; 2]>,
+    /// Commands run once before the merged timeline starts, positioned at the test's origin
+    #[serde(default)]
+    pub before: Vec<String>,
+    /// Commands run when the test finishes, before its area is cleaned up
+    #[serde(default)]
+    pub after: Vec<String>,
+    /// Fail a block check as soon as a wrong block reads the same twice, instead of retrying
+    #[serde(default)]
+    pub settles_fast: bool,
+    /// The test documents a known bug: it passes (XFAIL) when an assertion fails, and fails
+    /// (XPASS) when every assertion passes
+    #[serde(default)]
+    pub expect_failure: bool,
+    /// Assertion retry windows from `within`: timeline entry index -> extra ticks
+    #[serde(skip)]
+    pub assert_windows: HashMap<usize, u32>,
+    /// Checks declared with `negate`: (timeline entry index, check pos)
+    #[serde(skip)]
+    pub negated_checks: HashSet<(usize, [i32; 3])>,
+    /// Checks declared with `strict_properties`: (timeline entry index, check pos)
+    #[serde(skip)]
+    pub strict_checks: HashSet<(usize, [i32; 3])>,
+    /// Extra checks by the timeline entry index of the empty placeholder assert that runs them
+    #[serde(skip)]
+    pub extra_checks: HashMap<usize, ExtraCheck>,
+    /// Block entity data from `nbt` on place actions: timeline entry index -> SNBT compound
+    #[serde(skip)]
+    pub block_nbt: HashMap<usize, String>,
+    /// `{"from", "to"}` breakpoint ranges as declared, inclusive; their ticks are in `breakpoints`
+    #[serde(skip)]
+    pub breakpoint_ranges: Vec<(u32, u32)>,
+}
+
+/// Index of `entry` in its test's timeline
+/// Merged timelines borrow the test's entries, so entries are told apart by identity:
+/// two identical asserts at the same tick still get their own options. `entry` must come
+/// from the very TestSpec passed in (not a clone), as `TimelineAggregate::from_tests` gives.
+pub fn entry_index(test: &TestSpec, entry: &TimelineEntry) -> Option<usize> {
+    test.timeline
+        .iter()
+        .position(|candidate| std::ptr::eq(candidate, entry))
+}
+
+impl TestOptions {
+    /// Retry window of one of the test's assertions, if it declared `within`
+    pub fn assert_window(&self, test: &TestSpec, entry: &TimelineEntry) -> Option<u32> {
+        let entry_idx = entry_index(test, entry)?;
+        self.assert_windows.get(&entry_idx).copied()
+    }
+
+    /// Extra checks one of the test's assertions stands in for, if it is a placeholder
+    pub fn extra_checks_at(&self, test: &TestSpec, entry: &TimelineEntry) -> Option<&[ExtraCheck]> {
+        let entry_idx = entry_index(test, entry)?;
+        self.extra_checks.get(&entry_idx).map(std::slice::from_ref)
+    }
+
+    /// Block entity data (`nbt`) one of the test's place actions puts in the placed block
+    pub fn block_nbt_at(&self, test: &TestSpec, entry: &TimelineEntry) -> Option<&str> {
+        let entry_idx = entry_index(test, entry)?;
+        self.block_nbt.get(&entry_idx).map(String::as_str)
+    }
+
+    /// `negate` and `strict_properties` checks of one of the test's assertions
+    pub fn check_flags(&self, test: &TestSpec, entry: &TimelineEntry) -> CheckFlags {
+        let ActionType::Assert { checks } = &entry.action_type else {
+            return CheckFlags::default();
+        };
+        let entry_idx = entry_index(test, entry);
+        let flagged = |set: &HashSet<(usize, [i32; 3])>| -> Vec<[i32; 3]> {
+            let Some(entry_idx) = entry_idx else {
+                return Vec::new();
+            };
+            checks
+                .iter()
+                .map(|check| check.pos)
+                .filter(|pos| set.contains(&(entry_idx, *pos)))
+                .collect()
+        };
+        CheckFlags {
+            negated: flagged(&self.negated_checks),
+            strict: flagged(&self.strict_checks),
+            settles_fast: self.settles_fast,
+        }
+    }
+}
+
+/// Top-level keys read into TestOptions and removed before parsing the TestSpec
+const OPTION_KEYS: &[&str] = &[
+    "dimension",
+    "snapshot_region",
+    "before",
+    "after",
+    "settles_fast",
+    "expect_failure",
+];
+
+/// Keys under `setup` read into TestOptions and removed before parsing the TestSpec
+const SETUP_OPTION_KEYS: &[&str] = &["warmup_ticks"];
+
+/// Newest test schema version (`flintVersion`) this binary understands
+pub const CRATE_SCHEMA_VERSION: &str = "0.1";
+
+/// Set once a test written for an older schema was reported, so a run warns only once
+static OUTDATED_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Load a test spec from disk, with the local options flint-core's TestSpec has no field for
+/// `.yaml`/`.yml` files are parsed as YAML, everything else as JSON.
+/// Local schema extensions are expanded before handing the spec to flint-core.
+pub fn load_test(path: &Path) -> Result<(TestSpec, TestOptions)> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut value: Value = if is_yaml(path) {
+        serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse YAML test {}", path.display()))?
+    } else {
+        serde_json::from_str(&strip_json_comments(&contents))
+            .with_context(|| format!("Failed to parse JSON test {}", path.display()))?
+    };
+
+    // Editor hint from `--emit-schema`, not part of the spec
+    if let Some(object) = value.as_object_mut() {
+        object.remove("$schema");
+    }
+    remove_comment_keys(&mut value);
+    let breakpoint_ranges = expand_breakpoint_ranges(&mut value)
+        .with_context(|| format!("Invalid test {}", path.display()))?;
+    expand_extensions(&mut value).with_context(|| format!("Invalid test {}", path.display()))?;
+    let mut options =
+        take_options(&mut value).with_context(|| format!("Invalid test {}", path.display()))?;
+    options.breakpoint_ranges = breakpoint_ranges;
+    let test: TestSpec = serde_json::from_value(value)
+        .with_context(|| format!("Invalid test {}", path.display()))?;
+
+    if let Some(version) = test.flint_version.as_deref() {
+        let outdated = check_version(version, CRATE_SCHEMA_VERSION)
+            .with_context(|| format!("Incompatible test {}", path.display()))?;
+        if outdated && !OUTDATED_WARNED.swap(true, Ordering::Relaxed) {
+            tracing::warn!(
+                "{} was written for flintVersion {} (current: {}), it may need updating; other outdated tests are not listed",
+                path.display(),
+                version,
+                CRATE_SCHEMA_VERSION
+            );
+        }
+    }
+
+    Ok((test, options))
+}
+
+/// A test file with the result of loading it, so a run parses each file once
+pub struct LoadedTest {
+    pub path: PathBuf,
+    pub loaded: Result<(TestSpec, TestOptions)>,
+}
+
+impl LoadedTest {
+    pub fn load(path: PathBuf) -> Self {
+        let loaded = load_test(&path);
+        Self { path, loaded }
+    }
+
+    /// The test's name, or the file path for a file that didn't load
+    pub fn name(&self) -> String {
+        match &self.loaded {
+            Ok((test, _)) => test.name.clone(),
+            Err(_) => self.path.display().to_string(),
+        }
+    }
+}
+
+/// Remove local option keys from a test document and parse them into TestOptions
+fn take_options(value: &mut Value) -> Result<TestOptions> {
+    let mut options = serde_json::Map::new();
+    if let Some(object) = value.as_object_mut() {
+        for key in OPTION_KEYS {
+            if let Some(option) = object.remove(*key) {
+                options.insert(key.to_string(), option);
+            }
+        }
+        if let Some(setup) = object.get_mut("setup").and_then(Value::as_object_mut) {
+            for key in SETUP_OPTION_KEYS {
+                if let Some(option) = setup.remove(*key) {
+                    options.insert(key.to_string(), option);
+                }
+            }
+        }
+    }
+    let mut options: TestOptions = serde_json::from_value(Value::Object(options))?;
+    options.assert_windows = take_assert_windows(value)?;
+    options.negated_checks = take_check_flag(value, "negate")?;
+    options.strict_checks = take_check_flag(value, "strict_properties")?;
+    options.extra_checks = take_extra_checks(value)?;
+    options.block_nbt = take_block_nbt(value)?;
+    Ok(options)
+}
+
+/// Ticks a (fully expanded) timeline entry runs at
+fn entry_ticks(entry: &serde_json::Map<String, Value>) -> Result<Vec<u32>> {
+    match entry.get("at") {
+        Some(Value::Array(ticks)) => Ok(serde_json::from_value(Value::Array(ticks.clone()))?),
+        Some(tick) => Ok(vec![serde_json::from_value(tick.clone())?]),
+        None => bail!("timeline entry has no `at`"),
+    }
+}
+
+/// Remove `within` from assert entries, keyed by the entry's index in the timeline
+fn take_assert_windows(value: &mut Value) -> Result<HashMap<usize, u32>> {
+    let mut windows = HashMap::new();
+    let Some(timeline) = value.get_mut("timeline").and_then(Value::as_array_mut) else {
+        return Ok(windows);
+    };
+    for (entry_idx, entry) in timeline.iter_mut().enumerate() {
+        let Some(entry) = entry.as_object_mut() else {
+            continue;
+        };
+        let Some(within) = entry.remove("within") else {
+            continue;
+        };
+        let within: u32 =
+            serde_json::from_value(within).context("`within` must be a number of ticks")?;
+        if entry.get("do").and_then(Value::as_str) != Some("assert") {
+            bail!("`within` is only supported on assert actions");
+        }
+        let has_checks = entry
+            .get("checks")
+            .and_then(Value::as_array)
+            .is_some_and(|checks| !checks.is_empty());
+        if !has_checks {
+            bail!("assert with `within` needs at least one check");
+        }
+        windows.insert(entry_idx, within);
+    }
+    Ok(windows)
+}
+
+/// Remove `nbt` from place entries, keyed by the entry's index in the timeline
+fn take_block_nbt(value: &mut Value) -> Result<HashMap<usize, String>> {
+    let mut block_nbt = HashMap::new();
+    let Some(timeline) = value.get_mut("timeline").and_then(Value::as_array_mut) else {
+        return Ok(block_nbt);
+    };
+    for (entry_idx, entry) in timeline.iter_mut().enumerate() {
+        let Some(entry) = entry.as_object_mut() else {
+            continue;
+        };
+        let Some(nbt) = entry.remove("nbt") else {
+            continue;
+        };
+        if entry.get("do").and_then(Value::as_str) != Some("place") {
+            bail!("`nbt` is only supported on place actions");
+        }
+        let nbt: String = serde_json::from_value(nbt)
+            .ok()
+            .filter(|nbt: &String| nbt.trim_start().starts_with('{'))
+            .context("`nbt` must be an SNBT compound, e.g. \"{Items:[]}\"")?;
+        block_nbt.insert(entry_idx, nbt);
+    }
+    Ok(block_nbt)
+}
+
+/// Remove a boolean check flag (`negate`, `strict_properties`) from assert checks,
+/// keyed by the entry's index in the timeline and the check's pos
+fn take_check_flag(value: &mut Value, flag: &str) -> Result<HashSet<(usize, [i32; 3])>> {
+    let mut flagged = HashSet::new();
+    let Some(timeline) = value.get_mut("timeline").and_then(Value::as_array_mut) else {
+        return Ok(flagged);
+    };
+    for (entry_idx, entry) in timeline.iter_mut().enumerate() {
+        let Some(entry) = entry.as_object_mut() else {
+            continue;
+        };
+        let is_assert = entry.get("do").and_then(Value::as_str) == Some("assert");
+        let checks = entry
+            .get_mut("checks")
+            .and_then(Value::as_array_mut)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_object_mut);
+        for check in checks {
+            let Some(set) = check.remove(flag) else {
+                continue;
+            };
+            if !is_assert {
+                bail!("`{}` is only supported on assert checks", flag);
+            }
+            let set: bool = serde_json::from_value(set)
+                .with_context(|| format!("`{}` must be true or false", flag))?;
+            if set {
+                let pos: [i32; 3] = serde_json::from_value(
+                    check
+                        .get("pos")
+                        .cloned()
+                        .with_context(|| format!("check with `{}` has no `pos`", flag))?,
+                )?;
+                flagged.insert((entry_idx, pos));
+            }
+        }
+    }
+    Ok(flagged)
+}
+
+/// Replace each extra check entry with an empty assert in its place, and collect its check
+/// flint-core has no such actions, so the placeholder keeps the tick on the merged timeline.
+/// Entries keep their indices, which the other per-entry options are keyed by.
+fn take_extra_checks(value: &mut Value) -> Result<HashMap<usize, ExtraCheck>> {
+    let mut checks = HashMap::new();
+    let Some(timeline) = value.get_mut("timeline").and_then(Value::as_array_mut) else {
+        return Ok(checks);
+    };
+    for (entry_idx, entry) in timeline.iter_mut().enumerate() {
+        let Some(object) = entry.as_object() else {
+            continue;
+        };
+        let Some(action) = object
+            .get("do")
+            .and_then(Value::as_str)
+            .filter(|action| EXTRA_CHECK_ACTIONS.contains(action))
+        else {
+            continue;
+        };
+        let ticks = entry_ticks(object)?;
+        let check: ExtraCheck =
+            serde_json::from_value(entry.clone()).with_context(|| match action {
+                "assert_entities" => "assert_entities needs a `region` and a `count`",
+                "assert_structure" => "assert_structure needs a `pos`, `structure` and `size`",
+                "assert_light" => "assert_light needs a `pos`, with an optional `min` and `max`",
+                "assert_biome" => "assert_biome needs a `pos` and a `biome`",
+                "assert_fill" => "assert_fill needs a `region` and a `block`",
+                _ => "assert_container needs a `pos`, `slot`, `item` and `count`",
+            })?;
+        checks.insert(entry_idx, check);
+        *entry = serde_json::json!({ "at": ticks, "do": "assert", "checks": [] });
+    }
+    Ok(checks)
+}
+
+/// Rewrite schema extensions that flint-core doesn't know into plain TestSpec JSON
+fn expand_extensions(value: &mut Value) -> Result<()> {
+    if let Some(timeline) = value.get_mut("timeline").and_then(Value::as_array_mut) {
+        let entries = std::mem::take(timeline);
+        *timeline = expand_repeat_blocks(entries)?;
+    }
+    expand_every_ticks(value)?;
+    resolve_relative_positions(value)?;
+    expand_region_asserts(value)
+}
+
+/// Replace each `assert_region` entry with a plain assert holding one check per block
+/// `layers` go bottom to top, each a list of rows from low z to high z, and each row has one
+/// `palette` key per block from low x to high x.
+fn expand_region_asserts(value: &mut Value) -> Result<()> {
+    let Some(timeline) = value.get_mut("timeline").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+    for entry in timeline.iter_mut() {
+        let Some(object) = entry.as_object_mut() else {
+            continue;
+        };
+        if object.get("do").and_then(Value::as_str) != Some("assert_region") {
+            continue;
+        }
+        let mut take = |key: &str| object.remove(key).unwrap_or_default();
+        let region: [[i32; 3]; 2] = serde_json::from_value(take("region"))
+            .context("assert_region needs a `region` with two corners")?;
+        let palette: HashMap<String, Value> = serde_json::from_value(take("palette"))
+            .context("assert_region needs a `palette` from one-character keys to blocks")?;
+        let layers: Vec<Vec<String>> = serde_json::from_value(take("layers"))
+            .context("assert_region needs `layers`, each a list of rows of palette keys")?;
+
+        let min = [0, 1, 2].map(|axis| region[0][axis].min(region[1][axis]));
+        let max = [0, 1, 2].map(|axis| region[0][axis].max(region[1][axis]));
+        let size = [0, 1, 2].map(|axis| (max[axis] - min[axis] + 1) as usize);
+        if layers.len() != size[1] {
+            bail!(
+                "assert_region has {} layers, but its region is {} blocks high",
+                layers.len(),
+                size[1]
+            );
+        }
+        let mut checks = Vec::new();
+        for (dy, layer) in layers.iter().enumerate() {
+            if layer.len() != size[2] {
+                bail!(
+                    "assert_region layer {} has {} rows, but its region is {} blocks deep (z)",
+                    dy,
+                    layer.len(),
+                    size[2]
+                );
+            }
+            for (dz, row) in layer.iter().enumerate() {
+                let keys: Vec<char> = row.chars().collect();
+                if keys.len() != size[0] {
+                    bail!(
+                        "assert_region row {} of layer {} has {} keys, but its region is {} blocks wide (x)",
+                        dz,
+                        dy,
+                        keys.len(),
+                        size[0]
+                    );
+                }
+                for (dx, key) in keys.into_iter().enumerate() {
+                    let block = palette
+                        .get(&key.to_string())
+                        .with_context(|| format!("assert_region palette has no `{}`", key))?;
+                    let pos = [min[0] + dx as i32, min[1] + dy as i32, min[2] + dz as i32];
+                    checks.push(serde_json::json!({ "pos": pos, "is": block }));
+                }
+            }
+        }
+        object.insert("do".to_string(), Value::from("assert"));
+        object.insert("checks".to_string(), Value::Array(checks));
+    }
+    Ok(())
+}
+
+/// Unit offset for a direction name: north is -z, east is +x, up is +y
+fn direction_offset(dir: &str) -> Option<[i64; 3]> {
+    match dir.to_lowercase().as_str() {
+        "north" => Some([0, 0, -1]),
+        "south" => Some([0, 0, 1]),
+        "east" => Some([1, 0, 0]),
+        "west" => Some([-1, 0, 0]),
+        "up" => Some([0, 1, 0]),
+        "down" => Some([0, -1, 0]),
+        _ => None,
+    }
+}
+
+/// Replace `{"relative_to": [x, y, z], "dir": "north", "dist": 2}` positions with plain coordinates
+/// `dist` defaults to 1. `relative_to` may itself be a relative position.
+fn resolve_relative_positions(value: &mut Value) -> Result<()> {
+    match value {
+        Value::Array(items) => items.iter_mut().try_for_each(resolve_relative_positions),
+        Value::Object(object) => {
+            object
+                .values_mut()
+                .try_for_each(resolve_relative_positions)?;
+            if !object.contains_key("relative_to") {
+                return Ok(());
+            }
+            let base: [i64; 3] = serde_json::from_value(object["relative_to"].clone())
+                .context("'relative_to' must be a position [x, y, z]")?;
+            let dir = object
+                .get("dir")
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow::anyhow!("Relative position needs a 'dir'"))?;
+            let step = direction_offset(dir).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown direction '{}', expected north, south, east, west, up or down",
+                    dir
+                )
+            })?;
+            let dist = match object.get("dist") {
+                Some(dist) => dist.as_i64().ok_or_else(|| {
+                    anyhow::anyhow!("Relative position 'dist' must be a whole number")
+                })?,
+                None => 1,
+            };
+            let pos: Vec<i64> = (0..3).map(|axis| base[axis] + step[axis] * dist).collect();
+            *value = Value::from(pos);
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Expand `{"repeat": {"start", "count", "period", "timeline"}}` entries into concrete entries
+/// Body ticks are relative to each iteration's start (start + i * period).
+/// Iterations are emitted in order, so an action at the end of one iteration that lands on
+/// the next iteration's start tick still runs before that iteration's first action.
+fn expand_repeat_blocks(entries: Vec<Value>) -> Result<Vec<Value>> {
+    let mut expanded = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let Some(repeat) = entry.get("repeat") else {
+            expanded.push(entry);
+            continue;
+        };
+        let field = |key: &str| {
+            repeat
+                .get(key)
+                .and_then(Value::as_u64)
+                .ok_or_else(|| anyhow::anyhow!("Repeat block needs a numeric '{}'", key))
+        };
+        let start = repeat.get("start").and_then(Value::as_u64).unwrap_or(0);
+        let count = field("count")?;
+        let period = field("period")?;
+        if period == 0 {
+            anyhow::bail!("Repeat block needs a period of at least 1");
+        }
+        let body = repeat
+            .get("timeline")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow::anyhow!("Repeat block needs a 'timeline' list"))?;
+        let body = expand_repeat_blocks(body.clone())?;
+
+        for iteration in 0..count {
+            let iteration_start = start + iteration * period;
+            for body_entry in &body {
+                let mut concrete = body_entry.clone();
+                let at = concrete
+                    .get_mut("at")
+                    .ok_or_else(|| anyhow::anyhow!("Repeat block entry is missing 'at'"))?;
+                shift_tick_spec(at, iteration_start)?;
+                expanded.push(concrete);
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+/// Shift a tick spec (single tick, tick list or every-N spec) by an offset
+fn shift_tick_spec(at: &mut Value, offset: u64) -> Result<()> {
+    let shift = |tick: &mut Value| match tick.as_u64() {
+        Some(t) => {
+            *tick = Value::from(t + offset);
+            Ok(())
+        }
+        None => anyhow::bail!("Tick must be a non-negative number, got {}", tick),
+    };
+    match at {
+        Value::Array(ticks) => ticks.iter_mut().try_for_each(shift),
+        Value::Object(every) => ["start", "end"]
+            .iter()
+            .filter_map(|key| every.get_mut(*key))
+            .try_for_each(shift),
+        tick => shift(tick),
+    }
+}
+
+/// Expand `"at": {"start": S, "step": N, "end": E}` into the tick list S, S+N, ... up to E
+/// An assert with `values` is then split into one assert per tick, the i-th checking `values[i]`.
+fn expand_every_ticks(value: &mut Value) -> Result<()> {
+    let Some(timeline) = value.get_mut("timeline").and_then(Value::as_array_mut) else {
+        return Ok(());
+    };
+
+    for entry in timeline.iter_mut() {
+        let Some(at) = entry.get_mut("at") else {
+            continue;
+        };
+        let Some(every) = at.as_object() else {
+            continue;
+        };
+        let field = |key: &str| {
+            every
+                .get(key)
+                .and_then(Value::as_u64)
+                .ok_or_else(|| anyhow::anyhow!("Repeating tick spec needs a numeric '{}'", key))
+        };
+        let ticks = every_ticks(field("start")?, field("step")?, field("end")?)?;
+        *at = Value::from(ticks);
+    }
+
+    let entries = std::mem::take(timeline);
+    for entry in entries {
+        if entry.get("values").is_some() {
+            timeline.extend(pair_assert_values(entry)?);
+        } else {
+            timeline.push(entry);
+        }
+    }
+    Ok(())
+}
+
+/// Split an assert with `values` into one assert per tick of its `at`
+/// The i-th tick checks every position against `values[i]`, so the checks leave out `is`.
+fn pair_assert_values(mut entry: Value) -> Result<Vec<Value>> {
+    if entry.get("do").and_then(Value::as_str) != Some("assert") {
+        bail!("`values` is only supported on assert actions");
+    }
+    let values = entry
+        .as_object_mut()
+        .and_then(|object| object.remove("values"))
+        .and_then(|values| values.as_array().cloned())
+        .context("`values` must be a list of blocks, one per tick")?;
+    let ticks: Vec<u64> = match entry.get("at") {
+        Some(Value::Array(ticks)) => ticks.iter().filter_map(Value::as_u64).collect(),
+        Some(tick) => tick.as_u64().into_iter().collect(),
+        None => Vec::new(),
+    };
+    if ticks.len() != values.len() {
+        bail!(
+            "Assert has {} values for {} ticks; give one value per tick",
+            values.len(),
+            ticks.len()
+        );
+    }
+    let checks = entry
+        .get("checks")
+        .and_then(Value::as_array)
+        .context("Assert with `values` needs `checks`")?;
+    if checks.iter().any(|check| check.get("is").is_some()) {
+        bail!("Checks of an assert with `values` take their block from `values`, not `is`");
+    }
+
+    let mut occurrences = Vec::with_capacity(ticks.len());
+    for (tick, block) in ticks.into_iter().zip(values) {
+        let mut occurrence = entry.clone();
+        occurrence["at"] = Value::from(tick);
+        if let Some(checks) = occurrence.get_mut("checks").and_then(Value::as_array_mut) {
+            for check in checks.iter_mut().filter_map(Value::as_object_mut) {
+                check.insert("is".to_string(), block.clone());
+            }
+        }
+        occurrences.push(occurrence);
+    }
+    Ok(occurrences)
+}
+
+/// Ticks matched by an every-N spec: start, start + step, ... while <= end
+fn every_ticks(start: u64, step: u64, end: u64) -> Result<Vec<u64>> {
+    if step == 0 {
+        anyhow::bail!("Repeating tick spec needs a step of at least 1");
+    }
+    if start > end {
+        anyhow::bail!(
+            "Repeating tick spec starts at {} after its end {}",
+            start,
+            end
+        );
+    }
+    Ok((start..=end).step_by(step as usize).collect())
+}
+
+/// Expand `{"from": A, "to": B}` breakpoint entries into every tick from A to B inclusive
+/// Returns the ranges as declared, since the expanded ticks no longer tell where one ends.
+fn expand_breakpoint_ranges(value: &mut Value) -> Result<Vec<(u32, u32)>> {
+    let mut ranges = Vec::new();
+    let Some(breakpoints) = value.get_mut("breakpoints").and_then(Value::as_array_mut) else {
+        return Ok(ranges);
+    };
+
+    let mut expanded = Vec::with_capacity(breakpoints.len());
+    for entry in breakpoints.drain(..) {
+        let Some(range) = entry.as_object() else {
+            expanded.push(entry);
+            continue;
+        };
+        let bound = |key: &str| {
+            range
+                .get(key)
+                .and_then(Value::as_u64)
+                .and_then(|tick| u32::try_from(tick).ok())
+                .ok_or_else(|| anyhow::anyhow!("Breakpoint range needs a numeric '{}'", key))
+        };
+        let (from, to) = (bound("from")?, bound("to")?);
+        if from > to {
+            anyhow::bail!("Breakpoint range from {} to {} is empty", from, to);
+        }
+        expanded.extend((from..=to).map(Value::from));
+        ranges.push((from, to));
+    }
+    *breakpoints = expanded;
+    Ok(ranges)
+}
+
+/// Parse a dotted version ("0.1", "1.2.3") into [major, minor, patch]
+fn parse_version(version: &str) -> Option<[u64; 3]> {
+    let mut parts = [0u64; 3];
+    let mut count = 0;
+    for part in version.trim().trim_start_matches('v').split('.') {
+        if count == parts.len() {
+            return None;
+        }
+        parts[count] = part.parse().ok()?;
+        count += 1;
+    }
+    Some(parts)
+}
+
+/// Compare a test's declared schema version against the supported one
+/// Errors if the test needs a newer binary; Ok(true) if it was written for an older schema
+fn check_version(declared: &str, supported: &str) -> Result<bool> {
+    let Some(declared_parts) = parse_version(declared) else {
+        anyhow::bail!("Invalid flintVersion '{}'", declared);
+    };
+    let supported_parts =
+        parse_version(supported).expect("CRATE_SCHEMA_VERSION must be a valid version");
+
+    if declared_parts > supported_parts {
+        anyhow::bail!(
+            "Test requires flintVersion {} but this FlintMC supports up to {}. Please upgrade FlintMC.",
+            declared,
+            supported
+        );
+    }
+    Ok(declared_parts < supported_parts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_json_comments() {
+        let text = "{\n  // note\n  \"url\": \"http://a/*b*/\", /* two\n lines */ \"n\": 1\n}";
+        let stripped = strip_json_comments(text);
+        assert_eq!(stripped.lines().count(), text.lines().count());
+        let value: Value = serde_json::from_str(&stripped).unwrap();
+        assert_eq!(value, serde_json::json!({ "url": "http://a/*b*/", "n": 1 }));
+        assert_eq!(strip_json_comments(r#""a\"//b""#), r#""a\"//b""#);
+    }
+
+    #[test]
+    fn test_load_test_with_comments() {
+        let path = std::env::temp_dir().join(format!("flint-comments-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{
+  "$schema": "./flint-test.schema.json",
+  // Lever next to a lamp
+  "name": "commented_lamp",
+  "_comment": "checks the lamp turns on",
+  "timeline": [
+    { "at": 0, "do": "place", "pos": [0, 0, 0], "block": "minecraft:stone", "_comment": "base" } /* tick 0 */
+  ]
+}"#,
+        )
+        .unwrap();
+        let test = load_test(&path);
+        std::fs::remove_file(&path).unwrap();
+        let (test, _) = test.unwrap();
+        assert_eq!(test.name, "commented_lamp");
+        assert_eq!(test.timeline.len(), 1);
+    }
+
+    #[test]
+    fn test_is_yaml() {
+        assert!(is_yaml(Path::new("tests/fence.yaml")));
+        assert!(is_yaml(Path::new("tests/fence.yml")));
+        assert!(!is_yaml(Path::new("tests/fence.json")));
+        assert!(!is_yaml(Path::new("tests/yaml")));
+    }
+
+    #[test]
+    fn test_expand_breakpoint_ranges() {
+        let mut value = serde_json::json!({ "breakpoints": [1, { "from": 10, "to": 13 }, 20] });
+        assert_eq!(
+            expand_breakpoint_ranges(&mut value).unwrap(),
+            vec![(10, 13)]
+        );
+        assert_eq!(
+            value["breakpoints"],
+            serde_json::json!([1, 10, 11, 12, 13, 20])
+        );
+
+        // Adjacent single breakpoints stay single breakpoints
+        let mut adjacent = serde_json::json!({ "breakpoints": [10, 11] });
+        assert!(expand_breakpoint_ranges(&mut adjacent).unwrap().is_empty());
+
+        let mut reversed = serde_json::json!({ "breakpoints": [{ "from": 5, "to": 2 }] });
+        assert!(expand_breakpoint_ranges(&mut reversed).is_err());
+    }
+
+    #[test]
+    fn test_every_ticks() {
+        assert_eq!(every_ticks(10, 10, 40).unwrap(), vec![10, 20, 30, 40]);
+        assert_eq!(every_ticks(10, 10, 45).unwrap(), vec![10, 20, 30, 40]);
+        assert_eq!(every_ticks(5, 3, 5).unwrap(), vec![5]);
+        assert!(every_ticks(1, 0, 10).is_err());
+        assert!(every_ticks(10, 1, 5).is_err());
+
+        let mut value = serde_json::json!({
+            "timeline": [{ "at": { "start": 2, "step": 2, "end": 6 }, "do": "remove", "pos": [0, 0, 0] }]
+        });
+        expand_every_ticks(&mut value).unwrap();
+        assert_eq!(value["timeline"][0]["at"], serde_json::json!([2, 4, 6]));
+    }
+
+    #[test]
+    fn test_every_assert_values() {
+        // An observer pulses for one tick: unpowered, powered, unpowered
+        let mut value = serde_json::json!({
+            "timeline": [{
+                "at": { "start": 10, "step": 1, "end": 12 }, "do": "assert",
+                "checks": [{ "pos": [0, 64, 0] }],
+                "values": [
+                    "minecraft:observer[powered=false]",
+                    "minecraft:observer[powered=true]",
+                    "minecraft:observer[powered=false]"
+                ]
+            }]
+        });
+        expand_every_ticks(&mut value).unwrap();
+        let observer = |tick: u64, powered: bool| {
+            serde_json::json!({
+                "at": tick, "do": "assert",
+                "checks": [{ "pos": [0, 64, 0], "is": format!("minecraft:observer[powered={}]", powered) }]
+            })
+        };
+        assert_eq!(
+            value["timeline"],
+            serde_json::json!([observer(10, false), observer(11, true), observer(12, false)])
+        );
+
+        let mut short = serde_json::json!({
+            "timeline": [{ "at": { "start": 0, "step": 1, "end": 2 }, "do": "assert",
+                           "checks": [{ "pos": [0, 64, 0] }], "values": ["minecraft:stone"] }]
+        });
+        assert!(expand_every_ticks(&mut short).is_err());
+    }
+
+    #[test]
+    fn test_expand_repeat_blocks() {
+        let entries = vec![serde_json::json!({
+            "repeat": {
+                "start": 10,
+                "count": 3,
+                "period": 3,
+                "timeline": [
+                    { "at": 0, "do": "place", "pos": [0, 0, 0], "block": "minecraft:redstone_block" },
+                    { "at": 3, "do": "remove", "pos": [0, 0, 0] }
+                ]
+            }
+        })];
+        let expanded = expand_repeat_blocks(entries).unwrap();
+        let ticks: Vec<(u64, &str)> = expanded
+            .iter()
+            .map(|e| (e["at"].as_u64().unwrap(), e["do"].as_str().unwrap()))
+            .collect();
+        // The remove ending one iteration shares a tick with the next place, and runs first
+        assert_eq!(
+            ticks,
+            vec![
+                (10, "place"),
+                (13, "remove"),
+                (13, "place"),
+                (16, "remove"),
+                (16, "place"),
+                (19, "remove"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_relative_positions() {
+        let mut value = serde_json::json!({
+            "timeline": [
+                { "at": 0, "do": "place", "pos": { "relative_to": [1, 0, 0], "dir": "north", "dist": 2 } },
+                { "at": 1, "do": "assert", "checks": [
+                    { "pos": { "relative_to": [1, 0, 0], "dir": "UP" }, "is": "minecraft:air" }
+                ] },
+                { "at": 2, "do": "remove", "pos": {
+                    "relative_to": { "relative_to": [0, 0, 0], "dir": "east", "dist": 3 },
+                    "dir": "down"
+                } }
+            ]
+        });
+        resolve_relative_positions(&mut value).unwrap();
+        assert_eq!(value["timeline"][0]["pos"], serde_json::json!([1, 0, -2]));
+        assert_eq!(
+            value["timeline"][1]["checks"][0]["pos"],
+            serde_json::json!([1, 1, 0])
+        );
+        assert_eq!(value["timeline"][2]["pos"], serde_json::json!([3, -1, 0]));
+
+        let mut bad = serde_json::json!({ "pos": { "relative_to": [0, 0, 0], "dir": "left" } });
+        assert!(resolve_relative_positions(&mut bad).is_err());
+    }
+
+    #[test]
+    fn test_take_options() {
+        let mut value = serde_json::json!({ "name": "portal", "dimension": "the_nether" });
+        let options = take_options(&mut value).unwrap();
+        assert_eq!(options.dimension, Dimension::TheNether);
+        assert!(value.get("dimension").is_none());
+
+        let mut plain = serde_json::json!({ "name": "fence" });
+        assert_eq!(
+            take_options(&mut plain).unwrap().dimension,
+            Dimension::Overworld
+        );
+
+        let mut alias = serde_json::json!({ "dimension": "minecraft:the_end" });
+        assert_eq!(
+            take_options(&mut alias).unwrap().dimension,
+            Dimension::TheEnd
+        );
+
+        let mut hooks = serde_json::json!({ "before": ["weather clear"], "after": [] });
+        let options = take_options(&mut hooks).unwrap();
+        assert_eq!(options.before, vec!["weather clear".to_string()]);
+        assert!(options.after.is_empty());
+        assert_eq!(hooks, serde_json::json!({}));
+
+        let mut warmup = serde_json::json!({ "setup": { "warmup_ticks": 20, "cleanup": {} } });
+        assert_eq!(take_options(&mut warmup).unwrap().warmup_ticks, 20);
+        assert_eq!(warmup["setup"], serde_json::json!({ "cleanup": {} }));
+    }
+
+    #[test]
+    fn test_take_assert_windows() {
+        let mut value = serde_json::json!({
+            "timeline": [
+                { "at": [2, 6], "do": "assert", "within": 3,
+                  "checks": [{ "pos": [1, 64, 0], "is": "minecraft:redstone_lamp" }] },
+                { "at": 8, "do": "assert",
+                  "checks": [{ "pos": [0, 64, 0], "is": "minecraft:air" }] }
+            ]
+        });
+        let options = take_options(&mut value).unwrap();
+        assert_eq!(options.assert_windows, HashMap::from([(0, 3)]));
+        assert!(value["timeline"][0].get("within").is_none());
+
+        // Two asserts of the same block at the same tick keep their own windows
+        let mut shared = serde_json::json!({
+            "timeline": [
+                { "at": 4, "do": "assert",
+                  "checks": [{ "pos": [0, 64, 0], "is": "minecraft:piston" }] },
+                { "at": 4, "do": "assert", "within": 5,
+                  "checks": [{ "pos": [0, 64, 0], "is": "minecraft:piston" }] }
+            ]
+        });
+        let options = take_options(&mut shared).unwrap();
+        assert_eq!(options.assert_windows, HashMap::from([(1, 5)]));
+
+        let mut place = serde_json::json!({
+            "timeline": [{ "at": 0, "do": "place", "within": 2, "pos": [0, 64, 0], "block": "minecraft:stone" }]
+        });
+        assert!(take_options(&mut place).is_err());
+    }
+
+    #[test]
+    fn test_take_check_flags() {
+        let mut value = serde_json::json!({
+            "settles_fast": true,
+            "expect_failure": true,
+            "timeline": [
+                { "at": [3, 5], "do": "assert", "checks": [
+                    { "pos": [0, 64, 0], "is": "minecraft:lava", "negate": true },
+                    { "pos": [1, 64, 0], "is": "minecraft:oak_stairs[facing=north]", "strict_properties": true }
+                ] }
+            ]
+        });
+        let options = take_options(&mut value).unwrap();
+        assert_eq!(options.negated_checks, HashSet::from([(0, [0, 64, 0])]));
+        assert_eq!(options.strict_checks, HashSet::from([(0, [1, 64, 0])]));
+
+        // The same block asserted twice at one tick, once negated: only that entry is flagged
+        let mut shared = serde_json::json!({
+            "timeline": [
+                { "at": 2, "do": "assert",
+                  "checks": [{ "pos": [0, 64, 0], "is": "minecraft:piston" }] },
+                { "at": 2, "do": "assert",
+                  "checks": [{ "pos": [0, 64, 0], "is": "minecraft:sticky_piston", "negate": true }] }
+            ]
+        });
+        let options = take_options(&mut shared).unwrap();
+        assert_eq!(options.negated_checks, HashSet::from([(1, [0, 64, 0])]));
+        assert!(options.settles_fast);
+        assert!(options.expect_failure);
+        assert!(value.get("settles_fast").is_none());
+        assert!(value["timeline"][0]["checks"][0].get("negate").is_none());
+        assert!(
+            value["timeline"][0]["checks"][1]
+                .get("strict_properties")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_expand_region_asserts() {
+        let mut value = serde_json::json!({
+            "timeline": [
+                { "at": 5, "do": "assert_region", "region": [[1, 64, 0], [0, 65, 1]],
+                  "palette": { "s": "minecraft:stone", ".": "minecraft:air" },
+                  "layers": [["ss", "s."], ["..", ".s"]], "within": 2 }
+            ]
+        });
+        expand_region_asserts(&mut value).unwrap();
+        let entry = &value["timeline"][0];
+        assert_eq!(entry["do"], "assert");
+        assert_eq!(entry["within"], 2);
+        assert!(entry.get("palette").is_none());
+        let checks = entry["checks"].as_array().unwrap();
+        assert_eq!(checks.len(), 8);
+        assert_eq!(
+            checks[3],
+            serde_json::json!({ "pos": [1, 64, 1], "is": "minecraft:air" })
+        );
+        assert_eq!(
+            checks[7],
+            serde_json::json!({ "pos": [1, 65, 1], "is": "minecraft:stone" })
+        );
+
+        let mut short = serde_json::json!({
+            "timeline": [{ "at": 0, "do": "assert_region", "region": [[0, 64, 0], [1, 64, 0]],
+                           "palette": { "s": "minecraft:stone" }, "layers": [["s"]] }]
+        });
+        assert!(expand_region_asserts(&mut short).is_err());
+    }
+
+    #[test]
+    fn test_take_extra_checks() {
+        let mut value = serde_json::json!({
+            "timeline": [
+                { "at": 0, "do": "place", "pos": [0, 64, 0], "block": "minecraft:stone" },
+                { "at": [10, 20], "do": "assert_entities", "region": [[0, 64, 0], [2, 66, 2]],
+                  "entity": "minecraft:item", "count": 3 },
+                { "at": 20, "do": "assert_container", "pos": [1, 64, 1], "slot": 0,
+                  "item": "minecraft:diamond", "count": 2 }
+            ]
+        });
+        let options = take_options(&mut value).unwrap();
+        assert_eq!(options.extra_checks.len(), 2);
+        assert!(matches!(
+            options.extra_checks[&1],
+            ExtraCheck::AssertEntities(_)
+        ));
+        assert_eq!(
+            options.extra_checks[&2],
+            ExtraCheck::AssertContainer(ContainerCheck {
+                pos: [1, 64, 1],
+                slot: 0,
+                item: "minecraft:diamond".to_string(),
+                count: 2,
+            })
+        );
+        // Each check gets a placeholder in its own place, so entry indices don't shift
+        assert_eq!(value["timeline"].as_array().unwrap().len(), 3);
+        assert_eq!(
+            value["timeline"][1],
+            serde_json::json!({ "at": [10, 20], "do": "assert", "checks": [] })
+        );
+        assert_eq!(
+            value["timeline"][2],
+            serde_json::json!({ "at": [20], "do": "assert", "checks": [] })
+        );
+
+        let mut missing = serde_json::json!({
+            "timeline": [{ "at": 1, "do": "assert_entities", "region": [[0, 0, 0], [1, 1, 1]] }]
+        });
+        assert!(take_options(&mut missing).is_err());
+
+        let mut structure = serde_json::json!({
+            "timeline": [{ "at": 4, "do": "assert_structure", "pos": [0, 64, 0],
+                           "structure": "mypack:door", "size": [3, 3, 1] }]
+        });
+        let options = take_options(&mut structure).unwrap();
+        assert_eq!(
+            options.extra_checks[&0],
+            ExtraCheck::AssertStructure(StructureCheck {
+                pos: [0, 64, 0],
+                structure: "mypack:door".to_string(),
+                size: [3, 3, 1],
+            })
+        );
+
+        let mut environment = serde_json::json!({
+            "timeline": [
+                { "at": 2, "do": "assert_light", "pos": [0, 64, 0], "max": 7 },
+                { "at": 2, "do": "assert_biome", "pos": [0, 64, 0], "biome": "minecraft:plains" },
+                { "at": 2, "do": "assert_fill", "region": [[2, 64, 2], [0, 64, 0]], "block": "minecraft:stone" }
+            ]
+        });
+        let options = take_options(&mut environment).unwrap();
+        assert_eq!(
+            options.extra_checks,
+            HashMap::from([
+                (
+                    0,
+                    ExtraCheck::AssertLight(LightCheck {
+                        pos: [0, 64, 0],
+                        min: 0,
+                        max: 7,
+                    })
+                ),
+                (
+                    1,
+                    ExtraCheck::AssertBiome(BiomeCheck {
+                        pos: [0, 64, 0],
+                        biome: "minecraft:plains".to_string(),
+                    })
+                ),
+                (
+                    2,
+                    ExtraCheck::AssertFill(FillCheck {
+                        region: [[2, 64, 2], [0, 64, 0]],
+                        block: "minecraft:stone".to_string(),
+                    })
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_options_resolve_through_aggregate() {
+        use flint_core::timeline::TimelineAggregate;
+
+        let path =
+            std::env::temp_dir().join(format!("flint-aggregate-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{
+  "name": "aggregate_options",
+  "timeline": [
+    { "at": 0, "do": "place", "pos": [0, 0, 0], "block": "minecraft:chest", "nbt": "{Items:[]}" },
+    { "at": 4, "do": "assert", "checks": [{ "pos": [0, 0, 0], "is": "minecraft:chest" }] },
+    { "at": 4, "do": "assert", "within": 3, "checks": [{ "pos": [0, 0, 0], "is": "minecraft:chest" }] },
+    { "at": 4, "do": "assert", "checks": [{ "pos": [1, 0, 0], "is": "minecraft:lava", "negate": true }] },
+    { "at": 4, "do": "assert_container", "pos": [0, 0, 0], "slot": 0, "item": "minecraft:diamond", "count": 1 }
+  ]
+}"#,
+        )
+        .unwrap();
+        let loaded = load_test(&path);
+        std::fs::remove_file(&path).unwrap();
+        let (test, options) = loaded.unwrap();
+
+        // Look entries up the way the executor does: through the aggregate's borrowed entries
+        let tests_with_offsets = [(test, [0, 0, 0])];
+        let aggregate = TimelineAggregate::from_tests(&tests_with_offsets);
+        let test = &tests_with_offsets[0].0;
+        let mut resolved = Vec::new();
+        for entries in aggregate.timeline.values() {
+            for (_, entry, _) in entries {
+                let entry_idx =
+                    entry_index(test, entry).expect("aggregate entries are the test's own");
+                match entry_idx {
+                    0 => assert_eq!(options.block_nbt_at(test, entry), Some("{Items:[]}")),
+                    1 => assert_eq!(options.assert_window(test, entry), None),
+                    2 => assert_eq!(options.assert_window(test, entry), Some(3)),
+                    3 => assert_eq!(options.check_flags(test, entry).negated, vec![[1, 0, 0]]),
+                    4 => assert!(matches!(
+                        options.extra_checks_at(test, entry),
+                        Some([ExtraCheck::AssertContainer(_)])
+                    )),
+                    _ => unreachable!(),
+                }
+                resolved.push(entry_idx);
+            }
+        }
+        resolved.sort();
+        assert_eq!(resolved, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("0.1"), Some([0, 1, 0]));
+        assert_eq!(parse_version("1.2.3"), Some([1, 2, 3]));
+        assert_eq!(parse_version("v2"), Some([2, 0, 0]));
+        assert_eq!(parse_version("1.2.3.4"), None);
+        assert_eq!(parse_version("one"), None);
+    }
+
+    #[test]
+    fn test_check_version() {
+        assert!(!check_version("0.1", "0.1").unwrap());
+        assert!(check_version("0.0.9", "0.1").unwrap());
+        assert!(check_version("0.2", "0.1").is_err());
+        assert!(check_version("1.0", "0.1").is_err());
+        assert!(check_version("latest", "0.1").is_err());
+    }
+}
@@ -0,0 +1,337 @@
+//! Test file linting (`--validate`) - checks invariants flint-core's parser doesn't, without a server
+
+use crate::executor::{BoundingBox, apply_offset};
+use crate::spec::TestOptions;
+use flint_core::test_spec::{ActionType, TestSpec};
+use flint_core::timeline::TimelineAggregate;
+use std::collections::{BTreeSet, HashMap};
+
+/// Timelines longer than this (one in-game day) are probably a typo
+const MAX_REASONABLE_TICK: u32 = 24_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// One finding about a test file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Issue {
+    fn error(message: String) -> Self {
+        Self {
+            severity: Severity::Error,
+            message,
+        }
+    }
+
+    fn warning(message: String) -> Self {
+        Self {
+            severity: Severity::Warning,
+            message,
+        }
+    }
+}
+
+/// Every position a test writes or asserts, in local coordinates (fills as their two corners)
+pub fn touched_positions(test: &TestSpec) -> Vec<[i32; 3]> {
+    test.timeline
+        .iter()
+        .flat_map(|entry| match &entry.action_type {
+            ActionType::Place { pos, .. } | ActionType::Remove { pos } => vec![*pos],
+            ActionType::PlaceEach { blocks } => blocks.iter().map(|b| b.pos).collect(),
+            ActionType::Fill { region, .. } => region.to_vec(),
+            ActionType::Assert { checks } => checks.iter().map(|c| c.pos).collect(),
+        })
+        .collect()
+}
+
+/// Box around every position a test touches, or None for an empty timeline
+pub fn touched_bounds(test: &TestSpec) -> Option<BoundingBox> {
+    let mut bounds = BoundingBox::new();
+    for pos in touched_positions(test) {
+        bounds.expand(pos);
+    }
+    bounds.is_valid().then_some(bounds)
+}
+
+/// Describe positions outside the declared cleanup region, or None if it covers everything
+/// Positions are reported in world coordinates for the given grid offset.
+pub fn cleanup_escape(test: &TestSpec, offset: [i32; 3]) -> Option<String> {
+    test.setup.as_ref()?;
+    let region = BoundingBox::from_region(test.cleanup_region());
+    let touched = touched_bounds(test)?;
+    if region.contains(&touched) {
+        return None;
+    }
+    let outside: BTreeSet<[i32; 3]> = touched_positions(test)
+        .into_iter()
+        .filter(|pos| !region.contains(&BoundingBox::from_region([*pos, *pos])))
+        .collect();
+    let listed: Vec<String> = outside
+        .iter()
+        .take(5)
+        .map(|pos| apply_offset(*pos, offset))
+        .map(|pos| format!("({}, {}, {})", pos[0], pos[1], pos[2]))
+        .collect();
+    let more = if outside.len() > listed.len() {
+        format!(" and {} more", outside.len() - listed.len())
+    } else {
+        String::new()
+    };
+    Some(format!(
+        "touched blocks span {:?}..{:?}, outside cleanup region {:?}..{:?}: {}{}",
+        apply_offset(touched.min, offset),
+        apply_offset(touched.max, offset),
+        apply_offset(region.min, offset),
+        apply_offset(region.max, offset),
+        listed.join(", "),
+        more
+    ))
+}
+
+/// Block ids a test uses (placed, filled or asserted)
+fn block_ids(test: &TestSpec) -> BTreeSet<String> {
+    test.timeline
+        .iter()
+        .flat_map(|entry| match &entry.action_type {
+            ActionType::Place { block, .. } => vec![block.id.clone()],
+            ActionType::PlaceEach { blocks } => blocks.iter().map(|b| b.block.id.clone()).collect(),
+            ActionType::Fill { with, .. } => vec![with.id.clone()],
+            ActionType::Remove { .. } => Vec::new(),
+            ActionType::Assert { checks } => checks.iter().map(|c| c.is.id.clone()).collect(),
+        })
+        .collect()
+}
+
+/// Block ids a test places or fills
+fn placed_block_ids(test: &TestSpec) -> BTreeSet<String> {
+    test.timeline
+        .iter()
+        .flat_map(|entry| match &entry.action_type {
+            ActionType::Place { block, .. } => vec![block.id.clone()],
+            ActionType::PlaceEach { blocks } => blocks.iter().map(|b| b.block.id.clone()).collect(),
+            ActionType::Fill { with, .. } => vec![with.id.clone()],
+            ActionType::Remove { .. } | ActionType::Assert { .. } => Vec::new(),
+        })
+        .collect()
+}
+
+/// Lint one loaded test
+/// Blocks outside the cleanup region are a warning, or an error when `strict`.
+pub fn lint(test: &TestSpec, options: &TestOptions, strict: bool) -> Vec<Issue> {
+    let mut issues = Vec::new();
+
+    if test.timeline.is_empty() {
+        issues.push(Issue::error("timeline is empty".to_string()));
+        return issues;
+    }
+    let has_asserts = test
+        .timeline
+        .iter()
+        .any(|entry| matches!(entry.action_type, ActionType::Assert { .. }));
+    if !has_asserts && options.snapshot_region.is_none() {
+        issues.push(Issue::warning(
+            "no assertions, the test can't fail".to_string(),
+        ));
+    }
+
+    // Cleanup coverage
+    if test.setup.is_none() {
+        issues.push(Issue::warning(
+            "no setup.cleanup.region, blocks may leak into neighboring tests".to_string(),
+        ));
+    } else if let Some(escape) = cleanup_escape(test, [0, 0, 0]) {
+        issues.push(if strict {
+            Issue::error(escape)
+        } else {
+            Issue::warning(escape)
+        });
+    }
+
+    // Coordinates the dimension can hold
+    let (min_y, max_y) = options.dimension.y_range();
+    let out_of_world: BTreeSet<[i32; 3]> = touched_positions(test)
+        .into_iter()
+        .filter(|pos| pos[1] < min_y || pos[1] > max_y)
+        .collect();
+    if let Some(pos) = out_of_world.first() {
+        issues.push(Issue::error(format!(
+            "{} position(s) outside y {}..{} of {}, e.g. ({}, {}, {})",
+            out_of_world.len(),
+            min_y,
+            max_y,
+            options.dimension.id(),
+            pos[0],
+            pos[1],
+            pos[2]
+        )));
+    }
+
+    for id in block_ids(test) {
+        let (prefix, name) = match id.strip_prefix('#') {
+            Some(tag) => ("#", tag),
+            None => ("", id.as_str()),
+        };
+        if !name.contains(':') {
+            issues.push(Issue::error(format!(
+                "block id '{}' is not namespaced (use '{}minecraft:{}')",
+                id, prefix, name
+            )));
+        }
+    }
+    for id in placed_block_ids(test) {
+        if id.starts_with('#') {
+            issues.push(Issue::error(format!(
+                "block tag '{}' can only be asserted, not placed",
+                id
+            )));
+        }
+    }
+
+    // Tick sanity
+    let max_tick = test.max_tick();
+    if max_tick > MAX_REASONABLE_TICK {
+        issues.push(Issue::warning(format!(
+            "timeline runs to tick {}, longer than an in-game day",
+            max_tick
+        )));
+    }
+    for breakpoint in &test.breakpoints {
+        if *breakpoint > max_tick {
+            issues.push(Issue::warning(format!(
+                "breakpoint at tick {} is after the last action (tick {})",
+                breakpoint, max_tick
+            )));
+        }
+    }
+
+    // Duplicate writes or asserts of one position in the same tick
+    let aggregate = TimelineAggregate::from_tests(&[(test.clone(), [0, 0, 0])]);
+    let mut ticks: Vec<&u32> = aggregate.timeline.keys().collect();
+    ticks.sort();
+    for tick in ticks {
+        let mut writes: HashMap<[i32; 3], usize> = HashMap::new();
+        let mut asserts: HashMap<[i32; 3], usize> = HashMap::new();
+        for (_, entry, _) in &aggregate.timeline[tick] {
+            match &entry.action_type {
+                ActionType::Place { pos, .. } | ActionType::Remove { pos } => {
+                    *writes.entry(*pos).or_default() += 1;
+                }
+                ActionType::PlaceEach { blocks } => {
+                    for block in blocks {
+                        *writes.entry(block.pos).or_default() += 1;
+                    }
+                }
+                ActionType::Fill { .. } => {}
+                ActionType::Assert { checks } => {
+                    for check in checks {
+                        *asserts.entry(check.pos).or_default() += 1;
+                    }
+                }
+            }
+        }
+        let duplicated = |counts: HashMap<[i32; 3], usize>| -> BTreeSet<[i32; 3]> {
+            counts
+                .into_iter()
+                .filter(|(_, count)| *count > 1)
+                .map(|(pos, _)| pos)
+                .collect()
+        };
+        for pos in duplicated(writes) {
+            issues.push(Issue::warning(format!(
+                "tick {}: ({}, {}, {}) is set more than once",
+                tick, pos[0], pos[1], pos[2]
+            )));
+        }
+        for pos in duplicated(asserts) {
+            issues.push(Issue::warning(format!(
+                "tick {}: ({}, {}, {}) is asserted more than once",
+                tick, pos[0], pos[1], pos[2]
+            )));
+        }
+    }
+
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_from(json: serde_json::Value) -> TestSpec {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_lint_flags_cleanup_escape_and_ids() {
+        let test = test_from(serde_json::json!({
+            "name": "validate_escape",
+            "setup": { "cleanup": { "region": [[0, 64, 0], [2, 66, 2]] } },
+            "timeline": [
+                { "at": 0, "do": "place", "pos": [0, 64, 0], "block": "stone" },
+                { "at": 1, "do": "assert", "checks": [{ "pos": [5, 64, 0], "is": "minecraft:stone" }] }
+            ]
+        }));
+        let errors = |strict| -> Vec<String> {
+            lint(&test, &TestOptions::default(), strict)
+                .into_iter()
+                .filter(|issue| issue.severity == Severity::Error)
+                .map(|issue| issue.message)
+                .collect()
+        };
+        assert_eq!(errors(false).len(), 1);
+        assert!(errors(false)[0].contains("'stone'"));
+        let strict = errors(true);
+        assert_eq!(strict.len(), 2);
+        assert!(strict[0].contains("(5, 64, 0)"));
+
+        let escape = cleanup_escape(&test, [100, 0, 0]).unwrap();
+        assert!(escape.contains("(105, 64, 0)"));
+    }
+
+    #[test]
+    fn test_lint_block_tags() {
+        let test = test_from(serde_json::json!({
+            "name": "validate_tags",
+            "setup": { "cleanup": { "region": [[0, 64, 0], [2, 66, 2]] } },
+            "timeline": [
+                { "at": 0, "do": "place", "pos": [0, 64, 0], "block": "#minecraft:logs" },
+                { "at": 1, "do": "assert", "checks": [
+                    { "pos": [0, 64, 0], "is": "#minecraft:logs" },
+                    { "pos": [1, 64, 0], "is": "#wool" }
+                ] }
+            ]
+        }));
+        let errors: Vec<String> = lint(&test, &TestOptions::default(), false)
+            .into_iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .map(|issue| issue.message)
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                "block id '#wool' is not namespaced (use '#minecraft:wool')".to_string(),
+                "block tag '#minecraft:logs' can only be asserted, not placed".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_clean_test() {
+        let test = test_from(serde_json::json!({
+            "name": "validate_clean",
+            "setup": { "cleanup": { "region": [[0, 64, 0], [2, 66, 2]] } },
+            "timeline": [
+                { "at": 0, "do": "place", "pos": [0, 64, 0], "block": "minecraft:stone" },
+                { "at": 1, "do": "assert", "checks": [{ "pos": [0, 64, 0], "is": "minecraft:stone" }] }
+            ]
+        }));
+        assert!(lint(&test, &TestOptions::default(), true).is_empty());
+    }
+}